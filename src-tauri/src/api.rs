@@ -0,0 +1,352 @@
+//! An HTTP API for scripts and dashboards, served from this host while it is
+//! enabled. Every request but `GET /openapi.json` needs the configured token
+//! as `Authorization: Bearer <token>`. Errors are the same [`AppError`]s the
+//! frontend gets, with a status code to match.
+use crate::{
+    app::{
+        self,
+        groups::{self, Group, GroupState, GroupToggled},
+        scenes::{self, Scene},
+        settings, AppError, AppResult, AppState, Device, DeviceOutcome,
+    },
+    commands,
+    tplink::{error::TpError, models::PowerState, prelude::*},
+};
+use axum::{
+    extract::{Path, Request, State},
+    http::{header, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+    Json, Router,
+};
+use log::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    io,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::Mutex,
+};
+use tauri::{AppHandle, Manager};
+use tokio::sync::oneshot;
+use utoipa::{
+    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+    Modify, OpenApi, ToSchema,
+};
+
+/// Where the API listens and the token it expects
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ApiConfig {
+    pub enabled: bool,
+    /// Loopback by default, so only scripts on this host can reach it
+    pub address: IpAddr,
+    pub port: u16,
+    /// Generated when the API is enabled without one
+    pub token: String,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            port: 7878,
+            token: String::new(),
+        }
+    }
+}
+
+/// Save the API settings and apply them, generating a token if the API is
+/// enabled without one. Returns the settings as saved.
+pub async fn configure(app: &AppHandle, mut config: ApiConfig) -> AppResult<ApiConfig> {
+    if config.enabled && config.token.is_empty() {
+        config.token = generate_token()?;
+    }
+    settings::save_api_config(app, &config)?;
+    restart(app).await?;
+    Ok(config)
+}
+
+/// Start serving if the API is enabled, replacing any server that is running
+pub async fn restart(app: &AppHandle) -> AppResult<()> {
+    let config = settings::load_api_config(app)?;
+    let previous = app.state::<Mutex<AppState>>().lock().unwrap().api.take();
+    if let Some(previous) = previous {
+        previous.stop().await;
+    }
+    if !config.enabled || config.token.is_empty() {
+        return Ok(());
+    }
+
+    let addr = SocketAddr::new(config.address, config.port);
+    let server = ApiServer::start(addr, router(app.clone(), config.token))
+        .await
+        .map_err(|err| TpError::from(format!("Can't serve the API on {}: {}", addr, err)))?;
+    app.state::<Mutex<AppState>>().lock().unwrap().api = Some(server);
+    Ok(())
+}
+
+/// A running HTTP server, which holds its port until [`ApiServer::stop`]
+/// returns
+pub struct ApiServer {
+    shutdown: oneshot::Sender<()>,
+    served: tauri::async_runtime::JoinHandle<()>,
+}
+
+impl ApiServer {
+    /// Bind `addr` and serve `router` on it in the background
+    pub async fn start(addr: SocketAddr, router: Router) -> io::Result<Self> {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        let (shutdown, stopped) = oneshot::channel();
+        let served = tauri::async_runtime::spawn(async move {
+            info!("Serving the API on {}", addr);
+            let served = axum::serve(listener, router)
+                .with_graceful_shutdown(async {
+                    let _ = stopped.await;
+                })
+                .await;
+            if let Err(err) = served {
+                error!("The API server failed {:?}", err);
+            }
+        });
+        Ok(Self { shutdown, served })
+    }
+
+    /// Stop accepting connections and wait for the open ones to finish, so
+    /// the port can be bound again as soon as this returns
+    pub async fn stop(self) {
+        let _ = self.shutdown.send(());
+        if let Err(err) = self.served.await {
+            error!("The API server task failed {:?}", err);
+        }
+    }
+}
+
+/// 128 bits from the OS random source, as 32 hex digits
+fn generate_token() -> AppResult<String> {
+    let mut bytes = [0_u8; 16];
+    getrandom::fill(&mut bytes)
+        .map_err(|err| TpError::from(format!("Can't generate an API token: {}", err)))?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+fn router(app: AppHandle, token: String) -> Router {
+    Router::new()
+        .route("/devices", get(list_devices))
+        .route("/devices/{id}/toggle", post(toggle_device))
+        .route("/devices/{id}/brightness", put(set_brightness))
+        .route("/scenes", get(list_scenes))
+        .route("/scenes/{name}/apply", post(apply_scene))
+        .route("/groups", get(list_groups))
+        .route("/groups/{name}", get(group_state))
+        .route("/groups/{name}/toggle", post(toggle_group))
+        .route("/groups/{name}/state", put(set_group_state))
+        .route_layer(middleware::from_fn_with_state(token, authorize))
+        .route("/openapi.json", get(|| async { Json(ApiDoc::openapi()) }))
+        .with_state(app)
+}
+
+async fn authorize(State(token): State<String>, request: Request, next: Next) -> Response {
+    let presented = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    match presented {
+        Some(presented) if same(presented.as_bytes(), token.as_bytes()) => next.run(request).await,
+        _ => StatusCode::UNAUTHORIZED.into_response(),
+    }
+}
+
+/// Compare without returning early, so the time taken doesn't give the
+/// token away
+fn same(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            AppError::NotFound(_)
+            | AppError::SceneNotFound(_)
+            | AppError::GroupNotFound(_)
+            | AppError::AutomationNotFound(_) => StatusCode::NOT_FOUND,
+            AppError::InvalidAutomation(_) => StatusCode::BAD_REQUEST,
+            AppError::Tp(TpError::Unsupported(_) | TpError::InvalidArgument { .. }) => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            AppError::Tp(err) if err.is_unreachable() => StatusCode::GATEWAY_TIMEOUT,
+            AppError::Tp(_) => StatusCode::BAD_GATEWAY,
+            AppError::Store(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(self)).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct Toggled {
+    /// Whether the device is now on
+    on: bool,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct Brightness {
+    /// 1 to 100
+    brightness: u8,
+}
+
+/// Every device and outlet that answers, as it is now
+#[utoipa::path(get, path = "/devices", responses((status = 200, body = Vec<Device>)))]
+async fn list_devices(State(app): State<AppHandle>) -> Json<Vec<Device>> {
+    let addrs = app
+        .state::<Mutex<AppState>>()
+        .lock()
+        .unwrap()
+        .registry
+        .entries()
+        .map(|entry| (entry.id.clone(), entry.addr))
+        .collect::<HashMap<_, _>>();
+    let ids = addrs.keys().cloned().collect();
+
+    let mut devices = app::map_devices(&app, ids, |_, mut device| async move {
+        device.get_sysinfo().await
+    })
+    .await
+    .into_iter()
+    .filter_map(|(id, sysinfo)| Some((addrs[&id], sysinfo.ok()?)))
+    .flat_map(|(addr, sysinfo)| Device::all_from(addr, &sysinfo.into()))
+    .collect::<Vec<_>>();
+    devices.sort_by(|a, b| a.name.cmp(&b.name));
+    Json(devices)
+}
+
+#[utoipa::path(
+    post,
+    path = "/devices/{id}/toggle",
+    params(("id" = String, Path, description = "Device id, or an outlet's full id")),
+    responses((status = 200, body = Toggled), (status = 404))
+)]
+async fn toggle_device(
+    State(app): State<AppHandle>,
+    Path(id): Path<String>,
+) -> AppResult<Json<Toggled>> {
+    let on = commands::devices::toggle(id, app).await?;
+    Ok(Json(Toggled { on }))
+}
+
+/// Dim a dimmer or bulb without switching it on or off
+#[utoipa::path(
+    put,
+    path = "/devices/{id}/brightness",
+    params(("id" = String, Path, description = "Device id")),
+    request_body = Brightness,
+    responses((status = 204), (status = 404), (status = 422))
+)]
+async fn set_brightness(
+    State(app): State<AppHandle>,
+    Path(id): Path<String>,
+    Json(body): Json<Brightness>,
+) -> AppResult<StatusCode> {
+    commands::devices::set_brightness(id, body.brightness, app).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(get, path = "/scenes", responses((status = 200, body = Vec<Scene>)))]
+async fn list_scenes(State(app): State<AppHandle>) -> AppResult<Json<Vec<Scene>>> {
+    Ok(Json(settings::load_scenes(&app)?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/scenes/{name}/apply",
+    params(("name" = String, Path)),
+    responses((status = 200, body = Vec<DeviceOutcome>), (status = 404))
+)]
+async fn apply_scene(
+    State(app): State<AppHandle>,
+    Path(name): Path<String>,
+) -> AppResult<Json<Vec<DeviceOutcome>>> {
+    Ok(Json(scenes::apply(&app, &name).await?))
+}
+
+#[utoipa::path(get, path = "/groups", responses((status = 200, body = Vec<Group>)))]
+async fn list_groups(State(app): State<AppHandle>) -> AppResult<Json<Vec<Group>>> {
+    Ok(Json(settings::load_groups(&app)?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/groups/{name}",
+    params(("name" = String, Path)),
+    responses((status = 200, body = GroupState), (status = 404))
+)]
+async fn group_state(
+    State(app): State<AppHandle>,
+    Path(name): Path<String>,
+) -> AppResult<Json<GroupState>> {
+    Ok(Json(groups::state(&app, &name).await?))
+}
+
+/// Switch the group off if most of it is on, otherwise on
+#[utoipa::path(
+    post,
+    path = "/groups/{name}/toggle",
+    params(("name" = String, Path)),
+    responses((status = 200, body = GroupToggled), (status = 404))
+)]
+async fn toggle_group(
+    State(app): State<AppHandle>,
+    Path(name): Path<String>,
+) -> AppResult<Json<GroupToggled>> {
+    Ok(Json(groups::toggle(&app, &name).await?))
+}
+
+#[utoipa::path(
+    put,
+    path = "/groups/{name}/state",
+    params(("name" = String, Path)),
+    request_body = PowerState,
+    responses((status = 200, body = Vec<DeviceOutcome>), (status = 404))
+)]
+async fn set_group_state(
+    State(app): State<AppHandle>,
+    Path(name): Path<String>,
+    Json(state): Json<PowerState>,
+) -> AppResult<Json<Vec<DeviceOutcome>>> {
+    Ok(Json(groups::set(&app, &name, state).await?))
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Overhead"),
+    paths(
+        list_devices,
+        toggle_device,
+        set_brightness,
+        list_scenes,
+        apply_scene,
+        list_groups,
+        group_state,
+        toggle_group,
+        set_group_state,
+    ),
+    modifiers(&BearerAuth),
+    security(("token" = []))
+)]
+struct ApiDoc;
+
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        openapi
+            .components
+            .get_or_insert_with(Default::default)
+            .add_security_scheme(
+                "token",
+                SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+            );
+    }
+}
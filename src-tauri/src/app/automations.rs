@@ -0,0 +1,306 @@
+//! Automations that run on this host rather than on the devices: at cron
+//! times, at sunrise or sunset, or when a device is switched. Unlike device
+//! schedules, one automation can act on any number of devices, groups and
+//! scenes.
+use super::{groups, registry, scenes, settings, AppError, AppResult, AppState, DeviceOutcome};
+use crate::tplink::{models::PowerState, solar};
+use chrono::{DateTime, Days, Local, TimeDelta};
+use cron::Schedule;
+use log::*;
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr, sync::Mutex, time::Duration};
+use tauri::{AppHandle, Manager};
+
+/// How often time based triggers are checked
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(20);
+/// How many executions the history keeps
+pub const HISTORY_LIMIT: usize = 200;
+
+/// Serializes changes to the history, which is read and written as a whole
+static HISTORY: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Automation {
+    /// Unique among automations
+    pub name: String,
+    pub enabled: bool,
+    pub trigger: Trigger,
+    /// Run in order
+    pub actions: Vec<Action>,
+}
+
+/// What makes an automation run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Trigger {
+    /// A cron expression in local time, either the usual five fields or six
+    /// with seconds first
+    Cron { expression: String },
+    /// Minutes after sunrise or sunset, or before if negative, at the location
+    /// the devices report
+    Solar { event: SolarEvent, offset: i16 },
+    /// A device or outlet was switched on or off, by anything
+    Switched { id: String, on: bool },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum SolarEvent {
+    Sunrise,
+    Sunset,
+}
+
+/// Something an automation does
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Action {
+    /// Put a device or outlet in a state
+    Device { id: String, state: PowerState },
+    /// Put every member of a group in a state
+    Group { name: String, state: PowerState },
+    /// Recall a scene
+    Scene { name: String },
+}
+
+/// A record of an automation having run
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Execution {
+    pub automation: String,
+    /// Unix timestamp (seconds)
+    pub at: u64,
+    /// What triggered it, for display
+    pub reason: String,
+    pub outcomes: Vec<DeviceOutcome>,
+    /// Set if an action couldn't be started at all, such as a deleted scene
+    pub error: Option<AppError>,
+}
+
+impl Trigger {
+    /// The first time after `after` that this trigger fires. `location` is
+    /// (latitude, longitude) in degrees and is needed for solar triggers.
+    pub fn next_after(
+        &self,
+        after: DateTime<Local>,
+        location: Option<(f64, f64)>,
+    ) -> AppResult<Option<DateTime<Local>>> {
+        match self {
+            Trigger::Cron { expression } => Ok(schedule(expression)?.after(&after).next()),
+            Trigger::Solar { event, offset } => {
+                let Some((latitude, longitude)) = location else {
+                    return Ok(None);
+                };
+                // Tomorrow's event is the next one once today's has passed;
+                // near the poles there may be none for a while
+                let next = (0..3)
+                    .filter_map(|days| after.date_naive().checked_add_days(Days::new(days)))
+                    .filter_map(|date| solar::sun_times(date, latitude, longitude))
+                    .map(|times| match event {
+                        SolarEvent::Sunrise => times.sunrise,
+                        SolarEvent::Sunset => times.sunset,
+                    })
+                    .map(|time| time.with_timezone(&Local) + TimeDelta::minutes((*offset).into()))
+                    .find(|time| *time > after);
+                Ok(next)
+            }
+            Trigger::Switched { .. } => Ok(None),
+        }
+    }
+}
+
+impl fmt::Display for Trigger {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Trigger::Cron { expression } => write!(f, "cron {}", expression),
+            Trigger::Solar { event, offset } => {
+                let event = match event {
+                    SolarEvent::Sunrise => "sunrise",
+                    SolarEvent::Sunset => "sunset",
+                };
+                match offset {
+                    0 => f.write_str(event),
+                    offset => write!(f, "{} {:+} min", event, offset),
+                }
+            }
+            Trigger::Switched { id, on } => {
+                write!(f, "{} switched {}", id, if *on { "on" } else { "off" })
+            }
+        }
+    }
+}
+
+/// Parse a cron expression, allowing the usual five fields as well as the six
+/// or seven that start with seconds
+fn schedule(expression: &str) -> AppResult<Schedule> {
+    let expression = match expression.split_whitespace().count() {
+        5 => format!("0 {}", expression),
+        _ => expression.to_string(),
+    };
+    Schedule::from_str(&expression).map_err(|err| AppError::InvalidAutomation(err.to_string()))
+}
+
+pub fn validate(automation: &Automation) -> AppResult<()> {
+    if automation.name.trim().is_empty() {
+        return Err(AppError::InvalidAutomation("The name is empty".to_string()));
+    }
+    if let Trigger::Cron { expression } = &automation.trigger {
+        schedule(expression)?;
+    }
+    Ok(())
+}
+
+pub fn find(app: &AppHandle, name: &str) -> AppResult<Automation> {
+    settings::load_automations(app)?
+        .into_iter()
+        .find(|automation| automation.name == name)
+        .ok_or_else(|| AppError::AutomationNotFound(name.to_string()))
+}
+
+/// Save `automation`, replacing any automation with the same name
+pub fn save(app: &AppHandle, automation: Automation) -> AppResult<()> {
+    validate(&automation)?;
+    let mut automations = settings::load_automations(app)?;
+    match automations
+        .iter_mut()
+        .find(|saved| saved.name == automation.name)
+    {
+        Some(saved) => *saved = automation,
+        None => automations.push(automation),
+    }
+    settings::save_automations(app, &automations)
+}
+
+pub fn delete(app: &AppHandle, name: &str) -> AppResult<()> {
+    let mut automations = settings::load_automations(app)?;
+    automations.retain(|automation| automation.name != name);
+    settings::save_automations(app, &automations)
+}
+
+/// When `name` will next run on its own, if it has a time based trigger
+pub fn next_run(app: &AppHandle, name: &str) -> AppResult<Option<DateTime<Local>>> {
+    let automation = find(app, name)?;
+    if !automation.enabled {
+        return Ok(None);
+    }
+    automation.trigger.next_after(Local::now(), location(app))
+}
+
+/// Where the devices are, as the poller last saw them report it
+fn location(app: &AppHandle) -> Option<(f64, f64)> {
+    app.state::<Mutex<AppState>>().lock().unwrap().location
+}
+
+/// Run the actions of `automation` in order and record the execution in the
+/// history. An action that fails doesn't stop the ones after it.
+pub async fn run(app: &AppHandle, automation: &Automation, reason: String) -> Execution {
+    info!("Running {:?} ({})", automation.name, reason);
+    let mut execution = Execution {
+        automation: automation.name.clone(),
+        at: registry::now(),
+        reason,
+        outcomes: vec![],
+        error: None,
+    };
+
+    for action in &automation.actions {
+        let outcomes = match action {
+            Action::Device { id, state } => {
+                let state = *state;
+                let targets = vec![(id.clone(), id.clone())];
+                Ok(
+                    super::for_each_device(app, targets, move |_, mut device| async move {
+                        device.set_power_state(&state).await
+                    })
+                    .await,
+                )
+            }
+            Action::Group { name, state } => groups::set(app, name, *state).await,
+            Action::Scene { name } => scenes::apply(app, name).await,
+        };
+        match outcomes {
+            Ok(outcomes) => execution.outcomes.extend(outcomes),
+            Err(err) => {
+                warn!("{:?} could not {:?}: {}", automation.name, action, err);
+                execution.error.get_or_insert(err);
+            }
+        }
+    }
+
+    if let Err(err) = record(app, execution.clone()) {
+        error!("Failed to record automation history {:?}", err);
+    }
+    execution
+}
+
+fn record(app: &AppHandle, execution: Execution) -> AppResult<()> {
+    let _guard = HISTORY.lock().unwrap();
+    let mut history = settings::load_history(app)?;
+    history.insert(0, execution);
+    history.truncate(HISTORY_LIMIT);
+    settings::save_history(app, &history)
+}
+
+/// Start checking time based triggers in the background for as long as the
+/// app runs
+pub fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut last_check = Local::now();
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+            let now = Local::now();
+            run_due(&app, last_check, now);
+            last_check = now;
+        }
+    });
+}
+
+/// Start every enabled automation whose trigger fell in `(since, now]`
+fn run_due(app: &AppHandle, since: DateTime<Local>, now: DateTime<Local>) {
+    let automations = match settings::load_automations(app) {
+        Ok(automations) => automations,
+        Err(err) => return error!("Failed to load automations {:?}", err),
+    };
+
+    let location = location(app);
+    for automation in automations.into_iter().filter(|a| a.enabled) {
+        match automation.trigger.next_after(since, location) {
+            Ok(Some(time)) if time <= now => {
+                let reason = format!("{} at {}", automation.trigger, time.format("%H:%M"));
+                start(app, automation, reason);
+            }
+            Ok(_) => {}
+            Err(err) => warn!("Skipping {:?}: {}", automation.name, err),
+        }
+    }
+}
+
+/// Start every enabled automation waiting for the device or outlet `id` to be
+/// switched to `on`
+pub fn device_switched(app: &AppHandle, id: &str, on: bool) {
+    let automations = match settings::load_automations(app) {
+        Ok(automations) => automations,
+        Err(err) => return error!("Failed to load automations {:?}", err),
+    };
+
+    for automation in automations.into_iter().filter(|automation| {
+        automation.enabled
+            && automation.trigger
+                == Trigger::Switched {
+                    id: id.to_string(),
+                    on,
+                }
+    }) {
+        let reason = automation.trigger.to_string();
+        start(app, automation, reason);
+    }
+}
+
+fn start(app: &AppHandle, automation: Automation, reason: String) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        run(&app, &automation, reason).await;
+    });
+}
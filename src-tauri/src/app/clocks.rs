@@ -0,0 +1,117 @@
+//! Device schedules and timers run on the device's own clock, which drifts
+//! and is never corrected if the device can't reach TP-Link's servers
+use super::{registry::RegistryEntry, AppState};
+use crate::tplink::{
+    error::{TpError, TpResult},
+    models::DeviceTime,
+    prelude::*,
+    timezones,
+};
+use chrono::Utc;
+use log::*;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tokio::task::JoinSet;
+
+/// How far a device clock may be off, in seconds, before it is reported
+pub const MAX_DRIFT: i64 = 60;
+
+/// How a device clock compares to the host's
+#[derive(Debug, Clone, Default, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ClockReport {
+    pub id: String,
+    pub name: String,
+    pub time: Option<DeviceTime>,
+    /// The IANA zone the device's timezone index stands for
+    pub zone: Option<String>,
+    /// Seconds the device is ahead of the host, negative when behind. `None`
+    /// if the device reported a time that doesn't exist.
+    pub drift: Option<i64>,
+    /// The device has enabled schedule rules that will fire at the wrong time
+    pub schedule_affected: bool,
+    /// The clock was reset to the host's
+    pub synced: bool,
+    pub error: Option<TpError>,
+}
+
+impl ClockReport {
+    pub fn is_wrong(&self) -> bool {
+        self.drift.is_none_or(|drift| drift.abs() > MAX_DRIFT)
+    }
+}
+
+/// Compare the clock of every registered device that has one to the host's,
+/// and when `fix` is set, reset the ones that are off
+pub async fn check_clocks(app: &AppHandle, fix: bool) -> Vec<ClockReport> {
+    let entries = app
+        .state::<Mutex<AppState>>()
+        .lock()
+        .unwrap()
+        .registry
+        .entries()
+        .filter(|entry| entry.capabilities.is_none_or(|c| c.timesetting))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let mut checks = JoinSet::new();
+    for entry in entries {
+        checks.spawn(check_clock(entry, fix));
+    }
+
+    let mut reports = vec![];
+    while let Some(joined) = checks.join_next().await {
+        match joined {
+            // Devices that turn out to have no clock aren't worth reporting
+            Ok(Some(report)) => reports.push(report),
+            Ok(None) => {}
+            Err(err) => error!("Clock check failed {:?}", err),
+        }
+    }
+    reports.sort_by(|a, b| a.name.cmp(&b.name));
+    reports
+}
+
+async fn check_clock(entry: RegistryEntry, fix: bool) -> Option<ClockReport> {
+    let mut report = ClockReport {
+        id: entry.id.clone(),
+        name: entry.alias.clone(),
+        ..Default::default()
+    };
+
+    match read_clock(&entry, fix, &mut report).await {
+        Ok(()) => Some(report),
+        Err(TpError::ModuleNotSupported { .. }) => None,
+        Err(err) => Some(ClockReport {
+            error: Some(err.at(entry.addr)),
+            ..report
+        }),
+    }
+}
+
+async fn read_clock(entry: &RegistryEntry, fix: bool, report: &mut ClockReport) -> TpResult<()> {
+    let mut device = super::connect(entry, None)?;
+    let (time, timezone) = device.get_clock().await?;
+    report.time = Some(time);
+    report.zone = timezones::zone_of(timezone.index).map(|zone| zone.name().to_string());
+    report.drift = timezones::clock_drift(&time, timezone.index, Utc::now());
+
+    if !report.is_wrong() {
+        return Ok(());
+    }
+    report.schedule_affected = device
+        .get_rules()
+        .await
+        .is_ok_and(|schedule| schedule.enabled && schedule.rules.iter().any(|r| r.enabled));
+
+    if fix {
+        info!(
+            "Resetting the clock of {} ({:?}s off)",
+            entry.id, report.drift
+        );
+        device.sync_clock(timezone.index).await?;
+        report.synced = true;
+    }
+    Ok(())
+}
@@ -0,0 +1,44 @@
+//! Events pushed to the frontend as device state changes
+use super::Device;
+use crate::tplink::models::EmeterRealtime;
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use tauri::AppHandle;
+use tauri_specta::Event;
+
+/// Emit an event to the frontend, logging rather than failing if it can't be
+/// delivered
+pub fn emit<E: Event + Serialize + Clone>(app: &AppHandle, event: E) {
+    if let Err(err) = event.emit(app) {
+        log::error!("Failed to emit {} {:?}", E::NAME, err);
+    }
+}
+
+/// A known device answered and its state differs from what we last saw
+#[derive(Debug, Clone, Deserialize, Serialize, specta::Type, tauri_specta::Event)]
+pub struct DeviceUpdated {
+    pub device: Device,
+}
+
+/// A device that was answering has stopped responding
+#[derive(Debug, Clone, Deserialize, Serialize, specta::Type, tauri_specta::Event)]
+pub struct DeviceWentOffline {
+    pub id: String,
+    pub addr: SocketAddr,
+}
+
+/// Discovery found a device that wasn't in the registry
+#[derive(Debug, Clone, Deserialize, Serialize, specta::Type, tauri_specta::Event)]
+pub struct DeviceDiscovered {
+    pub device: Device,
+}
+
+/// A metered device reported its power draw. Sent on every poll so the
+/// frontend can chart consumption.
+#[derive(Debug, Clone, Deserialize, Serialize, specta::Type, tauri_specta::Event)]
+pub struct EnergyReading {
+    pub id: String,
+    pub realtime: EmeterRealtime,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+}
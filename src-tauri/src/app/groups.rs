@@ -0,0 +1,148 @@
+//! Groups, such as rooms, that are switched and dimmed as one
+use super::{settings, AppError, AppResult, AppState, DeviceOutcome};
+use crate::tplink::models::PowerState;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+/// A named set of devices
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Group {
+    /// Unique among groups
+    pub name: String,
+    /// Device ids, or outlets' full ids
+    pub members: Vec<String>,
+}
+
+/// How a group looks as a whole, for display
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, specta::Type, utoipa::ToSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupState {
+    pub name: String,
+    /// Members that answered
+    pub reachable: u32,
+    /// Reachable members that are on
+    pub on: u32,
+    /// Average brightness of the members that are on and dim, if any
+    pub brightness: Option<u8>,
+}
+
+impl GroupState {
+    /// Summarize the states of the members that answered
+    pub fn from_members(name: String, states: &[PowerState]) -> Self {
+        let dimmed = states
+            .iter()
+            .filter(|state| state.on)
+            .filter_map(|state| state.brightness.map(u32::from))
+            .collect::<Vec<_>>();
+
+        Self {
+            name,
+            reachable: states.len() as u32,
+            on: states.iter().filter(|state| state.on).count() as u32,
+            brightness: (!dimmed.is_empty())
+                .then(|| (dimmed.iter().sum::<u32>() / dimmed.len() as u32) as u8),
+        }
+    }
+
+    /// Whether more than half of the reachable members are on, so that
+    /// toggling the group switches it off
+    pub fn mostly_on(&self) -> bool {
+        self.on * 2 > self.reachable
+    }
+}
+
+pub fn find(app: &AppHandle, name: &str) -> AppResult<Group> {
+    settings::load_groups(app)?
+        .into_iter()
+        .find(|group| group.name == name)
+        .ok_or_else(|| AppError::GroupNotFound(name.to_string()))
+}
+
+/// Save `group`, replacing any group with the same name
+pub fn save(app: &AppHandle, group: Group) -> AppResult<()> {
+    let mut groups = settings::load_groups(app)?;
+    match groups.iter_mut().find(|saved| saved.name == group.name) {
+        Some(saved) => *saved = group,
+        None => groups.push(group),
+    }
+    settings::save_groups(app, &groups)
+}
+
+pub fn delete(app: &AppHandle, name: &str) -> AppResult<()> {
+    let mut groups = settings::load_groups(app)?;
+    groups.retain(|group| group.name != name);
+    settings::save_groups(app, &groups)
+}
+
+/// Read every member and summarize. Members that don't answer are left out.
+pub async fn state(app: &AppHandle, name: &str) -> AppResult<GroupState> {
+    let group = find(app, name)?;
+    let states = super::map_devices(app, group.members, |_, mut device| async move {
+        device.get_power_state().await
+    })
+    .await
+    .into_iter()
+    .filter_map(|(id, state)| {
+        state
+            .inspect_err(|err| log::info!("Group {:?}: {} didn't answer: {}", name, id, err))
+            .ok()
+    })
+    .collect::<Vec<_>>();
+
+    Ok(GroupState::from_members(group.name, &states))
+}
+
+/// Put every member in `state` at once. Members that don't dim ignore the
+/// brightness and just switch on.
+pub async fn set(app: &AppHandle, name: &str, state: PowerState) -> AppResult<Vec<DeviceOutcome>> {
+    let group = find(app, name)?;
+    let targets = {
+        let state = app.state::<Mutex<AppState>>();
+        let state = state.lock().unwrap();
+        group
+            .members
+            .into_iter()
+            .map(|id| {
+                let name = state
+                    .resolve(&id)
+                    .map(|(entry, _)| entry.alias)
+                    .unwrap_or_else(|_| id.clone());
+                (id, name)
+            })
+            .collect()
+    };
+
+    let outcomes = super::for_each_device(app, targets, move |_, mut device| async move {
+        device.set_power_state(&state).await
+    })
+    .await;
+    Ok(outcomes)
+}
+
+/// The result of toggling a group
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupToggled {
+    /// Whether the group was switched on rather than off
+    pub on: bool,
+    pub outcomes: Vec<DeviceOutcome>,
+}
+
+/// Switch the group off if most of it is on, otherwise on
+pub async fn toggle(app: &AppHandle, name: &str) -> AppResult<GroupToggled> {
+    let on = !state(app, name).await?.mostly_on();
+    let outcomes = set(
+        app,
+        name,
+        PowerState {
+            on,
+            brightness: None,
+        },
+    )
+    .await?;
+    Ok(GroupToggled { on, outcomes })
+}
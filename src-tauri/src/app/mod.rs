@@ -0,0 +1,416 @@
+use crate::{
+    api::ApiServer,
+    mqtt::MqttBridge,
+    tplink::{
+        capabilities::CommonCapabilities,
+        devices::Device as TpLinkDevice,
+        discover::discover_devices,
+        error::{TpError, TpResult},
+        models::{ActiveMode, DeviceResponse, LightState, NextAction, PreferredState},
+    },
+};
+use registry::{Registry, RegistryEntry};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt, future::Future, net::SocketAddr, sync::Mutex};
+use tauri::{AppHandle, Manager};
+use tokio::task::JoinSet;
+
+pub mod automations;
+pub mod clocks;
+pub mod events;
+pub mod groups;
+pub mod mqtt;
+pub mod poller;
+pub mod presets;
+pub mod registry;
+pub mod scenes;
+pub mod settings;
+
+#[derive(Default)]
+pub struct AppState {
+    /// Every device we've discovered, persisted across launches
+    pub registry: Registry,
+    /// Where the devices are, as (latitude, longitude) in degrees, once one
+    /// that reports it has been polled
+    pub location: Option<(f64, f64)>,
+    /// Set while the MQTT bridge is enabled
+    pub mqtt: Option<MqttBridge>,
+    /// The HTTP API server, set while it runs
+    pub api: Option<ApiServer>,
+}
+
+impl AppState {
+    pub fn get_entry(&self, id: &str) -> AppResult<RegistryEntry> {
+        self.registry
+            .get(id)
+            .cloned()
+            .ok_or(AppError::NotFound(id.to_string()))
+    }
+
+    /// Find the registered device that `id` addresses. Outlets of a power
+    /// strip aren't registered themselves, so for those this returns the
+    /// strip's entry along with the outlet's id, which is the strip's id
+    /// followed by the outlet's index as two hex digits.
+    pub fn resolve(&self, id: &str) -> AppResult<(RegistryEntry, Option<String>)> {
+        if let Some(entry) = self.registry.get(id) {
+            return Ok((entry.clone(), None));
+        }
+
+        let is_outlet_of = |entry: &RegistryEntry| {
+            id.strip_prefix(&entry.id).is_some_and(|index| {
+                index.len() == 2 && index.chars().all(|c| c.is_ascii_hexdigit())
+            })
+        };
+        self.registry
+            .entries()
+            .find(|entry| is_outlet_of(entry))
+            .map(|entry| (entry.clone(), Some(id.to_string())))
+            .ok_or(AppError::NotFound(id.to_string()))
+    }
+}
+
+/// Run a discovery scan and record everything that answers in the registry
+pub async fn discover(app: &AppHandle) -> AppResult<Vec<(SocketAddr, DeviceResponse)>> {
+    let config = settings::load_discovery_config(app)?;
+    // Scan without holding the lock so other commands aren't blocked meanwhile
+    let resps = discover_devices(&config).await?;
+
+    let unprobed = {
+        let state = app.state::<Mutex<AppState>>();
+        let mut state = state.lock().unwrap();
+        for (addr, resp) in &resps {
+            if state.registry.get(&resp.sysinfo().device_id).is_none() {
+                for device in Device::all_from(*addr, resp) {
+                    events::emit(app, events::DeviceDiscovered { device });
+                }
+            }
+            state.registry.upsert(*addr, resp.sysinfo());
+        }
+        settings::save_registry(app, &state.registry)?;
+
+        state
+            .registry
+            .entries()
+            .filter(|entry| entry.capabilities.is_none())
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+
+    if !unprobed.is_empty() {
+        probe(app, unprobed).await?;
+    }
+
+    Ok(resps)
+}
+
+/// Probe each of `entries` for its capabilities and record them in the
+/// registry. Devices that don't answer are left to be probed next time.
+pub async fn probe(app: &AppHandle, entries: Vec<RegistryEntry>) -> AppResult<()> {
+    let mut probes = JoinSet::new();
+    for entry in entries {
+        probes.spawn(async move {
+            let result = TpLinkDevice::try_new(entry.addr, &entry.model)
+                .unwrap_or_else(|_| {
+                    TpLinkDevice::from_capabilities(entry.addr, &Default::default())
+                })
+                .probe()
+                .await;
+            (entry, result)
+        });
+    }
+
+    let mut probed = vec![];
+    while let Some(joined) = probes.join_next().await {
+        match joined {
+            Ok((entry, Ok(capabilities))) => probed.push((entry.id, capabilities)),
+            Ok((entry, Err(err))) => log::info!("Could not probe {}: {}", entry.id, err),
+            Err(err) => log::error!("Probe task failed {:?}", err),
+        }
+    }
+
+    let state = app.state::<Mutex<AppState>>();
+    let mut state = state.lock().unwrap();
+    for (id, capabilities) in probed {
+        state.registry.set_capabilities(&id, capabilities);
+    }
+    settings::save_registry(app, &state.registry)
+}
+
+/// Connect to the device, or the outlet of a power strip, that `id` addresses.
+/// Known models are connected as their own type, and probed capabilities
+/// decide the type of any other model.
+fn connect(entry: &RegistryEntry, child_id: Option<&str>) -> TpResult<TpLinkDevice> {
+    let device = match (
+        TpLinkDevice::try_new(entry.addr, &entry.model),
+        &entry.capabilities,
+    ) {
+        (Ok(device), _) => device,
+        (Err(_), Some(capabilities)) => TpLinkDevice::from_capabilities(entry.addr, capabilities),
+        (Err(err), None) => return Err(err),
+    };
+
+    match child_id {
+        Some(child_id) => device.with_child(child_id),
+        None => Ok(device),
+    }
+}
+
+/// Run `f` concurrently against each `(id, name)` target, which may address
+/// outlets, and report how each went. `f` is given the target's id along with
+/// the device.
+pub async fn for_each_device<F, Fut>(
+    app: &AppHandle,
+    targets: Vec<(String, String)>,
+    f: F,
+) -> Vec<DeviceOutcome>
+where
+    F: Fn(&str, TpLinkDevice) -> Fut,
+    Fut: Future<Output = TpResult<()>> + Send + 'static,
+{
+    let (ids, mut names): (Vec<_>, HashMap<_, _>) = targets
+        .into_iter()
+        .map(|(id, name)| (id.clone(), (id, name)))
+        .unzip();
+
+    let mut outcomes = map_devices(app, ids, f)
+        .await
+        .into_iter()
+        .map(|(id, result)| {
+            let name = names.remove(&id).unwrap_or_default();
+            DeviceOutcome::new(id, name, result)
+        })
+        .collect::<Vec<_>>();
+    outcomes.sort_by(|a, b| a.name.cmp(&b.name));
+    outcomes
+}
+
+/// Run `f` concurrently against each of `ids`, which may address outlets, and
+/// collect the results in no particular order. Unlike [`with_device`],
+/// devices that can't be reached aren't rediscovered.
+pub async fn map_devices<T, F, Fut>(
+    app: &AppHandle,
+    ids: Vec<String>,
+    f: F,
+) -> Vec<(String, AppResult<T>)>
+where
+    T: Send + 'static,
+    F: Fn(&str, TpLinkDevice) -> Fut,
+    Fut: Future<Output = TpResult<T>> + Send + 'static,
+{
+    let mut tasks = JoinSet::new();
+    for id in ids {
+        let resolved = app.state::<Mutex<AppState>>().lock().unwrap().resolve(&id);
+        let call = resolved.and_then(|(entry, child_id)| {
+            let device = connect(&entry, child_id.as_deref()).map_err(|err| err.at(entry.addr))?;
+            Ok((entry.addr, f(&id, device)))
+        });
+        tasks.spawn(async move {
+            let result = match call {
+                Ok((addr, call)) => call.await.map_err(|err| err.at(addr).into()),
+                Err(err) => Err(err),
+            };
+            (id, result)
+        });
+    }
+
+    let mut results = vec![];
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok(result) => results.push(result),
+            Err(err) => log::error!("Device task failed {:?}", err),
+        }
+    }
+    results
+}
+
+/// Run `f` against the registered device with `id`. If the device can't be
+/// reached at its last known address, rediscover it in case it has moved and
+/// try once more at the new address.
+pub async fn with_device<T, F, Fut>(app: &AppHandle, id: &str, f: F) -> AppResult<T>
+where
+    F: Fn(TpLinkDevice) -> Fut,
+    Fut: Future<Output = TpResult<T>>,
+{
+    let (entry, child_id) = app.state::<Mutex<AppState>>().lock().unwrap().resolve(id)?;
+
+    match f(connect(&entry, child_id.as_deref())?).await {
+        Err(err) if err.is_unreachable() => {
+            log::info!(
+                "{} unreachable at {} ({}), rediscovering",
+                id,
+                entry.addr,
+                err
+            );
+            discover(app).await?;
+
+            let moved = app
+                .state::<Mutex<AppState>>()
+                .lock()
+                .unwrap()
+                .get_entry(&entry.id)?;
+            if moved.addr == entry.addr {
+                return Err(err.into());
+            }
+
+            Ok(f(connect(&moved, child_id.as_deref())?)
+                .await
+                .map_err(|err| err.at(moved.addr))?)
+        }
+        result => Ok(result.map_err(|err| err.at(entry.addr))?),
+    }
+}
+
+pub type AppResult<T> = std::result::Result<T, AppError>;
+
+/// What happened to one device when changing several at once
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceOutcome {
+    pub id: String,
+    pub name: String,
+    /// `None` if the change succeeded
+    pub error: Option<AppError>,
+}
+
+impl DeviceOutcome {
+    pub fn new(id: String, name: String, result: AppResult<()>) -> Self {
+        Self {
+            id,
+            name,
+            error: result.err(),
+        }
+    }
+
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, specta::Type, utoipa::ToSchema)]
+pub enum AppError {
+    /// Tried to send a command to a device that was never discovered
+    NotFound(String),
+    /// TPLinker error
+    Tp(TpError),
+    /// Failed to read or write the persistent store
+    Store(String),
+    /// No scene with this name was saved
+    SceneNotFound(String),
+    /// No group with this name was saved
+    GroupNotFound(String),
+    /// No automation with this name was saved
+    AutomationNotFound(String),
+    /// The automation can't be saved as it is, such as for a malformed cron
+    /// expression
+    InvalidAutomation(String),
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AppError::NotFound(id) => write!(f, "Unknown device: {:?}", id),
+            AppError::Tp(err) => err.fmt(f),
+            AppError::Store(err) => write!(f, "Store error: {}", err),
+            AppError::SceneNotFound(name) => write!(f, "Unknown scene: {:?}", name),
+            AppError::GroupNotFound(name) => write!(f, "Unknown group: {:?}", name),
+            AppError::AutomationNotFound(name) => write!(f, "Unknown automation: {:?}", name),
+            AppError::InvalidAutomation(reason) => write!(f, "Invalid automation: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<TpError> for AppError {
+    fn from(err: TpError) -> Self {
+        AppError::Tp(err)
+    }
+}
+
+impl From<tauri_plugin_store::Error> for AppError {
+    fn from(err: tauri_plugin_store::Error) -> Self {
+        AppError::Store(err.to_string())
+    }
+}
+
+/// A controllable entry in the frontend. Each outlet of a power strip is its
+/// own entry, with the outlet's child id as its `id`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, Eq, specta::Type, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Device {
+    #[schema(value_type = String)]
+    pub addr: SocketAddr,
+    pub id: String,
+    /// For an outlet, the id of the power strip it belongs to
+    pub parent_id: Option<String>,
+    pub model: String,
+    pub name: String,
+    pub brightness: Option<u8>,
+    pub is_on: bool,
+    /// Set for smart bulbs
+    pub light_state: Option<LightState>,
+    pub is_color: bool,
+    /// What last switched the device, when it reports it
+    pub active_mode: Option<ActiveMode>,
+    /// What the device's schedule will do next
+    pub next_action: Option<NextAction>,
+    /// Whether the status LED is lit. Outlets share their strip's LED.
+    pub led_on: bool,
+    /// Brightness or light presets
+    pub preferred_state: Vec<PreferredState>,
+}
+
+impl From<(SocketAddr, DeviceResponse)> for Device {
+    fn from((addr, resp): (SocketAddr, DeviceResponse)) -> Self {
+        Self {
+            addr,
+            brightness: resp.sysinfo().brightness.or_else(|| {
+                resp.sysinfo()
+                    .light_state
+                    .as_ref()
+                    .and_then(LightState::brightness)
+            }),
+            id: resp.sysinfo().device_id.clone(),
+            parent_id: None,
+            is_on: resp.sysinfo().is_on(),
+            model: resp.sysinfo().model.clone(),
+            name: resp.sysinfo().alias.clone(),
+            light_state: resp.sysinfo().light_state.clone(),
+            is_color: resp.sysinfo().is_color(),
+            active_mode: resp.sysinfo().active_mode,
+            next_action: resp.sysinfo().next_action.clone(),
+            led_on: resp.sysinfo().led_on(),
+            preferred_state: resp.sysinfo().preferred_state.clone().unwrap_or_default(),
+        }
+    }
+}
+
+impl Device {
+    /// Every entry for a device: one per outlet for a power strip, otherwise
+    /// just the device itself
+    pub fn all_from(addr: SocketAddr, resp: &DeviceResponse) -> Vec<Self> {
+        let sysinfo = resp.sysinfo();
+        let Some(children) = sysinfo.children.as_ref().filter(|c| !c.is_empty()) else {
+            return vec![(addr, resp.clone()).into()];
+        };
+
+        children
+            .iter()
+            .map(|child| Self {
+                addr,
+                id: child.full_id(&sysinfo.device_id),
+                parent_id: Some(sysinfo.device_id.clone()),
+                model: sysinfo.model.clone(),
+                name: child.alias.clone(),
+                brightness: None,
+                is_on: child.is_on(),
+                light_state: None,
+                is_color: false,
+                active_mode: sysinfo.active_mode,
+                next_action: child.next_action.clone(),
+                led_on: sysinfo.led_on(),
+                preferred_state: vec![],
+            })
+            .collect()
+    }
+}
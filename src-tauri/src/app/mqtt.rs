@@ -0,0 +1,59 @@
+//! Runs the MQTT bridge while it is enabled and feeds it what the poller sees
+use super::{registry::RegistryEntry, settings, AppResult, AppState, Device};
+use crate::{
+    mqtt::{MqttBridge, MqttConfig, MqttDevice},
+    tplink::models::PowerState,
+};
+use log::*;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+/// Start the bridge if it is enabled, replacing any that is running
+pub async fn restart(app: &AppHandle) -> AppResult<()> {
+    let config = settings::load_mqtt_config(app)?;
+    let previous = app.state::<Mutex<AppState>>().lock().unwrap().mqtt.take();
+    if let Some(previous) = previous {
+        previous.stop().await;
+    }
+    if config.enabled {
+        app.state::<Mutex<AppState>>().lock().unwrap().mqtt = Some(MqttBridge::start(config));
+    }
+    Ok(())
+}
+
+/// Save the bridge settings and apply them
+pub async fn configure(app: &AppHandle, config: MqttConfig) -> AppResult<()> {
+    settings::save_mqtt_config(app, &config)?;
+    restart(app).await
+}
+
+/// Bridge the devices the poller just read from `entry`
+pub async fn update(app: &AppHandle, entry: &RegistryEntry, devices: &[Device]) {
+    let Some(bridge) = app.state::<Mutex<AppState>>().lock().unwrap().mqtt.clone() else {
+        return;
+    };
+
+    for device in devices {
+        let child_id = device.parent_id.as_ref().map(|_| device.id.as_str());
+        let handle = match super::connect(entry, child_id) {
+            Ok(handle) => handle,
+            Err(err) => {
+                warn!("Can't bridge {}: {}", device.id, err);
+                continue;
+            }
+        };
+        let state = PowerState {
+            on: device.is_on,
+            brightness: device.brightness,
+        };
+        let bridged = MqttDevice::new(
+            device.id.clone(),
+            device.name.clone(),
+            device.model.clone(),
+            handle,
+        );
+        if let Err(err) = bridge.update(bridged, state).await {
+            warn!("Failed to publish {} over MQTT {:?}", device.id, err);
+        }
+    }
+}
@@ -0,0 +1,174 @@
+//! Periodically polls every registered device so that changes made at the
+//! wall switch or from other apps reach the frontend
+use super::{
+    automations,
+    events::{emit, DeviceUpdated, DeviceWentOffline, EnergyReading},
+    mqtt,
+    registry::{self, RegistryEntry},
+    settings, AppState, Device,
+};
+use crate::tplink::{
+    error::TpResult,
+    models::{EmeterRealtime, SysInfo},
+    prelude::*,
+};
+use log::*;
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+use tauri::{AppHandle, Manager};
+use tokio::task::JoinSet;
+
+/// How often every device is polled
+pub const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Start polling in the background for as long as the app runs
+pub fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        // Last entries seen per registered device id, `None` if it stopped
+        // answering. Power strips have an entry per outlet.
+        let mut last_seen = HashMap::<String, Option<Vec<Device>>>::new();
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+
+        loop {
+            interval.tick().await;
+            poll(&app, &mut last_seen).await;
+        }
+    });
+}
+
+async fn poll(app: &AppHandle, last_seen: &mut HashMap<String, Option<Vec<Device>>>) {
+    let entries = app
+        .state::<Mutex<AppState>>()
+        .lock()
+        .unwrap()
+        .registry
+        .entries()
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let mut polls = JoinSet::new();
+    for entry in entries {
+        polls.spawn(async move {
+            let result = poll_device(&entry).await;
+            (entry, result)
+        });
+    }
+
+    while let Some(joined) = polls.join_next().await {
+        let Ok((entry, result)) = joined else {
+            continue;
+        };
+
+        match result {
+            Ok((sysinfo, readings)) => {
+                for (id, realtime) in readings {
+                    emit(
+                        app,
+                        EnergyReading {
+                            id,
+                            realtime,
+                            timestamp: registry::now(),
+                        },
+                    );
+                }
+
+                let devices = Device::all_from(entry.addr, &sysinfo.clone().into());
+                let previous = last_seen.get(&entry.id).cloned().flatten();
+                for device in &devices {
+                    let before = previous.iter().flatten().find(|seen| seen.id == device.id);
+                    if before.is_some_and(|before| before.is_on != device.is_on) {
+                        automations::device_switched(app, &device.id, device.is_on);
+                    }
+                    if before != Some(device) {
+                        debug!("{} changed", device.id);
+                        emit(
+                            app,
+                            DeviceUpdated {
+                                device: device.clone(),
+                            },
+                        );
+                    }
+                }
+                mqtt::update(app, &entry, &devices).await;
+                last_seen.insert(entry.id.clone(), Some(devices));
+                record(app, &entry, sysinfo);
+            }
+            Err(err) => {
+                if let Some(Some(devices)) = last_seen.insert(entry.id.clone(), None) {
+                    info!("{} went offline: {}", entry.id, err);
+                    for device in devices {
+                        emit(
+                            app,
+                            DeviceWentOffline {
+                                id: device.id,
+                                addr: entry.addr,
+                            },
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    // Forget state for devices that have left the registry
+    let state = app.state::<Mutex<AppState>>();
+    let state = state.lock().unwrap();
+    last_seen.retain(|id, _| state.registry.get(id).is_some());
+}
+
+/// Read a device's sysinfo, along with the power draw of the device or of
+/// each of its outlets if it has an energy meter
+async fn poll_device(entry: &RegistryEntry) -> TpResult<(SysInfo, Vec<(String, EmeterRealtime)>)> {
+    let mut device = super::connect(entry, None)?;
+    let snapshot = device.get_snapshot().await?;
+    let sysinfo = snapshot.sysinfo;
+
+    // Outlets are metered separately, with a request addressed to each
+    let Some(children) = sysinfo.children.as_ref() else {
+        let readings = snapshot
+            .realtime
+            .map(|realtime| (entry.id.clone(), realtime));
+        return Ok((sysinfo, readings.into_iter().collect()));
+    };
+
+    let mut readings = vec![];
+    for child_id in children
+        .iter()
+        .map(|child| child.full_id(&sysinfo.device_id))
+    {
+        let mut outlet = super::connect(entry, Some(&child_id))?;
+        let Ok(meter) = outlet.try_into_energy_meter() else {
+            break;
+        };
+        match meter.get_realtime().await {
+            Ok(realtime) => readings.push((child_id, realtime)),
+            Err(err) => debug!("{} has no realtime reading: {}", child_id, err),
+        }
+    }
+
+    Ok((sysinfo, readings))
+}
+
+/// Refresh the registry with what the device reported, persisting it only if
+/// something other than the last seen time changed. A device forgotten while
+/// it was being polled stays forgotten.
+fn record(app: &AppHandle, entry: &RegistryEntry, sysinfo: SysInfo) {
+    let state = app.state::<Mutex<AppState>>();
+    let mut state = state.lock().unwrap();
+    if state.registry.get(&entry.id).is_none() {
+        return;
+    }
+    if let Some(location) = sysinfo.location() {
+        state.location = Some(location);
+    }
+    let updated = state.registry.upsert(entry.addr, &sysinfo).clone();
+
+    if (RegistryEntry {
+        last_seen: entry.last_seen,
+        ..updated
+    }) != *entry
+    {
+        if let Err(err) = settings::save_registry(app, &state.registry) {
+            error!("Failed to save registry {:?}", err);
+        }
+    }
+}
@@ -0,0 +1,46 @@
+//! Status LEDs and presets changed on every device at once, from the app or
+//! the tray
+use super::{AppState, DeviceOutcome};
+use crate::tplink::{models::DeviceCapabilities, prelude::*};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+/// Turn the status LED of every device that has one on or off
+pub async fn set_leds(app: &AppHandle, on: bool) -> Vec<DeviceOutcome> {
+    let targets = probed_devices(app, |capabilities| !capabilities.lighting);
+    super::for_each_device(app, targets, move |_, device| async move {
+        device.set_led_on(on).await
+    })
+    .await
+}
+
+/// Switch every dimmer and bulb on with its preset in slot `index`
+pub async fn apply_presets(app: &AppHandle, index: u8) -> Vec<DeviceOutcome> {
+    let targets = probed_devices(app, |capabilities| {
+        capabilities.dimmer || capabilities.lighting
+    });
+    super::for_each_device(app, targets, move |_, mut device| async move {
+        device
+            .try_into_presets()?
+            .apply_preset(index)
+            .await
+            .map(drop)
+    })
+    .await
+}
+
+/// The id and name of every registered device whose probed capabilities pass
+/// `filter`. Unprobed devices are skipped.
+fn probed_devices(
+    app: &AppHandle,
+    filter: impl Fn(&DeviceCapabilities) -> bool,
+) -> Vec<(String, String)> {
+    app.state::<Mutex<AppState>>()
+        .lock()
+        .unwrap()
+        .registry
+        .entries()
+        .filter(|entry| entry.capabilities.as_ref().is_some_and(&filter))
+        .map(|entry| (entry.id.clone(), entry.alias.clone()))
+        .collect()
+}
@@ -0,0 +1,84 @@
+use crate::tplink::models::{DeviceCapabilities, SysInfo};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Everything we remember about a device between launches
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RegistryEntry {
+    /// The device's `deviceId`, which survives address changes
+    pub id: String,
+    pub mac: String,
+    /// Where the device was last seen. May be stale if DHCP has moved it.
+    pub addr: SocketAddr,
+    pub model: String,
+    pub alias: String,
+    pub hw_ver: String,
+    pub sw_ver: String,
+    /// Unix timestamp (seconds) of the last successful contact
+    pub last_seen: u64,
+    /// What the device was found to support when it was probed, `None`
+    /// until then
+    #[serde(default)]
+    pub capabilities: Option<DeviceCapabilities>,
+}
+
+/// Known devices keyed by device id
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Registry {
+    devices: HashMap<String, RegistryEntry>,
+}
+
+impl Registry {
+    pub fn get(&self, id: &str) -> Option<&RegistryEntry> {
+        self.devices.get(id)
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &RegistryEntry> {
+        self.devices.values()
+    }
+
+    /// Record that the device described by `sysinfo` answered from `addr`
+    pub fn upsert(&mut self, addr: SocketAddr, sysinfo: &SysInfo) -> &RegistryEntry {
+        let entry = RegistryEntry {
+            id: sysinfo.device_id.clone(),
+            mac: sysinfo.mac.clone(),
+            addr,
+            model: sysinfo.model.clone(),
+            alias: sysinfo.alias.clone(),
+            hw_ver: sysinfo.hw_ver.clone(),
+            sw_ver: sysinfo.sw_ver.clone(),
+            last_seen: now(),
+            capabilities: self
+                .devices
+                .get(&sysinfo.device_id)
+                .and_then(|entry| entry.capabilities),
+        };
+
+        self.devices.insert(entry.id.clone(), entry);
+        &self.devices[&sysinfo.device_id]
+    }
+
+    pub fn set_capabilities(&mut self, id: &str, capabilities: DeviceCapabilities) {
+        if let Some(entry) = self.devices.get_mut(id) {
+            entry.capabilities = Some(capabilities);
+        }
+    }
+
+    pub fn remove(&mut self, id: &str) -> Option<RegistryEntry> {
+        self.devices.remove(id)
+    }
+}
+
+/// Seconds since the Unix epoch
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
@@ -0,0 +1,115 @@
+//! Scenes: the on state and brightness of several devices, saved under a
+//! name and recalled together
+use super::{settings, AppError, AppResult, AppState, DeviceOutcome};
+use crate::tplink::{error::TpError, models::PowerState, prelude::*};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Mutex};
+use tauri::{AppHandle, Manager};
+use tokio::task::JoinSet;
+
+/// A named state for a set of devices, such as "Movie night"
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Scene {
+    /// Unique among scenes
+    pub name: String,
+    pub devices: Vec<SceneDevice>,
+}
+
+/// One device's part in a [`Scene`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SceneDevice {
+    /// A device id, or an outlet's full id
+    pub id: String,
+    /// The device's name when the scene was saved
+    pub name: String,
+    pub state: PowerState,
+}
+
+/// Read the current state of the devices with `ids` into a scene called
+/// `name`. The scene isn't saved.
+pub async fn capture(app: &AppHandle, name: String, ids: Vec<String>) -> AppResult<Scene> {
+    let mut reads = JoinSet::new();
+    for (index, id) in ids.into_iter().enumerate() {
+        let (entry, child_id) = app
+            .state::<Mutex<AppState>>()
+            .lock()
+            .unwrap()
+            .resolve(&id)?;
+        let mut device = super::connect(&entry, child_id.as_deref())?;
+        reads.spawn(async move {
+            let read = async {
+                let state = device.get_power_state().await?;
+                let name = device.get_alias().await?;
+                Ok((index, SceneDevice { id, name, state }))
+            };
+            read.await
+                .map_err(|err: TpError| AppError::from(err.at(entry.addr)))
+        });
+    }
+
+    let mut devices = vec![];
+    while let Some(joined) = reads.join_next().await {
+        devices.push(
+            joined.map_err(|err| TpError::from(format!("Scene capture failed {:?}", err)))??,
+        );
+    }
+    devices.sort_by_key(|(index, _)| *index);
+
+    Ok(Scene {
+        name,
+        devices: devices.into_iter().map(|(_, device)| device).collect(),
+    })
+}
+
+/// Save `scene`, replacing any scene with the same name
+pub fn save(app: &AppHandle, scene: Scene) -> AppResult<()> {
+    let mut scenes = settings::load_scenes(app)?;
+    match scenes.iter_mut().find(|saved| saved.name == scene.name) {
+        Some(saved) => *saved = scene,
+        None => scenes.push(scene),
+    }
+    settings::save_scenes(app, &scenes)
+}
+
+pub fn delete(app: &AppHandle, name: &str) -> AppResult<()> {
+    let mut scenes = settings::load_scenes(app)?;
+    scenes.retain(|scene| scene.name != name);
+    settings::save_scenes(app, &scenes)
+}
+
+/// Put every device of the scene called `name` in its saved state, all at
+/// once. A device that fails doesn't stop the others.
+pub async fn apply(app: &AppHandle, name: &str) -> AppResult<Vec<DeviceOutcome>> {
+    let scene = settings::load_scenes(app)?
+        .into_iter()
+        .find(|scene| scene.name == name)
+        .ok_or_else(|| AppError::SceneNotFound(name.to_string()))?;
+
+    let states = scene
+        .devices
+        .iter()
+        .map(|device| (device.id.clone(), device.state))
+        .collect::<HashMap<_, _>>();
+    let targets = scene
+        .devices
+        .into_iter()
+        .map(|device| (device.id, device.name))
+        .collect();
+
+    let outcomes = super::for_each_device(app, targets, |id, mut device| {
+        let state = states[id];
+        async move { device.set_power_state(&state).await }
+    })
+    .await;
+    for outcome in outcomes.iter().filter(|outcome| !outcome.succeeded()) {
+        log::warn!(
+            "Scene {:?} failed for {}: {:?}",
+            name,
+            outcome.name,
+            outcome.error
+        );
+    }
+    Ok(outcomes)
+}
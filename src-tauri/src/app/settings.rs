@@ -0,0 +1,108 @@
+use super::{
+    automations::{Automation, Execution},
+    groups::Group,
+    registry::Registry,
+    scenes::Scene,
+    AppError, AppResult,
+};
+use crate::{api::ApiConfig, mqtt::MqttConfig, tplink::discover::DiscoveryConfig};
+use serde::{de::DeserializeOwned, Serialize};
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+/// The store that all persistent app state lives in
+pub const STORE_PATH: &str = "store.json";
+
+const DISCOVERY_KEY: &str = "discovery";
+const REGISTRY_KEY: &str = "devices";
+const SCENES_KEY: &str = "scenes";
+const GROUPS_KEY: &str = "groups";
+const AUTOMATIONS_KEY: &str = "automations";
+const HISTORY_KEY: &str = "history";
+const MQTT_KEY: &str = "mqtt";
+const API_KEY: &str = "api";
+
+/// Read a value from the store, falling back to its default if it's missing
+pub fn load<T: DeserializeOwned + Default>(app: &AppHandle, key: &str) -> AppResult<T> {
+    match app.store(STORE_PATH)?.get(key) {
+        Some(value) => {
+            serde_json::from_value(value).map_err(|err| AppError::Store(err.to_string()))
+        }
+        None => Ok(T::default()),
+    }
+}
+
+/// Write a value to the store and flush it to disk
+pub fn save<T: Serialize>(app: &AppHandle, key: &str, value: &T) -> AppResult<()> {
+    let store = app.store(STORE_PATH)?;
+    let value = serde_json::to_value(value).map_err(|err| AppError::Store(err.to_string()))?;
+    store.set(key, value);
+    store.save()?;
+    Ok(())
+}
+
+pub fn load_discovery_config(app: &AppHandle) -> AppResult<DiscoveryConfig> {
+    load(app, DISCOVERY_KEY)
+}
+
+pub fn save_discovery_config(app: &AppHandle, config: &DiscoveryConfig) -> AppResult<()> {
+    config.validate()?;
+    save(app, DISCOVERY_KEY, config)
+}
+
+pub fn load_registry(app: &AppHandle) -> AppResult<Registry> {
+    load(app, REGISTRY_KEY)
+}
+
+pub fn save_registry(app: &AppHandle, registry: &Registry) -> AppResult<()> {
+    save(app, REGISTRY_KEY, registry)
+}
+
+pub fn load_scenes(app: &AppHandle) -> AppResult<Vec<Scene>> {
+    load(app, SCENES_KEY)
+}
+
+pub fn save_scenes(app: &AppHandle, scenes: &[Scene]) -> AppResult<()> {
+    save(app, SCENES_KEY, &scenes)
+}
+
+pub fn load_groups(app: &AppHandle) -> AppResult<Vec<Group>> {
+    load(app, GROUPS_KEY)
+}
+
+pub fn save_groups(app: &AppHandle, groups: &[Group]) -> AppResult<()> {
+    save(app, GROUPS_KEY, &groups)
+}
+
+pub fn load_automations(app: &AppHandle) -> AppResult<Vec<Automation>> {
+    load(app, AUTOMATIONS_KEY)
+}
+
+pub fn save_automations(app: &AppHandle, automations: &[Automation]) -> AppResult<()> {
+    save(app, AUTOMATIONS_KEY, &automations)
+}
+
+/// Automation executions, newest first
+pub fn load_history(app: &AppHandle) -> AppResult<Vec<Execution>> {
+    load(app, HISTORY_KEY)
+}
+
+pub fn save_history(app: &AppHandle, history: &[Execution]) -> AppResult<()> {
+    save(app, HISTORY_KEY, &history)
+}
+
+pub fn load_mqtt_config(app: &AppHandle) -> AppResult<MqttConfig> {
+    load(app, MQTT_KEY)
+}
+
+pub fn save_mqtt_config(app: &AppHandle, config: &MqttConfig) -> AppResult<()> {
+    save(app, MQTT_KEY, config)
+}
+
+pub fn load_api_config(app: &AppHandle) -> AppResult<ApiConfig> {
+    load(app, API_KEY)
+}
+
+pub fn save_api_config(app: &AppHandle, config: &ApiConfig) -> AppResult<()> {
+    save(app, API_KEY, config)
+}
@@ -0,0 +1,19 @@
+use crate::{
+    api::{self, ApiConfig},
+    app::{settings, AppResult},
+};
+use tauri::AppHandle;
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_api_config(app: AppHandle) -> AppResult<ApiConfig> {
+    settings::load_api_config(&app)
+}
+
+/// Save the HTTP API settings and start or stop the server to match. Returns
+/// the settings as saved, with a token if one was generated.
+#[tauri::command]
+#[specta::specta]
+pub async fn set_api_config(config: ApiConfig, app: AppHandle) -> AppResult<ApiConfig> {
+    api::configure(&app, config).await
+}
@@ -0,0 +1,47 @@
+use crate::app::{
+    automations::{self, Automation, Execution},
+    settings, AppResult,
+};
+use tauri::AppHandle;
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_automations(app: AppHandle) -> AppResult<Vec<Automation>> {
+    settings::load_automations(&app)
+}
+
+/// Save an automation, replacing any automation with the same name
+#[tauri::command]
+#[specta::specta]
+pub fn save_automation(automation: Automation, app: AppHandle) -> AppResult<()> {
+    automations::save(&app, automation)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn delete_automation(name: String, app: AppHandle) -> AppResult<()> {
+    automations::delete(&app, &name)
+}
+
+/// Run an automation now, regardless of its trigger
+#[tauri::command]
+#[specta::specta]
+pub async fn run_automation(name: String, app: AppHandle) -> AppResult<Execution> {
+    let automation = automations::find(&app, &name)?;
+    Ok(automations::run(&app, &automation, "run by hand".to_string()).await)
+}
+
+/// When an automation will next run on its own, as a Unix timestamp
+/// (seconds). `None` if it is disabled or waits for a device to be switched.
+#[tauri::command]
+#[specta::specta]
+pub fn get_next_automation_run(name: String, app: AppHandle) -> AppResult<Option<i64>> {
+    Ok(automations::next_run(&app, &name)?.map(|time| time.timestamp()))
+}
+
+/// Past automation runs, newest first
+#[tauri::command]
+#[specta::specta]
+pub fn get_automation_history(app: AppHandle) -> AppResult<Vec<Execution>> {
+    settings::load_history(&app)
+}
@@ -0,0 +1,54 @@
+use crate::{
+    app::{self, AppResult},
+    tplink::{
+        models::{DefaultBehavior, LightDetails, LightState, LightTransition},
+        prelude::*,
+    },
+};
+use tauri::AppHandle;
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_light_state(id: String, app: AppHandle) -> AppResult<LightState> {
+    app::with_device(&app, &id, |mut device| async move {
+        device.try_into_smart_bulb()?.get_light_state().await
+    })
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn transition_light_state(
+    id: String,
+    transition: LightTransition,
+    app: AppHandle,
+) -> AppResult<LightState> {
+    app::with_device(&app, &id, |mut device| {
+        let transition = transition.clone();
+        async move {
+            device
+                .try_into_smart_bulb()?
+                .transition_light_state(&transition)
+                .await
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_light_details(id: String, app: AppHandle) -> AppResult<LightDetails> {
+    app::with_device(&app, &id, |mut device| async move {
+        device.try_into_smart_bulb()?.get_light_details().await
+    })
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_light_default_behavior(id: String, app: AppHandle) -> AppResult<DefaultBehavior> {
+    app::with_device(&app, &id, |mut device| async move {
+        device.try_into_smart_bulb()?.get_default_behavior().await
+    })
+    .await
+}
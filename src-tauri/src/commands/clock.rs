@@ -0,0 +1,44 @@
+use crate::{
+    app::{self, clocks::ClockReport, AppResult},
+    tplink::{
+        models::{DeviceTime, DeviceTimezone},
+        prelude::*,
+    },
+};
+use tauri::AppHandle;
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_device_time(id: String, app: AppHandle) -> AppResult<DeviceTime> {
+    app::with_device(&app, &id, |device| async move { device.get_time().await }).await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_device_timezone(id: String, app: AppHandle) -> AppResult<DeviceTimezone> {
+    app::with_device(
+        &app,
+        &id,
+        |device| async move { device.get_timezone().await },
+    )
+    .await
+}
+
+/// Move a device to an IANA zone such as `Europe/Amsterdam` and set its clock
+#[tauri::command]
+#[specta::specta]
+pub async fn set_device_timezone(id: String, zone: String, app: AppHandle) -> AppResult<()> {
+    app::with_device(&app, &id, |mut device| {
+        let zone = zone.clone();
+        async move { device.set_zone(&zone).await }
+    })
+    .await
+}
+
+/// Compare every device clock to the host's. With `fix`, the ones that are
+/// off are reset.
+#[tauri::command]
+#[specta::specta]
+pub async fn sync_device_clocks(fix: bool, app: AppHandle) -> Vec<ClockReport> {
+    app::clocks::check_clocks(&app, fix).await
+}
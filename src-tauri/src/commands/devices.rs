@@ -0,0 +1,85 @@
+use crate::{
+    app::{self, registry::RegistryEntry, settings, AppResult, AppState},
+    tplink::{
+        models::{DeviceCapabilities, DeviceSnapshot, LightTransition},
+        prelude::*,
+    },
+};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+#[tauri::command]
+#[specta::specta]
+pub fn registered_devices(state: State<'_, Mutex<AppState>>) -> Vec<RegistryEntry> {
+    state.lock().unwrap().registry.entries().cloned().collect()
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn forget_device(
+    id: String,
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+) -> AppResult<()> {
+    let mut state = state.lock().unwrap();
+    state.registry.remove(&id);
+    settings::save_registry(&app, &state.registry)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn toggle(id: String, app: AppHandle) -> AppResult<bool> {
+    app::with_device(&app, &id, |mut device| async move { device.toggle().await }).await
+}
+
+/// Set the brightness of a dimmer or a bulb
+#[tauri::command]
+#[specta::specta]
+pub async fn set_brightness(id: String, brightness: u8, app: AppHandle) -> AppResult<()> {
+    app::with_device(&app, &id, |mut device| async move {
+        if let Ok(bulb) = device.try_into_smart_bulb() {
+            let transition = LightTransition {
+                brightness: Some(brightness),
+                ..Default::default()
+            };
+            return bulb.transition_light_state(&transition).await.map(drop);
+        }
+        device.try_into_dimmable()?.set_brightness(brightness).await
+    })
+    .await
+}
+
+/// Read everything about a device in one round trip
+#[tauri::command]
+#[specta::specta]
+pub async fn get_device_snapshot(id: String, app: AppHandle) -> AppResult<DeviceSnapshot> {
+    app::with_device(&app, &id, |mut device| async move {
+        device.get_snapshot().await
+    })
+    .await
+}
+
+/// Probe which modules a device has and remember the result. An outlet is
+/// probed through its power strip.
+#[tauri::command]
+#[specta::specta]
+pub async fn probe_device(id: String, app: AppHandle) -> AppResult<DeviceCapabilities> {
+    let (entry, _) = app
+        .state::<Mutex<AppState>>()
+        .lock()
+        .unwrap()
+        .resolve(&id)?;
+    let capabilities = app::with_device(
+        &app,
+        &entry.id,
+        |device| async move { device.probe().await },
+    )
+    .await?;
+
+    let state = app.state::<Mutex<AppState>>();
+    let mut state = state.lock().unwrap();
+    state.registry.set_capabilities(&entry.id, capabilities);
+    settings::save_registry(&app, &state.registry)?;
+
+    Ok(capabilities)
+}
@@ -0,0 +1,108 @@
+use crate::{
+    app::{self, AppResult},
+    tplink::{
+        models::{BehaviorAction, DefaultBehavior, DimmerButton, DimmerParameters, TransitionMode},
+        prelude::*,
+    },
+};
+use serde::Deserialize;
+use std::time::Duration;
+use tauri::AppHandle;
+
+/// A single tunable field of [`DimmerParameters`]. Times are in milliseconds.
+#[derive(Debug, Deserialize, Clone, Copy, specta::Type)]
+#[serde(tag = "parameter", content = "value", rename_all = "camelCase")]
+pub enum DimmerParameter {
+    FadeOnTime(u32),
+    FadeOffTime(u32),
+    GentleOnTime(u32),
+    GentleOffTime(u32),
+    RampRate(u32),
+    MinThreshold(u8),
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_switch_state(id: String, on: bool, app: AppHandle) -> AppResult<()> {
+    app::with_device(&app, &id, |mut device| async move {
+        device.try_into_dimmable()?.set_switch_state(on).await
+    })
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_dimmer_parameters(id: String, app: AppHandle) -> AppResult<DimmerParameters> {
+    app::with_device(&app, &id, |mut device| async move {
+        device.try_into_dimmable()?.get_dimmer_parameters().await
+    })
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_dimmer_parameter(
+    id: String,
+    parameter: DimmerParameter,
+    app: AppHandle,
+) -> AppResult<()> {
+    app::with_device(&app, &id, |mut device| async move {
+        let dimmer = device.try_into_dimmable()?;
+        match parameter {
+            DimmerParameter::FadeOnTime(millis) => dimmer.set_fade_on_time(millis).await,
+            DimmerParameter::FadeOffTime(millis) => dimmer.set_fade_off_time(millis).await,
+            DimmerParameter::GentleOnTime(millis) => dimmer.set_gentle_on_time(millis).await,
+            DimmerParameter::GentleOffTime(millis) => dimmer.set_gentle_off_time(millis).await,
+            DimmerParameter::RampRate(rate) => dimmer.set_ramp_rate(rate).await,
+            DimmerParameter::MinThreshold(min) => dimmer.calibrate_min_threshold(min).await,
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_default_behavior(id: String, app: AppHandle) -> AppResult<DefaultBehavior> {
+    app::with_device(&app, &id, |mut device| async move {
+        device.try_into_dimmable()?.get_default_behavior().await
+    })
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_button_action(
+    id: String,
+    button: DimmerButton,
+    action: BehaviorAction,
+    app: AppHandle,
+) -> AppResult<()> {
+    app::with_device(&app, &id, |mut device| {
+        let action = action.clone();
+        async move {
+            device
+                .try_into_dimmable()?
+                .set_button_action(button, action)
+                .await
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_dimmer_transition(
+    id: String,
+    brightness: u8,
+    mode: TransitionMode,
+    duration_ms: u32,
+    app: AppHandle,
+) -> AppResult<()> {
+    app::with_device(&app, &id, |mut device| async move {
+        device
+            .try_into_dimmable()?
+            .set_dimmer_transition(brightness, mode, Duration::from_millis(duration_ms.into()))
+            .await
+    })
+    .await
+}
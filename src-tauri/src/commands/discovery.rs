@@ -0,0 +1,27 @@
+use crate::{
+    app::{self, settings, AppResult, Device},
+    tplink::discover::DiscoveryConfig,
+};
+use tauri::AppHandle;
+
+#[tauri::command]
+#[specta::specta]
+pub async fn discover(app: AppHandle) -> AppResult<Vec<Device>> {
+    Ok(app::discover(&app)
+        .await?
+        .iter()
+        .flat_map(|(addr, resp)| Device::all_from(*addr, resp))
+        .collect())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_discovery_config(app: AppHandle) -> AppResult<DiscoveryConfig> {
+    settings::load_discovery_config(&app)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_discovery_config(app: AppHandle, config: DiscoveryConfig) -> AppResult<()> {
+    settings::save_discovery_config(&app, &config)
+}
@@ -0,0 +1,59 @@
+use crate::{
+    app::{self, AppResult},
+    tplink::{
+        models::{EmeterRealtime, EmeterStat},
+        prelude::*,
+    },
+};
+use tauri::AppHandle;
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_emeter_realtime(id: String, app: AppHandle) -> AppResult<EmeterRealtime> {
+    app::with_device(&app, &id, |mut device| async move {
+        device.try_into_energy_meter()?.get_realtime().await
+    })
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_emeter_daily_stats(
+    id: String,
+    year: u16,
+    month: u8,
+    app: AppHandle,
+) -> AppResult<Vec<EmeterStat>> {
+    app::with_device(&app, &id, |mut device| async move {
+        device
+            .try_into_energy_meter()?
+            .get_daily_stats(year, month)
+            .await
+    })
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_emeter_monthly_stats(
+    id: String,
+    year: u16,
+    app: AppHandle,
+) -> AppResult<Vec<EmeterStat>> {
+    app::with_device(&app, &id, |mut device| async move {
+        device
+            .try_into_energy_meter()?
+            .get_monthly_stats(year)
+            .await
+    })
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn erase_emeter_stats(id: String, app: AppHandle) -> AppResult<()> {
+    app::with_device(&app, &id, |mut device| async move {
+        device.try_into_energy_meter()?.erase_stats().await
+    })
+    .await
+}
@@ -0,0 +1,68 @@
+use crate::{
+    app::{
+        groups::{self, Group, GroupState, GroupToggled},
+        settings, AppResult, DeviceOutcome,
+    },
+    tplink::models::PowerState,
+};
+use tauri::AppHandle;
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_groups(app: AppHandle) -> AppResult<Vec<Group>> {
+    settings::load_groups(&app)
+}
+
+/// Save a group, replacing any group with the same name
+#[tauri::command]
+#[specta::specta]
+pub fn save_group(group: Group, app: AppHandle) -> AppResult<()> {
+    groups::save(&app, group)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn delete_group(name: String, app: AppHandle) -> AppResult<()> {
+    groups::delete(&app, &name)
+}
+
+/// How many members are on, and how bright
+#[tauri::command]
+#[specta::specta]
+pub async fn get_group_state(name: String, app: AppHandle) -> AppResult<GroupState> {
+    groups::state(&app, &name).await
+}
+
+/// Switch every member on or off
+#[tauri::command]
+#[specta::specta]
+pub async fn switch_group(name: String, on: bool, app: AppHandle) -> AppResult<Vec<DeviceOutcome>> {
+    let state = PowerState {
+        on,
+        brightness: None,
+    };
+    groups::set(&app, &name, state).await
+}
+
+/// Switch the group off if most of it is on, otherwise on
+#[tauri::command]
+#[specta::specta]
+pub async fn toggle_group(name: String, app: AppHandle) -> AppResult<GroupToggled> {
+    groups::toggle(&app, &name).await
+}
+
+/// Switch every member on at `brightness`. Members that don't dim just
+/// switch on.
+#[tauri::command]
+#[specta::specta]
+pub async fn set_group_brightness(
+    name: String,
+    brightness: u8,
+    app: AppHandle,
+) -> AppResult<Vec<DeviceOutcome>> {
+    let state = PowerState {
+        on: true,
+        brightness: Some(brightness),
+    };
+    groups::set(&app, &name, state).await
+}
@@ -0,0 +1,15 @@
+pub mod api;
+pub mod automations;
+pub mod bulb;
+pub mod clock;
+pub mod devices;
+pub mod dimmer;
+pub mod discovery;
+pub mod energy;
+pub mod groups;
+pub mod mqtt;
+pub mod presets;
+pub mod scenes;
+pub mod schedule;
+pub mod sensors;
+pub mod timers;
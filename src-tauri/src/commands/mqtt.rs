@@ -0,0 +1,18 @@
+use crate::{
+    app::{self, settings, AppResult},
+    mqtt::MqttConfig,
+};
+use tauri::AppHandle;
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_mqtt_config(app: AppHandle) -> AppResult<MqttConfig> {
+    settings::load_mqtt_config(&app)
+}
+
+/// Save the MQTT bridge settings, reconnecting or stopping the bridge to match
+#[tauri::command]
+#[specta::specta]
+pub async fn set_mqtt_config(config: MqttConfig, app: AppHandle) -> AppResult<()> {
+    app::mqtt::configure(&app, config).await
+}
@@ -0,0 +1,67 @@
+use crate::{
+    app::{self, AppResult, AppState, DeviceOutcome},
+    tplink::{models::PreferredState, prelude::*},
+};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+/// Turn a device's status LED on or off. An outlet changes its power strip's
+/// LED, which all outlets share.
+#[tauri::command]
+#[specta::specta]
+pub async fn set_led(id: String, on: bool, app: AppHandle) -> AppResult<()> {
+    let (entry, _) = app
+        .state::<Mutex<AppState>>()
+        .lock()
+        .unwrap()
+        .resolve(&id)?;
+    app::with_device(&app, &entry.id, |device| async move {
+        device.set_led_on(on).await
+    })
+    .await
+}
+
+/// Turn the status LED of every device that has one on or off
+#[tauri::command]
+#[specta::specta]
+pub async fn set_all_leds(on: bool, app: AppHandle) -> Vec<DeviceOutcome> {
+    app::presets::set_leds(&app, on).await
+}
+
+/// The presets of a dimmer or bulb
+#[tauri::command]
+#[specta::specta]
+pub async fn get_presets(id: String, app: AppHandle) -> AppResult<Vec<PreferredState>> {
+    app::with_device(&app, &id, |mut device| async move {
+        device.try_into_presets()?.get_presets().await
+    })
+    .await
+}
+
+/// Overwrite the preset in slot `preset.index`
+#[tauri::command]
+#[specta::specta]
+pub async fn save_preset(id: String, preset: PreferredState, app: AppHandle) -> AppResult<()> {
+    app::with_device(&app, &id, |mut device| {
+        let preset = preset.clone();
+        async move { device.try_into_presets()?.save_preset(&preset).await }
+    })
+    .await
+}
+
+/// Switch a dimmer or bulb on with the preset in slot `index`
+#[tauri::command]
+#[specta::specta]
+pub async fn apply_preset(id: String, index: u8, app: AppHandle) -> AppResult<PreferredState> {
+    app::with_device(&app, &id, |mut device| async move {
+        device.try_into_presets()?.apply_preset(index).await
+    })
+    .await
+}
+
+/// Switch every dimmer and bulb on with its preset in slot `index`
+#[tauri::command]
+#[specta::specta]
+pub async fn apply_preset_everywhere(index: u8, app: AppHandle) -> Vec<DeviceOutcome> {
+    app::presets::apply_presets(&app, index).await
+}
@@ -0,0 +1,41 @@
+use crate::app::{
+    scenes::{self, Scene},
+    settings, AppResult, DeviceOutcome,
+};
+use tauri::AppHandle;
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_scenes(app: AppHandle) -> AppResult<Vec<Scene>> {
+    settings::load_scenes(&app)
+}
+
+/// Save the current state of the devices with `ids` as the scene `name`,
+/// replacing any scene with that name
+#[tauri::command]
+#[specta::specta]
+pub async fn capture_scene(name: String, ids: Vec<String>, app: AppHandle) -> AppResult<Scene> {
+    let scene = scenes::capture(&app, name, ids).await?;
+    scenes::save(&app, scene.clone())?;
+    Ok(scene)
+}
+
+/// Save an edited scene, replacing any scene with the same name
+#[tauri::command]
+#[specta::specta]
+pub fn save_scene(scene: Scene, app: AppHandle) -> AppResult<()> {
+    scenes::save(&app, scene)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn delete_scene(name: String, app: AppHandle) -> AppResult<()> {
+    scenes::delete(&app, &name)
+}
+
+/// Recall a scene, reporting how each of its devices went
+#[tauri::command]
+#[specta::specta]
+pub async fn apply_scene(name: String, app: AppHandle) -> AppResult<Vec<DeviceOutcome>> {
+    scenes::apply(&app, &name).await
+}
@@ -0,0 +1,94 @@
+use crate::{
+    app::{self, AppResult},
+    tplink::{
+        models::{NextAction, ScheduleRule, ScheduleRules},
+        prelude::*,
+    },
+};
+use tauri::AppHandle;
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_schedule_rules(id: String, app: AppHandle) -> AppResult<ScheduleRules> {
+    app::with_device(&app, &id, |device| async move { device.get_rules().await }).await
+}
+
+/// Add a rule and return the id the device gave it
+#[tauri::command]
+#[specta::specta]
+pub async fn add_schedule_rule(
+    id: String,
+    rule: ScheduleRule,
+    app: AppHandle,
+) -> AppResult<String> {
+    app::with_device(&app, &id, |mut device| {
+        let rule = rule.clone();
+        async move { device.add_rule(&rule).await }
+    })
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn edit_schedule_rule(id: String, rule: ScheduleRule, app: AppHandle) -> AppResult<()> {
+    app::with_device(&app, &id, |mut device| {
+        let rule = rule.clone();
+        async move { device.edit_rule(&rule).await }
+    })
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn delete_schedule_rule(id: String, rule_id: String, app: AppHandle) -> AppResult<()> {
+    app::with_device(&app, &id, |mut device| {
+        let rule_id = rule_id.clone();
+        async move { device.delete_rule(&rule_id).await }
+    })
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn delete_all_schedule_rules(id: String, app: AppHandle) -> AppResult<()> {
+    app::with_device(&app, &id, |mut device| async move {
+        device.delete_all_rules().await
+    })
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_schedule_rule_enabled(
+    id: String,
+    rule_id: String,
+    enabled: bool,
+    app: AppHandle,
+) -> AppResult<()> {
+    app::with_device(&app, &id, |mut device| {
+        let rule_id = rule_id.clone();
+        async move { device.set_rule_enabled(&rule_id, enabled).await }
+    })
+    .await
+}
+
+/// Enable or disable every rule at once, keeping the rules themselves
+#[tauri::command]
+#[specta::specta]
+pub async fn set_schedule_enabled(id: String, enabled: bool, app: AppHandle) -> AppResult<()> {
+    app::with_device(&app, &id, |mut device| async move {
+        device.set_rules_enabled(enabled).await
+    })
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_next_action(id: String, app: AppHandle) -> AppResult<NextAction> {
+    app::with_device(
+        &app,
+        &id,
+        |device| async move { device.get_next_action().await },
+    )
+    .await
+}
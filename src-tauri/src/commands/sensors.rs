@@ -0,0 +1,109 @@
+use crate::{
+    app::{self, AppResult},
+    tplink::{
+        models::{AmbientLightConfig, MotionConfig, MotionRange},
+        prelude::*,
+    },
+};
+use serde::Deserialize;
+use std::time::Duration;
+use tauri::AppHandle;
+
+/// A single setting of a motion sensor's [`MotionConfig`]
+#[derive(Debug, Deserialize, Clone, Copy, specta::Type)]
+#[serde(tag = "setting", content = "value", rename_all = "camelCase")]
+pub enum MotionSetting {
+    Enabled(bool),
+    Range(MotionRange),
+    /// Custom threshold in percent, which also selects the custom range
+    Threshold(u8),
+    /// In milliseconds
+    ColdTime(u32),
+}
+
+/// A single setting of an ambient light sensor's [`AmbientLightConfig`]
+#[derive(Debug, Deserialize, Clone, Copy, specta::Type)]
+#[serde(tag = "setting", content = "value", rename_all = "camelCase")]
+pub enum AmbientLightSetting {
+    Enabled(bool),
+    /// Brightness in percent below which motion switches the light on
+    Threshold(u8),
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_motion_config(id: String, app: AppHandle) -> AppResult<MotionConfig> {
+    app::with_device(&app, &id, |mut device| async move {
+        device.try_into_motion_sensor()?.get_motion_config().await
+    })
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_motion_setting(
+    id: String,
+    setting: MotionSetting,
+    app: AppHandle,
+) -> AppResult<()> {
+    app::with_device(&app, &id, |mut device| async move {
+        let sensor = device.try_into_motion_sensor()?;
+        match setting {
+            MotionSetting::Enabled(enabled) => sensor.set_motion_enabled(enabled).await,
+            MotionSetting::Range(range) => sensor.set_motion_range(range).await,
+            MotionSetting::Threshold(threshold) => sensor.set_motion_threshold(threshold).await,
+            MotionSetting::ColdTime(millis) => {
+                sensor
+                    .set_cold_time(Duration::from_millis(millis.into()))
+                    .await
+            }
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_ambient_light_config(id: String, app: AppHandle) -> AppResult<AmbientLightConfig> {
+    app::with_device(&app, &id, |mut device| async move {
+        device
+            .try_into_ambient_light_sensor()?
+            .get_ambient_light_config()
+            .await
+    })
+    .await
+}
+
+/// How bright the room is now, in percent
+#[tauri::command]
+#[specta::specta]
+pub async fn get_ambient_light(id: String, app: AppHandle) -> AppResult<u8> {
+    app::with_device(&app, &id, |mut device| async move {
+        device
+            .try_into_ambient_light_sensor()?
+            .get_ambient_light()
+            .await
+    })
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_ambient_light_setting(
+    id: String,
+    setting: AmbientLightSetting,
+    app: AppHandle,
+) -> AppResult<()> {
+    app::with_device(&app, &id, |mut device| async move {
+        let sensor = device.try_into_ambient_light_sensor()?;
+        match setting {
+            AmbientLightSetting::Enabled(enabled) => {
+                sensor.set_ambient_light_enabled(enabled).await
+            }
+            AmbientLightSetting::Threshold(threshold) => {
+                sensor.set_ambient_light_threshold(threshold).await
+            }
+        }
+    })
+    .await
+}
@@ -0,0 +1,143 @@
+use crate::{
+    app::{self, AppResult},
+    tplink::{
+        models::{AwayModeRule, AwayModeRules, CountdownRule},
+        prelude::*,
+    },
+};
+use std::time::Duration;
+use tauri::AppHandle;
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_countdowns(id: String, app: AppHandle) -> AppResult<Vec<CountdownRule>> {
+    app::with_device(&app, &id, |mut device| async move {
+        device.try_into_countdown()?.get_countdowns().await
+    })
+    .await
+}
+
+/// Add a timer and return the id the device gave it
+#[tauri::command]
+#[specta::specta]
+pub async fn add_countdown(id: String, rule: CountdownRule, app: AppHandle) -> AppResult<String> {
+    app::with_device(&app, &id, |mut device| {
+        let rule = rule.clone();
+        async move { device.try_into_countdown()?.add_countdown(&rule).await }
+    })
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn edit_countdown(id: String, rule: CountdownRule, app: AppHandle) -> AppResult<()> {
+    app::with_device(&app, &id, |mut device| {
+        let rule = rule.clone();
+        async move { device.try_into_countdown()?.edit_countdown(&rule).await }
+    })
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn delete_countdown(id: String, rule_id: String, app: AppHandle) -> AppResult<()> {
+    app::with_device(&app, &id, |mut device| {
+        let rule_id = rule_id.clone();
+        async move {
+            device
+                .try_into_countdown()?
+                .delete_countdown(&rule_id)
+                .await
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn delete_all_countdowns(id: String, app: AppHandle) -> AppResult<()> {
+    app::with_device(&app, &id, |mut device| async move {
+        device.try_into_countdown()?.delete_all_countdowns().await
+    })
+    .await
+}
+
+/// Switch the device on or off in `seconds`, replacing any running timer
+#[tauri::command]
+#[specta::specta]
+pub async fn switch_after(id: String, seconds: u32, on: bool, app: AppHandle) -> AppResult<String> {
+    app::with_device(&app, &id, |mut device| async move {
+        device
+            .try_into_countdown()?
+            .switch_after(Duration::from_secs(seconds.into()), on)
+            .await
+    })
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_away_rules(id: String, app: AppHandle) -> AppResult<AwayModeRules> {
+    app::with_device(&app, &id, |mut device| async move {
+        device.try_into_away_mode()?.get_away_rules().await
+    })
+    .await
+}
+
+/// Add an away mode rule and return the id the device gave it
+#[tauri::command]
+#[specta::specta]
+pub async fn add_away_rule(id: String, rule: AwayModeRule, app: AppHandle) -> AppResult<String> {
+    app::with_device(&app, &id, |mut device| {
+        let rule = rule.clone();
+        async move { device.try_into_away_mode()?.add_away_rule(&rule).await }
+    })
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn edit_away_rule(id: String, rule: AwayModeRule, app: AppHandle) -> AppResult<()> {
+    app::with_device(&app, &id, |mut device| {
+        let rule = rule.clone();
+        async move { device.try_into_away_mode()?.edit_away_rule(&rule).await }
+    })
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn delete_away_rule(id: String, rule_id: String, app: AppHandle) -> AppResult<()> {
+    app::with_device(&app, &id, |mut device| {
+        let rule_id = rule_id.clone();
+        async move {
+            device
+                .try_into_away_mode()?
+                .delete_away_rule(&rule_id)
+                .await
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn delete_all_away_rules(id: String, app: AppHandle) -> AppResult<()> {
+    app::with_device(&app, &id, |mut device| async move {
+        device.try_into_away_mode()?.delete_all_away_rules().await
+    })
+    .await
+}
+
+/// Turn away mode on or off, keeping its rules
+#[tauri::command]
+#[specta::specta]
+pub async fn set_away_mode_enabled(id: String, enabled: bool, app: AppHandle) -> AppResult<()> {
+    app::with_device(&app, &id, |mut device| async move {
+        device
+            .try_into_away_mode()?
+            .set_away_mode_enabled(enabled)
+            .await
+    })
+    .await
+}
@@ -1,65 +1,115 @@
-use crate::tplink::{devices::Device as TpLinkDevice, discover::discover_devices, prelude::*};
-use app::{AppResult, AppState, Device};
-use std::{net::SocketAddr, sync::Mutex};
-use tauri::{Manager, State};
+use app::{
+    events::{DeviceDiscovered, DeviceUpdated, DeviceWentOffline, EnergyReading},
+    poller, settings, AppState,
+};
+use commands::{
+    automations, bulb, clock, devices, dimmer, discovery, energy, groups, presets, scenes,
+    schedule, sensors, timers,
+};
+use std::sync::Mutex;
+use tauri::Manager;
 use tauri_plugin_store::StoreExt;
+pub mod api;
 mod app;
-mod tplink;
+mod commands;
+pub mod mqtt;
+pub use overhead_tplink as tplink;
 
-#[tauri::command]
-#[specta::specta]
-async fn discover(state: State<'_, Mutex<AppState>>) -> AppResult<Vec<Device>> {
-    let mut state = state.lock().unwrap();
-
-    discover_devices()
-        .map(|resps| {
-            resps
-                .into_iter()
-                .map(|(addr, resp)| {
-                    let model = resp.sysinfo().model.clone();
-                    state.models.insert(addr, model);
-                    (addr, resp).into()
-                })
-                .collect()
-        })
-        .map_err(|err| err.into())
-}
-
-#[tauri::command]
-#[specta::specta]
-fn set_brightness(
-    socket_addr: SocketAddr,
-    brightness: u8,
-    state: State<'_, Mutex<AppState>>,
-) -> AppResult<()> {
-    let state = state.lock().unwrap();
-    let model = state.get_model(socket_addr)?;
-
-    TpLinkDevice::try_new(socket_addr, &model)?
-        .try_into_dimmable()
-        .and_then(|d| d.set_brightness(brightness))
-        .map_err(|err| err.into())
-}
-
-#[tauri::command]
-#[specta::specta]
-fn toggle(socket_addr: SocketAddr, state: State<'_, Mutex<AppState>>) -> AppResult<bool> {
-    let state = state.lock().unwrap();
-    let model = state.get_model(socket_addr)?;
-    TpLinkDevice::try_new(socket_addr, &model)?
-        .toggle()
-        .map_err(|err| err.into())
+fn specta_builder() -> tauri_specta::Builder {
+    tauri_specta::Builder::<tauri::Wry>::new()
+        .commands(tauri_specta::collect_commands![
+            discovery::discover,
+            discovery::get_discovery_config,
+            discovery::set_discovery_config,
+            devices::registered_devices,
+            devices::forget_device,
+            devices::toggle,
+            devices::set_brightness,
+            devices::get_device_snapshot,
+            devices::probe_device,
+            dimmer::set_switch_state,
+            dimmer::get_dimmer_parameters,
+            dimmer::set_dimmer_parameter,
+            dimmer::get_default_behavior,
+            dimmer::set_button_action,
+            dimmer::set_dimmer_transition,
+            sensors::get_motion_config,
+            sensors::set_motion_setting,
+            sensors::get_ambient_light_config,
+            sensors::get_ambient_light,
+            sensors::set_ambient_light_setting,
+            bulb::get_light_state,
+            bulb::transition_light_state,
+            bulb::get_light_details,
+            bulb::get_light_default_behavior,
+            energy::get_emeter_realtime,
+            energy::get_emeter_daily_stats,
+            energy::get_emeter_monthly_stats,
+            energy::erase_emeter_stats,
+            schedule::get_schedule_rules,
+            schedule::add_schedule_rule,
+            schedule::edit_schedule_rule,
+            schedule::delete_schedule_rule,
+            schedule::delete_all_schedule_rules,
+            schedule::set_schedule_rule_enabled,
+            schedule::set_schedule_enabled,
+            schedule::get_next_action,
+            timers::get_countdowns,
+            timers::add_countdown,
+            timers::edit_countdown,
+            timers::delete_countdown,
+            timers::delete_all_countdowns,
+            timers::switch_after,
+            timers::get_away_rules,
+            timers::add_away_rule,
+            timers::edit_away_rule,
+            timers::delete_away_rule,
+            timers::delete_all_away_rules,
+            timers::set_away_mode_enabled,
+            clock::get_device_time,
+            clock::get_device_timezone,
+            clock::set_device_timezone,
+            clock::sync_device_clocks,
+            presets::set_led,
+            presets::set_all_leds,
+            presets::get_presets,
+            presets::save_preset,
+            presets::apply_preset,
+            presets::apply_preset_everywhere,
+            scenes::get_scenes,
+            scenes::capture_scene,
+            scenes::save_scene,
+            scenes::delete_scene,
+            scenes::apply_scene,
+            groups::get_groups,
+            groups::save_group,
+            groups::delete_group,
+            groups::get_group_state,
+            groups::switch_group,
+            groups::toggle_group,
+            groups::set_group_brightness,
+            automations::get_automations,
+            automations::save_automation,
+            automations::delete_automation,
+            automations::run_automation,
+            automations::get_next_automation_run,
+            automations::get_automation_history,
+            commands::mqtt::get_mqtt_config,
+            commands::mqtt::set_mqtt_config,
+            commands::api::get_api_config,
+            commands::api::set_api_config
+        ])
+        .events(tauri_specta::collect_events![
+            DeviceDiscovered,
+            DeviceUpdated,
+            DeviceWentOffline,
+            EnergyReading
+        ])
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    let specta_builder = tauri_specta::Builder::<tauri::Wry>::new()
-        .commands(tauri_specta::collect_commands![
-            discover,
-            set_brightness,
-            toggle
-        ])
-        .events(tauri_specta::collect_events![]);
+    let specta_builder = specta_builder();
 
     #[cfg(debug_assertions)]
     specta_builder
@@ -82,25 +132,94 @@ pub fn run() {
         )
         .plugin(tauri_plugin_shell::init())
         .invoke_handler(specta_builder.invoke_handler())
-        .invoke_handler(tauri::generate_handler![discover, set_brightness, toggle])
         .setup(move |app| {
             specta_builder.mount_events(app);
 
-            app.manage(Mutex::new(AppState::default()));
+            let _store = app.store(settings::STORE_PATH)?;
+
+            app.manage(Mutex::new(AppState {
+                registry: settings::load_registry(app.handle())?,
+                ..Default::default()
+            }));
 
-            let _store = app.store("store.json")?;
+            poller::spawn(app.handle().clone());
+            app::automations::spawn(app.handle().clone());
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(err) = app::mqtt::restart(&handle).await {
+                    log::error!("Failed to start the MQTT bridge {:?}", err);
+                }
+                if let Err(err) = api::restart(&handle).await {
+                    log::error!("Failed to start the API server {:?}", err);
+                }
+            });
 
             #[cfg(desktop)]
             {
-                use tauri::menu::{Menu, MenuItem};
+                use tauri::menu::{Menu, MenuItem, Submenu};
+
+                let presets = (0..4)
+                    .map(|index| {
+                        let label = format!("Preset {}", index + 1);
+                        MenuItem::with_id(
+                            app,
+                            format!("preset:{}", index),
+                            label,
+                            true,
+                            None::<&str>,
+                        )
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                let presets = presets
+                    .iter()
+                    .map(|item| item as &dyn tauri::menu::IsMenuItem<_>)
+                    .collect::<Vec<_>>();
 
                 // System tray
                 tauri::tray::TrayIconBuilder::new()
                     .icon(app.default_window_icon().unwrap().clone())
                     .menu(&Menu::with_items(
                         app,
-                        &[&MenuItem::with_id(app, "quit", "Test", true, None::<&str>)?],
+                        &[
+                            &Submenu::with_items(app, "Presets", true, &presets)?,
+                            &Submenu::with_items(
+                                app,
+                                "Status lights",
+                                true,
+                                &[
+                                    &MenuItem::with_id(app, "leds:on", "On", true, None::<&str>)?,
+                                    &MenuItem::with_id(app, "leds:off", "Off", true, None::<&str>)?,
+                                ],
+                            )?,
+                            &MenuItem::with_id(app, "quit", "Test", true, None::<&str>)?,
+                        ],
                     )?)
+                    .on_menu_event(|app, event| {
+                        let app = app.clone();
+                        let id = event.id().as_ref().to_string();
+                        tauri::async_runtime::spawn(async move {
+                            let outcomes = match id.split_once(':') {
+                                Some(("preset", index)) => match index.parse() {
+                                    Ok(index) => app::presets::apply_presets(&app, index).await,
+                                    Err(_) => return,
+                                },
+                                Some(("leds", on)) => {
+                                    app::presets::set_leds(&app, on == "on").await
+                                }
+                                _ => return,
+                            };
+                            for outcome in outcomes {
+                                if let Some(err) = outcome.error {
+                                    log::warn!(
+                                        "{} from the tray failed for {}: {}",
+                                        id,
+                                        outcome.name,
+                                        err
+                                    );
+                                }
+                            }
+                        });
+                    })
                     // .menu_on_left_click(true)
                     // .m
                     .build(app)?;
@@ -0,0 +1,441 @@
+//! Bridges devices to MQTT in the form Home Assistant discovers: each device
+//! or outlet is announced as a `switch`, or a `light` with brightness if it
+//! dims, commands sent to it are carried out, and its state is published
+//! whenever it changes. The poller feeds the bridge what it sees with
+//! [`MqttBridge::update`].
+//!
+//! Topics, with the default prefixes:
+//! - `homeassistant/{switch,light}/<id>/config`: discovery, retained
+//! - `overhead/<id>/state`: `ON`/`OFF` for switches, JSON for lights
+//! - `overhead/<id>/set`: commands, in the same form as the state
+//! - `overhead/status`: `online` while the bridge is connected
+use crate::tplink::{devices::Device, error::TpResult, models::PowerState};
+use log::*;
+use rumqttc::{AsyncClient, ClientError, Event, LastWill, MqttOptions, Packet, QoS};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+/// How long to wait before reconnecting to a broker that went away
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Where to find the broker and which topics to use
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MqttConfig {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Home Assistant's discovery prefix
+    pub discovery_prefix: String,
+    /// Prefix of the state, command and availability topics
+    pub base_topic: String,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "localhost".to_string(),
+            port: 1883,
+            username: None,
+            password: None,
+            discovery_prefix: "homeassistant".to_string(),
+            base_topic: "overhead".to_string(),
+        }
+    }
+}
+
+impl MqttConfig {
+    pub fn availability_topic(&self) -> String {
+        format!("{}/status", self.base_topic)
+    }
+
+    pub fn state_topic(&self, id: &str) -> String {
+        format!("{}/{}/state", self.base_topic, id)
+    }
+
+    pub fn command_topic(&self, id: &str) -> String {
+        format!("{}/{}/set", self.base_topic, id)
+    }
+
+    pub fn discovery_topic(&self, device: &MqttDevice) -> String {
+        format!(
+            "{}/{}/{}/config",
+            self.discovery_prefix,
+            device.component(),
+            device.id
+        )
+    }
+
+    /// The id of the device a command topic addresses
+    fn command_id<'a>(&self, topic: &'a str) -> Option<&'a str> {
+        topic
+            .strip_prefix(&self.base_topic)?
+            .strip_prefix('/')?
+            .strip_suffix("/set")
+            .filter(|id| !id.is_empty() && !id.contains('/'))
+    }
+}
+
+/// A device or outlet as the bridge exposes it
+#[derive(Debug, Clone)]
+pub struct MqttDevice {
+    /// Device id, or an outlet's full id
+    pub id: String,
+    pub name: String,
+    pub model: String,
+    /// Whether it is a dimmer or bulb, announced as a light with brightness
+    pub dimmable: bool,
+    pub device: Device,
+}
+
+impl MqttDevice {
+    pub fn new(id: String, name: String, model: String, mut device: Device) -> Self {
+        Self {
+            dimmable: device.try_into_dimmable().is_ok() || device.try_into_smart_bulb().is_ok(),
+            id,
+            name,
+            model,
+            device,
+        }
+    }
+
+    /// The Home Assistant platform it is announced as
+    pub fn component(&self) -> &'static str {
+        if self.dimmable {
+            "light"
+        } else {
+            "switch"
+        }
+    }
+
+    /// The discovery payload for this device
+    pub fn discovery(&self, config: &MqttConfig) -> serde_json::Value {
+        let mut payload = json!({
+            "name": null,
+            "unique_id": format!("overhead_{}", self.id),
+            "object_id": self.name,
+            "command_topic": config.command_topic(&self.id),
+            "state_topic": config.state_topic(&self.id),
+            "availability_topic": config.availability_topic(),
+            "device": {
+                "identifiers": [self.id],
+                "name": self.name,
+                "model": self.model,
+                "manufacturer": "TP-Link",
+            },
+        });
+        if self.dimmable {
+            payload["schema"] = json!("json");
+            payload["brightness"] = json!(true);
+            payload["brightness_scale"] = json!(100);
+            payload["supported_color_modes"] = json!(["brightness"]);
+        }
+        payload
+    }
+
+    /// How `state` is published to the state topic
+    pub fn state_payload(&self, state: &PowerState) -> String {
+        let on_off = if state.on { "ON" } else { "OFF" };
+        if self.dimmable {
+            let mut payload = json!({ "state": on_off });
+            if let Some(brightness) = state.brightness {
+                payload["brightness"] = json!(brightness);
+            }
+            payload.to_string()
+        } else {
+            on_off.to_string()
+        }
+    }
+}
+
+/// A command from a command topic
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MqttCommand {
+    /// `None` for a light command that only changes brightness
+    pub on: Option<bool>,
+    pub brightness: Option<u8>,
+}
+
+impl MqttCommand {
+    /// Parse `ON`/`OFF`, or a JSON light command such as
+    /// `{"state": "ON", "brightness": 40}`
+    pub fn parse(payload: &[u8]) -> Option<Self> {
+        let on_off = |state: &str| match state {
+            "ON" => Some(true),
+            "OFF" => Some(false),
+            _ => None,
+        };
+        let payload = std::str::from_utf8(payload).ok()?.trim();
+        if let Some(on) = on_off(payload) {
+            return Some(Self {
+                on: Some(on),
+                brightness: None,
+            });
+        }
+
+        #[derive(Deserialize)]
+        struct LightCommand {
+            state: Option<String>,
+            brightness: Option<u8>,
+        }
+        let command: LightCommand = serde_json::from_str(payload).ok()?;
+        let on = match command.state {
+            Some(state) => Some(on_off(&state)?),
+            None => None,
+        };
+        Some(Self {
+            on,
+            brightness: command.brightness,
+        })
+    }
+
+    /// Carry out the command. A brightness without a state switches the
+    /// light on at that brightness, as Home Assistant expects.
+    pub async fn execute(&self, device: &mut Device) -> TpResult<()> {
+        let on = match (self.on, self.brightness) {
+            (Some(on), _) => on,
+            (None, Some(_)) => true,
+            (None, None) => return Ok(()),
+        };
+        let state = PowerState {
+            on,
+            brightness: self.brightness,
+        };
+        device.set_power_state(&state).await
+    }
+}
+
+/// A bridged device and the state last published for it
+struct Bridged {
+    device: MqttDevice,
+    state: PowerState,
+}
+
+/// A connection to the broker. Commands are handled in the background until
+/// [`MqttBridge::stop`]. Clones share the connection.
+#[derive(Clone)]
+pub struct MqttBridge {
+    client: AsyncClient,
+    config: MqttConfig,
+    devices: Arc<Mutex<HashMap<String, Bridged>>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl MqttBridge {
+    /// Connect to the broker. Devices are announced as they are added with
+    /// [`MqttBridge::update`], and all of them again after every reconnect.
+    /// Must be called from within a tokio runtime.
+    pub fn start(config: MqttConfig) -> Self {
+        static BRIDGES: AtomicUsize = AtomicUsize::new(0);
+        // Brokers drop a session when another connects with its client id,
+        // so each bridge needs its own
+        let client_id = format!(
+            "overhead-{}-{}",
+            std::process::id(),
+            BRIDGES.fetch_add(1, Ordering::Relaxed)
+        );
+        let mut options = MqttOptions::new(client_id, &config.host, config.port);
+        options
+            .set_keep_alive(Duration::from_secs(30))
+            .set_last_will(LastWill::new(
+                config.availability_topic(),
+                "offline",
+                QoS::AtLeastOnce,
+                true,
+            ));
+        if let Some(username) = &config.username {
+            options.set_credentials(username, config.password.clone().unwrap_or_default());
+        }
+
+        let (client, mut eventloop) = AsyncClient::new(options, 64);
+        let bridge = Self {
+            client,
+            config,
+            devices: Default::default(),
+            stopped: Arc::new(AtomicBool::new(false)),
+        };
+
+        let handler = bridge.clone();
+        tokio::spawn(async move {
+            loop {
+                match eventloop.poll().await {
+                    Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                        info!("Connected to MQTT broker {}", handler.config.host);
+                        let handler = handler.clone();
+                        tokio::spawn(async move { handler.announce().await });
+                    }
+                    Ok(Event::Incoming(Packet::Publish(publish))) => {
+                        let handler = handler.clone();
+                        tokio::spawn(async move {
+                            handler.handle(&publish.topic, &publish.payload).await
+                        });
+                    }
+                    Ok(_) => {}
+                    Err(_) if handler.stopped.load(Ordering::Relaxed) => break,
+                    Err(err) => {
+                        warn!("MQTT connection failed: {}", err);
+                        tokio::time::sleep(RECONNECT_DELAY).await;
+                    }
+                }
+            }
+        });
+
+        bridge
+    }
+
+    pub fn config(&self) -> &MqttConfig {
+        &self.config
+    }
+
+    /// Bridge `device` in `state`, announcing it if it is new or was renamed
+    /// and publishing its state if that changed
+    pub async fn update(&self, device: MqttDevice, state: PowerState) -> Result<(), ClientError> {
+        let (announce, publish) = {
+            let mut devices = self.devices.lock().unwrap();
+            let bridged = devices.get(&device.id);
+            let announce = bridged.is_none_or(|bridged| {
+                bridged.device.name != device.name || bridged.device.dimmable != device.dimmable
+            });
+            let publish = announce || bridged.is_none_or(|bridged| bridged.state != state);
+            devices.insert(
+                device.id.clone(),
+                Bridged {
+                    device: device.clone(),
+                    state,
+                },
+            );
+            (announce, publish)
+        };
+
+        if announce {
+            self.publish_discovery(&device).await?;
+        }
+        if publish {
+            self.publish_state(&device, &state).await?;
+        }
+        Ok(())
+    }
+
+    /// Mark the bridge offline and disconnect
+    pub async fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        let _ = self
+            .client
+            .publish(
+                self.config.availability_topic(),
+                QoS::AtLeastOnce,
+                true,
+                "offline",
+            )
+            .await;
+        let _ = self.client.disconnect().await;
+    }
+
+    async fn publish_state(
+        &self,
+        device: &MqttDevice,
+        state: &PowerState,
+    ) -> Result<(), ClientError> {
+        self.client
+            .publish(
+                self.config.state_topic(&device.id),
+                QoS::AtLeastOnce,
+                true,
+                device.state_payload(state),
+            )
+            .await
+    }
+
+    async fn publish_discovery(&self, device: &MqttDevice) -> Result<(), ClientError> {
+        self.client
+            .publish(
+                self.config.discovery_topic(device),
+                QoS::AtLeastOnce,
+                true,
+                device.discovery(&self.config).to_string(),
+            )
+            .await
+    }
+
+    /// Subscribe to commands and publish discovery and the last known state
+    /// of every device
+    async fn announce(&self) {
+        let subscribed = self
+            .client
+            .subscribe(self.config.command_topic("+"), QoS::AtLeastOnce)
+            .await;
+        if let Err(err) = subscribed {
+            return error!("Failed to subscribe to MQTT commands {:?}", err);
+        }
+
+        let devices = self
+            .devices
+            .lock()
+            .unwrap()
+            .values()
+            .map(|bridged| (bridged.device.clone(), bridged.state))
+            .collect::<Vec<_>>();
+        for (device, state) in &devices {
+            let published = match self.publish_discovery(device).await {
+                Ok(()) => self.publish_state(device, state).await,
+                Err(err) => Err(err),
+            };
+            if let Err(err) = published {
+                warn!("Failed to announce {} over MQTT {:?}", device.id, err);
+            }
+        }
+        let _ = self
+            .client
+            .publish(
+                self.config.availability_topic(),
+                QoS::AtLeastOnce,
+                true,
+                "online",
+            )
+            .await;
+    }
+
+    /// Carry out a command and publish the state the device ends up in
+    async fn handle(&self, topic: &str, payload: &[u8]) {
+        let Some(id) = self.config.command_id(topic) else {
+            return;
+        };
+        let Some(bridged) = self
+            .devices
+            .lock()
+            .unwrap()
+            .get(id)
+            .map(|bridged| bridged.device.clone())
+        else {
+            return debug!("MQTT command for unknown device {}", id);
+        };
+        let Some(command) = MqttCommand::parse(payload) else {
+            return warn!("Ignoring malformed MQTT command for {}", id);
+        };
+
+        let mut device = bridged.device.clone();
+        let result = match command.execute(&mut device).await {
+            Ok(()) => device.get_power_state().await,
+            Err(err) => Err(err),
+        };
+        match result {
+            Ok(state) => {
+                if let Err(err) = self.update(bridged, state).await {
+                    warn!("Failed to publish the state of {} {:?}", id, err);
+                }
+            }
+            Err(err) => warn!("MQTT command for {} failed: {}", id, err),
+        }
+    }
+}
@@ -1,11 +0,0 @@
-pub mod capabilities;
-pub mod devices;
-pub mod discover;
-pub mod error;
-pub mod models;
-pub mod protocol;
-pub mod requests;
-
-pub mod prelude {
-    pub use super::capabilities::*;
-}
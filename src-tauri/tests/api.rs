@@ -0,0 +1,42 @@
+use axum::{routing::get, Router};
+use overhead_lib::api::ApiServer;
+use std::net::{Ipv4Addr, SocketAddr};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+async fn get_root(addr: SocketAddr) -> String {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    response
+}
+
+#[tokio::test]
+async fn restarts_on_the_same_port() {
+    // Find a free port, then keep using it
+    let port = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let router = Router::new().route("/", get(|| async { "served" }));
+
+    let mut server = ApiServer::start(addr, router.clone()).await.unwrap();
+    for _ in 0..2 {
+        // A client still connected when the server stops mustn't keep the
+        // port from being bound again
+        let idle = TcpStream::connect(addr).await.unwrap();
+        server.stop().await;
+        drop(idle);
+        server = ApiServer::start(addr, router.clone()).await.unwrap();
+        assert!(get_root(addr).await.ends_with("served"));
+    }
+    server.stop().await;
+}
@@ -0,0 +1,227 @@
+//! Just enough of an MQTT 3.1.1 broker to test the bridge against: clients
+//! can connect, subscribe with wildcards and publish at QoS 0 or 1.
+//! Everything is delivered at QoS 0, and retained messages are kept so tests
+//! can wait for what the bridge published.
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{tcp::OwnedReadHalf, TcpListener},
+    sync::mpsc::{unbounded_channel, UnboundedSender},
+};
+
+#[derive(Default)]
+struct State {
+    retained: BTreeMap<String, Vec<u8>>,
+    /// (client, topic filter, outbox)
+    subscriptions: Vec<(usize, String, UnboundedSender<Vec<u8>>)>,
+    clients: usize,
+}
+
+pub struct Broker {
+    pub port: u16,
+    state: Arc<Mutex<State>>,
+}
+
+impl Broker {
+    /// Listen on a free loopback port
+    pub async fn spawn() -> Broker {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("broker should bind");
+        let port = listener.local_addr().unwrap().port();
+        let state = Arc::new(Mutex::new(State::default()));
+
+        let shared = state.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let state = shared.clone();
+                let client = {
+                    let mut state = state.lock().unwrap();
+                    state.clients += 1;
+                    state.clients
+                };
+                let (reader, mut writer) = stream.into_split();
+                let (outbox, mut outgoing) = unbounded_channel::<Vec<u8>>();
+                tokio::spawn(async move {
+                    while let Some(packet) = outgoing.recv().await {
+                        if writer.write_all(&packet).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+                tokio::spawn(async move {
+                    serve(client, reader, outbox, &state).await;
+                    state
+                        .lock()
+                        .unwrap()
+                        .subscriptions
+                        .retain(|(c, ..)| *c != client);
+                });
+            }
+        });
+
+        Broker { port, state }
+    }
+
+    /// Deliver a message to every subscriber, as a client would
+    pub fn publish(&self, topic: &str, payload: &str) {
+        route(&self.state, topic, payload.as_bytes(), false);
+    }
+
+    /// Wait for a retained message on `topic` that satisfies `accept`
+    pub async fn retained(&self, topic: &str, accept: impl Fn(&str) -> bool) -> String {
+        for _ in 0..100 {
+            let message = self.state.lock().unwrap().retained.get(topic).cloned();
+            if let Some(message) = message.map(|m| String::from_utf8(m).unwrap()) {
+                if accept(&message) {
+                    return message;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        panic!("nothing acceptable was retained on {}", topic);
+    }
+
+    /// Wait until some client subscribes to `filter`
+    pub async fn subscribed(&self, filter: &str) {
+        for _ in 0..100 {
+            let subscribed = self
+                .state
+                .lock()
+                .unwrap()
+                .subscriptions
+                .iter()
+                .any(|(_, f, _)| f == filter);
+            if subscribed {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        panic!("nobody subscribed to {}", filter);
+    }
+}
+
+async fn serve(
+    client: usize,
+    mut reader: OwnedReadHalf,
+    outbox: UnboundedSender<Vec<u8>>,
+    state: &Mutex<State>,
+) {
+    while let Some((header, body)) = read_packet(&mut reader).await {
+        match header >> 4 {
+            // CONNECT
+            1 => outbox.send(vec![0x20, 2, 0, 0]).ok(),
+            // PUBLISH
+            3 => {
+                let qos = (header >> 1) & 3;
+                let (topic, rest) = string(&body);
+                let (id, payload) = match qos {
+                    0 => (None, rest),
+                    _ => (Some(&rest[..2]), &rest[2..]),
+                };
+                route(state, &topic, payload, header & 1 == 1);
+                id.and_then(|id| outbox.send(vec![0x40, 2, id[0], id[1]]).ok())
+            }
+            // SUBSCRIBE
+            8 => {
+                let mut rest = &body[2..];
+                let mut filters = vec![];
+                while !rest.is_empty() {
+                    let (filter, tail) = string(rest);
+                    filters.push(filter);
+                    rest = &tail[1..];
+                }
+                let mut suback = vec![0x90, 2 + filters.len() as u8, body[0], body[1]];
+                suback.extend(filters.iter().map(|_| 0));
+                outbox.send(suback).ok();
+
+                let mut state = state.lock().unwrap();
+                for filter in filters {
+                    for (topic, payload) in &state.retained {
+                        if matches(&filter, topic) {
+                            outbox.send(publish_packet(topic, payload, true)).ok();
+                        }
+                    }
+                    state.subscriptions.push((client, filter, outbox.clone()));
+                }
+                Some(())
+            }
+            // PINGREQ
+            12 => outbox.send(vec![0xD0, 0]).ok(),
+            // DISCONNECT
+            14 => return,
+            _ => Some(()),
+        };
+    }
+}
+
+fn route(state: &Mutex<State>, topic: &str, payload: &[u8], retain: bool) {
+    let mut state = state.lock().unwrap();
+    if retain {
+        state.retained.insert(topic.to_string(), payload.to_vec());
+    }
+    for (_, filter, outbox) in &state.subscriptions {
+        if matches(filter, topic) {
+            outbox.send(publish_packet(topic, payload, false)).ok();
+        }
+    }
+}
+
+async fn read_packet(reader: &mut OwnedReadHalf) -> Option<(u8, Vec<u8>)> {
+    let header = reader.read_u8().await.ok()?;
+    let mut length = 0usize;
+    for shift in (0..28).step_by(7) {
+        let byte = reader.read_u8().await.ok()?;
+        length |= usize::from(byte & 0x7F) << shift;
+        if byte & 0x80 == 0 {
+            break;
+        }
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await.ok()?;
+    Some((header, body))
+}
+
+/// A length prefixed string and what follows it
+fn string(bytes: &[u8]) -> (String, &[u8]) {
+    let length = usize::from(u16::from_be_bytes([bytes[0], bytes[1]]));
+    let text = String::from_utf8_lossy(&bytes[2..2 + length]).to_string();
+    (text, &bytes[2 + length..])
+}
+
+fn publish_packet(topic: &str, payload: &[u8], retain: bool) -> Vec<u8> {
+    let mut body = (topic.len() as u16).to_be_bytes().to_vec();
+    body.extend(topic.as_bytes());
+    body.extend(payload);
+
+    let mut packet = vec![0x30 | u8::from(retain)];
+    let mut length = body.len();
+    loop {
+        let byte = (length % 128) as u8;
+        length /= 128;
+        packet.push(if length > 0 { byte | 0x80 } else { byte });
+        if length == 0 {
+            break;
+        }
+    }
+    packet.extend(body);
+    packet
+}
+
+/// Whether `topic` matches a filter with `+` and `#` wildcards
+fn matches(filter: &str, topic: &str) -> bool {
+    let mut topic = topic.split('/');
+    for level in filter.split('/') {
+        match (level, topic.next()) {
+            ("#", _) => return true,
+            ("+", Some(_)) => {}
+            (level, Some(part)) if level == part => {}
+            _ => return false,
+        }
+    }
+    topic.next().is_none()
+}
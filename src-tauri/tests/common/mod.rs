@@ -0,0 +1,24 @@
+#![allow(dead_code)]
+pub mod broker;
+
+use overhead_lib::tplink::simulator::{load_fixtures, Simulator};
+use std::net::{IpAddr, Ipv4Addr};
+
+/// Spawn the device with the given model from `devices.json` or the
+/// fixtures `overhead-tplink` is tested with
+pub async fn spawn_model(model: &str) -> Simulator {
+    let root = env!("CARGO_MANIFEST_DIR");
+    let fixtures = std::fs::read_dir(format!("{}/tplink/tests/fixtures", root))
+        .expect("fixtures directory should exist")
+        .map(|entry| entry.unwrap().path().display().to_string());
+
+    let device = std::iter::once(format!("{}/../devices.json", root))
+        .chain(fixtures)
+        .flat_map(|path| load_fixtures(path).expect("fixtures should load"))
+        .find(|d| d.sysinfo()["model"].as_str().unwrap().starts_with(model))
+        .expect("model should be in the fixtures");
+
+    Simulator::spawn(device, IpAddr::V4(Ipv4Addr::LOCALHOST), 0)
+        .await
+        .expect("simulator should bind")
+}
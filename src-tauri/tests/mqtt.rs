@@ -0,0 +1,167 @@
+mod common;
+
+use common::broker::Broker;
+use overhead_lib::{
+    mqtt::{MqttBridge, MqttCommand, MqttConfig, MqttDevice},
+    tplink::{devices::Device, models::PowerState, simulator::Simulator},
+};
+use serde_json::{json, Value};
+
+const OFF: PowerState = PowerState {
+    on: false,
+    brightness: None,
+};
+
+fn config(broker: &Broker) -> MqttConfig {
+    MqttConfig {
+        enabled: true,
+        host: "127.0.0.1".to_string(),
+        port: broker.port,
+        ..Default::default()
+    }
+}
+
+/// Bridge a simulated device the way the poller would
+async fn bridge(bridge: &MqttBridge, sim: &Simulator, model: &str) -> String {
+    let id = sim.device().sysinfo()["deviceId"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let device = Device::try_new(sim.addr, model).unwrap();
+    let bridged = MqttDevice::new(id.clone(), model.to_string(), model.to_string(), device);
+    bridge.update(bridged, OFF).await.unwrap();
+    id
+}
+
+#[tokio::test]
+async fn announces_dimmers_as_lights_and_plugs_as_switches() {
+    let broker = Broker::spawn().await;
+    let dimmer = common::spawn_model("HS220").await;
+    let plug = common::spawn_model("KP115").await;
+    let mqtt = MqttBridge::start(config(&broker));
+    broker.subscribed("overhead/+/set").await;
+
+    let dimmer_id = bridge(&mqtt, &dimmer, "HS220(US)").await;
+    let light: Value = serde_json::from_str(
+        &broker
+            .retained(&format!("homeassistant/light/{dimmer_id}/config"), |_| true)
+            .await,
+    )
+    .unwrap();
+    assert_eq!(light["schema"], "json");
+    assert_eq!(light["brightness_scale"], 100);
+    assert_eq!(light["command_topic"], format!("overhead/{dimmer_id}/set"));
+    assert_eq!(light["device"]["model"], "HS220(US)");
+
+    let plug_id = bridge(&mqtt, &plug, "KP115(US)").await;
+    let switch: Value = serde_json::from_str(
+        &broker
+            .retained(&format!("homeassistant/switch/{plug_id}/config"), |_| true)
+            .await,
+    )
+    .unwrap();
+    assert_eq!(switch["state_topic"], format!("overhead/{plug_id}/state"));
+    assert_eq!(switch["availability_topic"], "overhead/status");
+
+    broker.retained("overhead/status", |s| s == "online").await;
+    broker
+        .retained(&format!("overhead/{plug_id}/state"), |s| s == "OFF")
+        .await;
+    let state = broker
+        .retained(&format!("overhead/{dimmer_id}/state"), |_| true)
+        .await;
+    assert_eq!(
+        serde_json::from_str::<Value>(&state).unwrap(),
+        json!({"state": "OFF"})
+    );
+}
+
+#[tokio::test]
+async fn carries_out_commands_and_publishes_the_result() {
+    let broker = Broker::spawn().await;
+    let dimmer = common::spawn_model("HS220").await;
+    let plug = common::spawn_model("KP115").await;
+    let mqtt = MqttBridge::start(config(&broker));
+    broker.subscribed("overhead/+/set").await;
+    let dimmer_id = bridge(&mqtt, &dimmer, "HS220(US)").await;
+    let plug_id = bridge(&mqtt, &plug, "KP115(US)").await;
+
+    broker.publish(
+        &format!("overhead/{dimmer_id}/set"),
+        r#"{"state": "ON", "brightness": 30}"#,
+    );
+    broker
+        .retained(&format!("overhead/{dimmer_id}/state"), |s| {
+            serde_json::from_str::<Value>(s).unwrap() == json!({"state": "ON", "brightness": 30})
+        })
+        .await;
+    assert!(dimmer.device().is_on());
+    assert_eq!(dimmer.device().brightness(), Some(30));
+
+    broker.publish(&format!("overhead/{plug_id}/set"), "ON");
+    broker
+        .retained(&format!("overhead/{plug_id}/state"), |s| s == "ON")
+        .await;
+    assert!(plug.device().is_on());
+
+    mqtt.stop().await;
+    broker.retained("overhead/status", |s| s == "offline").await;
+}
+
+#[tokio::test]
+async fn bridges_bulbs_as_lights_with_brightness() {
+    let broker = Broker::spawn().await;
+    let bulb = common::spawn_model("KL130").await;
+    let mqtt = MqttBridge::start(config(&broker));
+    broker.subscribed("overhead/+/set").await;
+
+    let bulb_id = bridge(&mqtt, &bulb, "KL130(US)").await;
+    let light: Value = serde_json::from_str(
+        &broker
+            .retained(&format!("homeassistant/light/{bulb_id}/config"), |_| true)
+            .await,
+    )
+    .unwrap();
+    assert_eq!(light["brightness"], true);
+
+    broker.publish(
+        &format!("overhead/{bulb_id}/set"),
+        r#"{"state": "ON", "brightness": 40}"#,
+    );
+    broker
+        .retained(&format!("overhead/{bulb_id}/state"), |s| {
+            serde_json::from_str::<Value>(s).unwrap() == json!({"state": "ON", "brightness": 40})
+        })
+        .await;
+    assert!(bulb.device().is_on());
+    assert_eq!(bulb.device().brightness(), Some(40));
+}
+
+#[test]
+fn parses_commands() {
+    let command = |payload: &str| MqttCommand::parse(payload.as_bytes());
+
+    assert_eq!(
+        command("ON"),
+        Some(MqttCommand {
+            on: Some(true),
+            brightness: None,
+        })
+    );
+    assert_eq!(
+        command(r#"{"state": "OFF"}"#),
+        Some(MqttCommand {
+            on: Some(false),
+            brightness: None,
+        })
+    );
+    assert_eq!(
+        command(r#"{"brightness": 55}"#),
+        Some(MqttCommand {
+            on: None,
+            brightness: Some(55),
+        })
+    );
+    assert_eq!(command("toggle"), None);
+    assert_eq!(command(r#"{"state": "DIM"}"#), None);
+}
@@ -0,0 +1,254 @@
+//! Control devices from the command line, without the app.
+//!
+//! Hosts are an address or name, with the port defaulting to 9999. With
+//! `--json` every command prints JSON instead of text, for scripts.
+use overhead_tplink::{
+    devices::Device,
+    discover::{discover_devices, DiscoveryConfig, DISCOVERY_PORT},
+    error::{TpError, TpResult},
+    models::{LightTransition, SysInfo},
+    prelude::*,
+};
+use serde_json::{json, Value};
+use std::{
+    net::{IpAddr, SocketAddr},
+    process::exit,
+};
+
+const USAGE: &str = "Usage: overhead-cli [--json] <command>
+
+Commands:
+  discover [<target>...]      Find devices on the local networks, and at
+                              any given addresses or CIDR ranges
+  info <host>                 Show everything a device reports
+  on <host>                   Switch on
+  off <host>                  Switch off
+  toggle <host>               Switch on if off, otherwise off
+  brightness <host> <1-100>   Dim a dimmer or bulb
+  alias <host> <name>         Rename
+  reboot <host>               Restart
+  raw <host> <json>           Send a request and print the response";
+
+enum Command {
+    Discover(Vec<String>),
+    Info(String),
+    Switch(String, Option<bool>),
+    Brightness(String, u8),
+    Alias(String, String),
+    Reboot(String),
+    Raw(String, Value),
+}
+
+struct Args {
+    json: bool,
+    command: Command,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let (flags, mut args): (Vec<_>, Vec<_>) = std::env::args()
+        .skip(1)
+        .partition(|arg| arg.starts_with("--"));
+    let mut json = false;
+    for flag in flags {
+        match flag.as_str() {
+            "--json" => json = true,
+            "--help" => return Err(USAGE.to_string()),
+            _ => return Err(format!("Unknown option {}\n\n{}", flag, USAGE)),
+        }
+    }
+
+    if args.is_empty() {
+        return Err(USAGE.to_string());
+    }
+    let name = args.remove(0);
+    let mut args = args.into_iter();
+    let host = |args: &mut dyn Iterator<Item = String>| {
+        args.next().ok_or(format!("{} expects a host", name))
+    };
+    let command = match name.as_str() {
+        "discover" => Command::Discover(args.collect()),
+        "info" => Command::Info(host(&mut args)?),
+        "on" => Command::Switch(host(&mut args)?, Some(true)),
+        "off" => Command::Switch(host(&mut args)?, Some(false)),
+        "toggle" => Command::Switch(host(&mut args)?, None),
+        "brightness" => {
+            let host = host(&mut args)?;
+            let brightness = args
+                .next()
+                .and_then(|brightness| brightness.parse().ok())
+                .filter(|brightness| (1..=100).contains(brightness))
+                .ok_or("brightness expects a host and a level from 1 to 100")?;
+            Command::Brightness(host, brightness)
+        }
+        "alias" => {
+            let host = host(&mut args)?;
+            let alias = args.next().ok_or("alias expects a host and a name")?;
+            Command::Alias(host, alias)
+        }
+        "reboot" => Command::Reboot(host(&mut args)?),
+        "raw" => {
+            let host = host(&mut args)?;
+            let request = args.next().ok_or("raw expects a host and a JSON request")?;
+            let request = serde_json::from_str(&request)
+                .map_err(|err| format!("The request isn't JSON: {}", err))?;
+            Command::Raw(host, request)
+        }
+        "help" => return Err(USAGE.to_string()),
+        _ => return Err(format!("Unknown command {}\n\n{}", name, USAGE)),
+    };
+
+    Ok(Args { json, command })
+}
+
+/// Resolve `host`, which may leave out the port
+async fn resolve(host: &str) -> TpResult<SocketAddr> {
+    if let Ok(addr) = host.parse() {
+        return Ok(addr);
+    }
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        return Ok(SocketAddr::new(ip, DISCOVERY_PORT));
+    }
+    let with_port = match host.rsplit_once(':') {
+        Some((_, port)) if port.parse::<u16>().is_ok() => host.to_string(),
+        _ => format!("{}:{}", host, DISCOVERY_PORT),
+    };
+    let mut addrs = tokio::net::lookup_host(with_port).await?;
+    addrs
+        .next()
+        .ok_or_else(|| TpError::from(format!("Can't resolve {}", host)))
+}
+
+/// Connect to the device at `host` as whatever type it reports itself as
+async fn connect(host: &str) -> TpResult<(Device, SysInfo)> {
+    let addr = resolve(host).await?;
+    let mut probe = Device::from_capabilities(addr, &Default::default());
+    let sysinfo = probe.get_sysinfo().await?;
+    let device = match Device::try_new(addr, &sysinfo.model) {
+        Ok(device) => device,
+        Err(_) => Device::from_capabilities(addr, &probe.probe().await?),
+    };
+    Ok((device, sysinfo))
+}
+
+fn print(json: bool, value: Value, text: impl FnOnce() -> String) {
+    if json {
+        println!("{}", value);
+    } else {
+        println!("{}", text());
+    }
+}
+
+fn on_off(on: bool) -> &'static str {
+    if on {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+async fn run(args: Args) -> TpResult<()> {
+    let json = args.json;
+    match args.command {
+        Command::Discover(targets) => {
+            let config = DiscoveryConfig {
+                targets,
+                ..Default::default()
+            };
+            let found = discover_devices(&config).await?;
+            let value = found
+                .iter()
+                .map(|(addr, resp)| json!({ "addr": addr, "sysinfo": resp.sysinfo() }))
+                .collect();
+            print(json, value, || {
+                if found.is_empty() {
+                    return "No devices found".to_string();
+                }
+                found
+                    .iter()
+                    .map(|(addr, resp)| {
+                        let sysinfo = resp.sysinfo();
+                        format!(
+                            "{:<21} {:<10} {:<4} {}",
+                            addr,
+                            sysinfo.model,
+                            on_off(sysinfo.is_on()),
+                            sysinfo.alias
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            });
+        }
+        Command::Info(host) => {
+            let (_, sysinfo) = connect(&host).await?;
+            let value = serde_json::to_value(&sysinfo)?;
+            print(json, value.clone(), || {
+                serde_json::to_string_pretty(&value).unwrap_or_default()
+            });
+        }
+        Command::Switch(host, on) => {
+            let (mut device, _) = connect(&host).await?;
+            let on = match on {
+                Some(true) => device.switch_on().await.map(|_| true)?,
+                Some(false) => device.switch_off().await.map(|_| false)?,
+                None => device.toggle().await?,
+            };
+            print(json, json!({ "on": on }), || {
+                format!("{} is {}", host, on_off(on))
+            });
+        }
+        Command::Brightness(host, brightness) => {
+            let (mut device, _) = connect(&host).await?;
+            if let Ok(bulb) = device.try_into_smart_bulb() {
+                let transition = LightTransition {
+                    brightness: Some(brightness),
+                    ..Default::default()
+                };
+                bulb.transition_light_state(&transition).await?;
+            } else {
+                device
+                    .try_into_dimmable()?
+                    .set_brightness(brightness)
+                    .await?;
+            }
+            print(json, json!({ "brightness": brightness }), || {
+                format!("{} is at {}%", host, brightness)
+            });
+        }
+        Command::Alias(host, alias) => {
+            let (device, _) = connect(&host).await?;
+            device.set_alias(&alias).await?;
+            print(json, json!({ "alias": alias }), || {
+                format!("{} is now {:?}", host, alias)
+            });
+        }
+        Command::Reboot(host) => {
+            let (device, _) = connect(&host).await?;
+            device.reboot().await?;
+            print(json, json!({ "rebooting": true }), || {
+                format!("{} is rebooting", host)
+            });
+        }
+        Command::Raw(host, request) => {
+            let device = Device::from_capabilities(resolve(&host).await?, &Default::default());
+            let response: Value = device.send(&request.to_string()).await?;
+            print(json, response.clone(), || {
+                serde_json::to_string_pretty(&response).unwrap_or_default()
+            });
+        }
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    let args = parse_args().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        exit(2);
+    });
+
+    if let Err(err) = run(args).await {
+        eprintln!("{}", err);
+        exit(1);
+    }
+}
@@ -0,0 +1,102 @@
+//! Serve simulated TP-Link devices from fixture files.
+//!
+//! By default each device gets its own loopback address (127.0.0.1,
+//! 127.0.0.2, ...) on the standard port so that it looks like real hardware.
+//! Pass `--port 0` to put every device on the same address with its own
+//! ephemeral port instead.
+use overhead_tplink::simulator::{load_fixtures, Simulator};
+use std::{
+    net::{IpAddr, Ipv4Addr},
+    process::exit,
+};
+
+const USAGE: &str = "Usage: tplink-simulator [--ip <addr>] [--port <port>] <fixtures.json>...";
+
+struct Args {
+    ip: Ipv4Addr,
+    port: u16,
+    fixtures: Vec<String>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        ip: Ipv4Addr::LOCALHOST,
+        port: 9999,
+        fixtures: vec![],
+    };
+    let mut iter = std::env::args().skip(1);
+
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--ip" => {
+                args.ip = iter
+                    .next()
+                    .and_then(|ip| ip.parse().ok())
+                    .ok_or("--ip expects an IPv4 address")?
+            }
+            "--port" => {
+                args.port = iter
+                    .next()
+                    .and_then(|port| port.parse().ok())
+                    .ok_or("--port expects a port number")?
+            }
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ => args.fixtures.push(arg),
+        }
+    }
+
+    if args.fixtures.is_empty() {
+        return Err(USAGE.to_string());
+    }
+
+    Ok(args)
+}
+
+#[tokio::main]
+async fn main() {
+    let args = parse_args().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        exit(2);
+    });
+
+    let mut devices = vec![];
+    for path in &args.fixtures {
+        match load_fixtures(path) {
+            Ok(loaded) => devices.extend(loaded),
+            Err(err) => {
+                eprintln!("Could not load {}: {}", path, err);
+                exit(1);
+            }
+        }
+    }
+
+    let mut simulators = vec![];
+    for (i, device) in devices.into_iter().enumerate() {
+        // Fixed ports need an address per device, ephemeral ports can share
+        let ip = match args.port {
+            0 => Some(args.ip),
+            _ => u32::try_from(i)
+                .ok()
+                .and_then(|i| u32::from(args.ip).checked_add(i))
+                .map(Ipv4Addr::from),
+        };
+        let Some(ip) = ip else {
+            eprintln!("No address is left after {} for device {}", args.ip, i + 1);
+            exit(2);
+        };
+        let alias = device.alias().to_string();
+
+        match Simulator::spawn(device, IpAddr::V4(ip), args.port).await {
+            Ok(simulator) => {
+                println!("{} listening on {}", alias, simulator.addr);
+                simulators.push(simulator);
+            }
+            Err(err) => {
+                eprintln!("Could not start {} on {}: {}", alias, ip, err);
+                exit(1);
+            }
+        }
+    }
+
+    std::future::pending::<()>().await;
+}
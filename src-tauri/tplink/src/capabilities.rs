@@ -0,0 +1,1068 @@
+use super::{
+    error::{TpError, TpResult},
+    models::{
+        AmbientLightConfig, AwayModeRule, AwayModeRules, BehaviorAction, ChildInfo, CountdownRule,
+        DefaultBehavior, DeviceCapabilities, DeviceResponse, DeviceSnapshot, DeviceTime,
+        DeviceTimezone, DimmerButton, DimmerParameters, EmeterRealtime, EmeterStat, LightDetails,
+        LightState, LightTransition, MotionConfig, MotionRange, NextAction, PreferredState,
+        RawAmbientLightConfig, RawAwayModeRule, RawCountdownRule, RawDayStat, RawEmeterRealtime,
+        RawMonthStat, RawMotionConfig, RawRule, RawRuleList, RawSensorValue, ScheduleRule,
+        ScheduleRules, SysInfo, TransitionMode,
+    },
+    protocol::{parse_response_section, validate_response_code},
+    requests::{Batch, BatchResponse, Request, COMMON_EMETER, EMETER},
+    timezones,
+};
+use chrono::Utc;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::{future::Future, time::Duration};
+
+/// The basic set of functions available to all TPLink smart devices
+/// All devices support this trait.
+pub trait CommonCapabilities: Send + Sync {
+    /// Send a message to a device and return its parsed response
+    /// Will return `Err` if there is a `io::Error` communicating with the
+    /// device or a problem decoding the response.
+    fn send<T: DeserializeOwned>(&self, msg: &str) -> impl Future<Output = TpResult<T>> + Send;
+
+    /// The outlet of a power strip that this handle addresses, if any.
+    /// Implementations of `send` carry it in `context.child_ids`.
+    fn child_id(&self) -> Option<&str> {
+        None
+    }
+
+    /// Send several requests in one message
+    fn batch(&self, batch: &Batch) -> impl Future<Output = TpResult<BatchResponse>> + Send {
+        async move { Ok(BatchResponse::new(self.send(&batch.to_string()).await?)) }
+    }
+
+    /// Read sysinfo along with whichever of the energy meter, light state and
+    /// dimmer parameters the device has, in a single round trip. Both energy
+    /// meter modules are asked, since which one a device has depends on its
+    /// type.
+    fn get_snapshot(&mut self) -> impl Future<Output = TpResult<DeviceSnapshot>> + Send {
+        async move {
+            let realtime = [EMETER, COMMON_EMETER].map(|module| Request::GetRealtime {
+                module: module.to_string(),
+            });
+            let response = self
+                .batch(
+                    &Batch::new()
+                        .with(Request::GetSysinfo)
+                        .with(realtime[0].clone())
+                        .with(realtime[1].clone())
+                        .with(Request::GetLightState)
+                        .with(Request::GetDimmerParameters),
+                )
+                .await?;
+
+            Ok(DeviceSnapshot {
+                sysinfo: response.get(&Request::GetSysinfo)?,
+                realtime: realtime
+                    .iter()
+                    .find_map(|request| response.get::<RawEmeterRealtime>(request).ok())
+                    .map(EmeterRealtime::from),
+                light_state: response.get(&Request::GetLightState).ok(),
+                dimmer_parameters: response.get(&Request::GetDimmerParameters).ok(),
+            })
+        }
+    }
+
+    /// Find out which optional modules the device has by asking each of them
+    /// something harmless, all in one round trip
+    fn probe(&self) -> impl Future<Output = TpResult<DeviceCapabilities>> + Send {
+        async move {
+            let mut batch = Batch::new().with(Request::GetSysinfo);
+            for (module, method) in PROBES {
+                batch.push(Request::Custom {
+                    module: module.to_string(),
+                    method: method.to_string(),
+                    params: json!({}),
+                });
+            }
+            let response = self.batch(&batch).await?;
+            let sysinfo: SysInfo = response.get(&Request::GetSysinfo)?;
+            let has = |modules: &[&str]| modules.iter().any(|m| response.has_module(m));
+            // A power strip only meters its outlets, so the strip itself
+            // reports no emeter module but does advertise the feature
+            let has_feature = |feature: &str| {
+                sysinfo
+                    .feature
+                    .as_deref()
+                    .is_some_and(|f| f.split(':').any(|f| f == feature))
+            };
+
+            Ok(DeviceCapabilities {
+                dimmer: has(&["smartlife.iot.dimmer"]),
+                emeter: has(&["emeter", "smartlife.iot.common.emeter"]) || has_feature("ENE"),
+                lighting: has(&["smartlife.iot.smartbulb.lightingservice"]),
+                schedule: has(&["schedule", "smartlife.iot.common.schedule"]),
+                countdown: has(&["count_down"]),
+                timesetting: has(&["time", "smartlife.iot.common.timesetting"]),
+                cloud: has(&["cnCloud", "smartlife.iot.common.cloud"]),
+                outlets: sysinfo.children.is_some_and(|c| !c.is_empty()),
+                motion: has(&["smartlife.iot.PIR"]),
+            })
+        }
+    }
+
+    /// Get system information
+    #[allow(unused)]
+    fn get_sysinfo(&mut self) -> impl Future<Output = TpResult<SysInfo>> + Send {
+        async move {
+            Ok(self
+                .send::<DeviceResponse>(r#"{"system":{"get_sysinfo":null}}"#)
+                .await?
+                .system
+                .sysinfo)
+        }
+    }
+
+    /// The alias of the device, or of the outlet if this addresses one
+    #[allow(unused)]
+    fn get_alias(&mut self) -> impl Future<Output = TpResult<String>> + Send {
+        async move {
+            let sysinfo = self.get_sysinfo().await?;
+            match self.child_id() {
+                Some(child_id) => Ok(find_child(&sysinfo, child_id)?.alias.clone()),
+                None => Ok(sysinfo.alias),
+            }
+        }
+    }
+
+    /// Seconds since the device, or the outlet if this addresses one, was
+    /// switched on
+    #[allow(unused)]
+    fn get_on_time(&mut self) -> impl Future<Output = TpResult<Option<i64>>> + Send {
+        async move {
+            let sysinfo = self.get_sysinfo().await?;
+            match self.child_id() {
+                Some(child_id) => Ok(find_child(&sysinfo, child_id)?.on_time),
+                None => Ok(sysinfo.on_time),
+            }
+        }
+    }
+
+    #[allow(unused)]
+    fn set_alias(&self, alias: &str) -> impl Future<Output = TpResult<()>> + Send {
+        async move {
+            let command = json!({ "system": {"set_dev_alias": {"alias": alias}} }).to_string();
+
+            validate_response_code(
+                &self.send(&command).await?,
+                "/system/set_dev_alias/err_code",
+            )
+        }
+    }
+
+    /// Whether the status LED is lit. Outlets of a power strip share the
+    /// strip's LED.
+    fn get_led_on(&mut self) -> impl Future<Output = TpResult<bool>> + Send {
+        async move { Ok(self.get_sysinfo().await?.led_on()) }
+    }
+
+    /// Turn the status LED on or off. Bulbs have no LED.
+    fn set_led_on(&self, on: bool) -> impl Future<Output = TpResult<()>> + Send {
+        async move {
+            let request = Request::SetLedOff(!on);
+            self.batch(&Batch::new().with(request.clone()))
+                .await?
+                .section(&request)
+                .map(drop)
+        }
+    }
+
+    /// Reboot the device in 1 second
+    #[allow(unused)]
+    fn reboot(&self) -> impl Future<Output = TpResult<()>> + Send {
+        async move { self.reboot_with_delay(Duration::from_secs(1)).await }
+    }
+
+    /// Reboot the device with a specified delay
+    #[allow(unused)]
+    fn reboot_with_delay(&self, delay: Duration) -> impl Future<Output = TpResult<()>> + Send {
+        async move {
+            let command = json!({
+                "system": {"reboot": {"delay": delay.as_secs()}}
+            })
+            .to_string();
+
+            validate_response_code(&self.send(&command).await?, "/system/reboot/err_code")
+        }
+    }
+
+    /// Check whether the device, or the outlet if this addresses one, is on
+    fn get_is_on(&mut self) -> impl Future<Output = TpResult<bool>> + Send {
+        async move {
+            let sysinfo = self.get_sysinfo().await?;
+            match self.child_id() {
+                Some(child_id) => Ok(find_child(&sysinfo, child_id)?.is_on()),
+                None => Ok(sysinfo.is_on()),
+            }
+        }
+    }
+
+    /// Check whether the device is off
+    #[allow(dead_code)]
+    fn get_is_off(&mut self) -> impl Future<Output = TpResult<bool>> + Send {
+        async move { Ok(!self.get_is_on().await?) }
+    }
+
+    /// Switch the device on
+    fn switch_on(&mut self) -> impl Future<Output = TpResult<()>> + Send {
+        async move {
+            validate_response_code(
+                &self
+                    .send(r#"{"system":{"set_relay_state":{"state":1}}}"#)
+                    .await?,
+                "/system/set_relay_state/err_code",
+            )
+        }
+    }
+
+    /// Switch the device off
+    fn switch_off(&mut self) -> impl Future<Output = TpResult<()>> + Send {
+        async move {
+            validate_response_code(
+                &self
+                    .send(r#"{"system":{"set_relay_state":{"state":0}}}"#)
+                    .await?,
+                "/system/set_relay_state/err_code",
+            )
+        }
+    }
+
+    /// Toggle the device's on state
+    fn toggle(&mut self) -> impl Future<Output = TpResult<bool>> + Send {
+        async move {
+            if self.get_is_on().await? {
+                self.switch_off().await?;
+                Ok(false)
+            } else {
+                self.switch_on().await?;
+                Ok(true)
+            }
+        }
+    }
+}
+
+/// A harmless read for every module [`CommonCapabilities::probe`] looks for
+const PROBES: &[(&str, &str)] = &[
+    ("smartlife.iot.dimmer", "get_dimmer_parameters"),
+    ("emeter", "get_realtime"),
+    ("smartlife.iot.common.emeter", "get_realtime"),
+    ("smartlife.iot.smartbulb.lightingservice", "get_light_state"),
+    ("schedule", "get_next_action"),
+    ("smartlife.iot.common.schedule", "get_next_action"),
+    ("count_down", "get_rules"),
+    ("time", "get_time"),
+    ("smartlife.iot.common.timesetting", "get_time"),
+    ("cnCloud", "get_info"),
+    ("smartlife.iot.common.cloud", "get_info"),
+    ("smartlife.iot.PIR", "get_config"),
+];
+
+fn find_child<'a>(sysinfo: &'a SysInfo, child_id: &str) -> TpResult<&'a ChildInfo> {
+    sysinfo
+        .child(child_id)
+        .ok_or_else(|| TpError::from(format!("No outlet with id {}", child_id)))
+}
+
+const DIMMER: &str = "smartlife.iot.dimmer";
+
+/// Call a `smartlife.iot.dimmer` method that only returns an error code
+async fn dimmer_command<D: CommonCapabilities + ?Sized>(
+    device: &D,
+    method: &str,
+    params: Value,
+) -> TpResult<()> {
+    let command = json!({ DIMMER: { method: params } }).to_string();
+
+    validate_response_code(
+        &device.send(&command).await?,
+        &format!("/{}/{}/err_code", DIMMER, method),
+    )
+}
+
+/// Set a dimmer's brightness and switch it on in one batch
+async fn switch_on_at<D: CommonCapabilities + ?Sized>(device: &D, brightness: u8) -> TpResult<()> {
+    let requests = [
+        Request::SetBrightness(brightness.max(1)),
+        Request::SetRelayState(true),
+    ];
+    let batch = requests
+        .iter()
+        .fold(Batch::new(), |batch, request| batch.with(request.clone()));
+    let response = device.batch(&batch).await?;
+    requests
+        .iter()
+        .try_for_each(|request| response.section(request).map(drop))
+}
+
+pub trait Dimmable: CommonCapabilities {
+    fn get_dimmer_parameters(&self) -> impl Future<Output = TpResult<DimmerParameters>> + Send {
+        async move {
+            let command = json!({"smartlife.iot.dimmer":{"get_dimmer_parameters":{}}}).to_string();
+
+            parse_response_section(
+                &self.send(&command).await?,
+                "/smartlife.iot.dimmer/get_dimmer_parameters",
+            )
+        }
+    }
+
+    fn get_default_behavior(&self) -> impl Future<Output = TpResult<DefaultBehavior>> + Send {
+        async move {
+            let command = json!({"smartlife.iot.dimmer":{"get_default_behavior":{}}}).to_string();
+
+            parse_response_section(
+                &self.send(&command).await?,
+                "/smartlife.iot.dimmer/get_default_behavior",
+            )
+        }
+    }
+
+    /// Fade time in milliseconds when switched on from the app
+    fn set_fade_on_time(&self, millis: u32) -> impl Future<Output = TpResult<()>> + Send {
+        dimmer_command(self, "set_fade_on_time", json!({ "fadeTime": millis }))
+    }
+
+    /// Fade time in milliseconds when switched off from the app
+    fn set_fade_off_time(&self, millis: u32) -> impl Future<Output = TpResult<()>> + Send {
+        dimmer_command(self, "set_fade_off_time", json!({ "fadeTime": millis }))
+    }
+
+    /// Fade time in milliseconds when switched on at the switch
+    fn set_gentle_on_time(&self, millis: u32) -> impl Future<Output = TpResult<()>> + Send {
+        dimmer_command(self, "set_gentle_on_time", json!({ "duration": millis }))
+    }
+
+    /// Fade time in milliseconds when switched off at the switch
+    fn set_gentle_off_time(&self, millis: u32) -> impl Future<Output = TpResult<()>> + Send {
+        dimmer_command(self, "set_gentle_off_time", json!({ "duration": millis }))
+    }
+
+    /// How quickly brightness changes while the switch is held
+    fn set_ramp_rate(&self, rate: u32) -> impl Future<Output = TpResult<()>> + Send {
+        dimmer_command(self, "set_button_ramp_rate", json!({ "rampRate": rate }))
+    }
+
+    /// Set the lowest brightness the connected load can be driven at, for
+    /// bulbs that flicker or cut out at low levels
+    fn calibrate_min_threshold(&self, min: u8) -> impl Future<Output = TpResult<()>> + Send {
+        dimmer_command(
+            self,
+            "calibrate_brightness",
+            json!({ "minThreshold": min.clamp(1, 100) }),
+        )
+    }
+
+    /// Reassign what a long press or double click of the switch does
+    fn set_button_action(
+        &self,
+        button: DimmerButton,
+        action: BehaviorAction,
+    ) -> impl Future<Output = TpResult<()>> + Send {
+        async move {
+            let method = match button {
+                DimmerButton::LongPress => "set_long_press_action",
+                DimmerButton::DoubleClick => "set_double_click_action",
+            };
+
+            dimmer_command(self, method, serde_json::to_value(action)?).await
+        }
+    }
+
+    /// Move to `brightness` over `duration`. A brightness of 0 fades the
+    /// device off.
+    fn set_dimmer_transition(
+        &mut self,
+        brightness: u8,
+        mode: TransitionMode,
+        duration: Duration,
+    ) -> impl Future<Output = TpResult<()>> + Send {
+        dimmer_command(
+            self,
+            "set_dimmer_transition",
+            json!({
+                "brightness": brightness.min(100),
+                "mode": mode,
+                "duration": duration.as_millis() as u64,
+            }),
+        )
+    }
+
+    fn set_brightness(&mut self, brightness: u8) -> impl Future<Output = TpResult<()>> + Send {
+        async move {
+            let brightness = brightness.clamp(1, 100);
+
+            let command = json!({
+                "smartlife.iot.dimmer": {
+                    "set_brightness": {
+                        "brightness": brightness
+                    }
+                }
+            })
+            .to_string();
+
+            validate_response_code(
+                &self.send(&command).await?,
+                "/smartlife.iot.dimmer/set_brightness/err_code",
+            )
+        }
+    }
+
+    /// Set the brightness and switch on, in one round trip
+    fn switch_on_at(&mut self, brightness: u8) -> impl Future<Output = TpResult<()>> + Send {
+        switch_on_at(self, brightness)
+    }
+
+    /// Switch the dimmer on or off, using its configured fade
+    fn set_switch_state(&mut self, switch_on: bool) -> impl Future<Output = TpResult<()>> + Send {
+        dimmer_command(
+            self,
+            "set_switch_state",
+            json!({ "state": if switch_on { 1 } else { 0 } }),
+        )
+    }
+}
+
+const LIGHTING: &str = "smartlife.iot.smartbulb.lightingservice";
+
+/// Call a `smartlife.iot.smartbulb.lightingservice` method and parse its
+/// response
+async fn lighting_query<D: CommonCapabilities + ?Sized, T: DeserializeOwned>(
+    device: &D,
+    method: &str,
+    params: Value,
+) -> TpResult<T> {
+    let command = json!({ LIGHTING: { method: params } }).to_string();
+
+    parse_response_section(
+        &device.send(&command).await?,
+        &format!("/{}/{}", LIGHTING, method),
+    )
+}
+
+/// Smart bulbs (KL and LB series), which are driven through the lighting
+/// service rather than a relay
+pub trait SmartBulb: CommonCapabilities {
+    fn get_light_state(&self) -> impl Future<Output = TpResult<LightState>> + Send {
+        lighting_query(self, "get_light_state", json!({}))
+    }
+
+    /// Apply a change to the light state and return the resulting state.
+    /// The bulb is switched on unless `transition.on` is `Some(false)`; when
+    /// nothing but `on` is given it comes back in its default state.
+    fn transition_light_state(
+        &mut self,
+        transition: &LightTransition,
+    ) -> impl Future<Output = TpResult<LightState>> + Send {
+        let mut params = json!({
+            "on_off": if transition.on.unwrap_or(true) { 1 } else { 0 },
+            "ignore_default": 0,
+        });
+        let fields = [
+            ("hue", transition.hue.map(|hue| hue.min(360).into())),
+            (
+                "saturation",
+                transition.saturation.map(|s| s.min(100).into()),
+            ),
+            ("color_temp", transition.color_temp.map(u32::from)),
+            (
+                "brightness",
+                transition.brightness.map(|b| b.min(100).into()),
+            ),
+        ];
+        for (field, value) in fields {
+            if let Some(value) = value {
+                params[field] = json!(value);
+                params["ignore_default"] = json!(1);
+            }
+        }
+        if let Some(period) = transition.transition_period {
+            params["transition_period"] = json!(period);
+        }
+
+        lighting_query(self, "transition_light_state", params)
+    }
+
+    fn get_light_details(&self) -> impl Future<Output = TpResult<LightDetails>> + Send {
+        lighting_query(self, "get_light_details", json!({}))
+    }
+
+    /// What the bulb does when it is switched on with the app (`soft_on`)
+    /// or at the wall (`hard_on`)
+    fn get_default_behavior(&self) -> impl Future<Output = TpResult<DefaultBehavior>> + Send {
+        lighting_query(self, "get_default_behavior", json!({}))
+    }
+}
+
+/// Plugs, strips and bulbs that measure their own energy use
+pub trait EnergyMeter: CommonCapabilities {
+    /// Plugs use `emeter`, bulbs `smartlife.iot.common.emeter`
+    fn emeter_module(&self) -> &'static str {
+        EMETER
+    }
+
+    fn get_realtime(&self) -> impl Future<Output = TpResult<EmeterRealtime>> + Send {
+        async move {
+            let raw: RawEmeterRealtime =
+                module_query(self, self.emeter_module(), "get_realtime", json!({})).await?;
+            Ok(raw.into())
+        }
+    }
+
+    /// Energy used each day of a month. `month` is 1-12.
+    fn get_daily_stats(
+        &self,
+        year: u16,
+        month: u8,
+    ) -> impl Future<Output = TpResult<Vec<EmeterStat>>> + Send {
+        async move {
+            let params = json!({ "year": year, "month": month });
+            let raw: RawDayStat =
+                module_query(self, self.emeter_module(), "get_daystat", params).await?;
+            Ok(raw.day_list.into_iter().map(EmeterStat::from).collect())
+        }
+    }
+
+    /// Energy used each month of a year
+    fn get_monthly_stats(
+        &self,
+        year: u16,
+    ) -> impl Future<Output = TpResult<Vec<EmeterStat>>> + Send {
+        async move {
+            let params = json!({ "year": year });
+            let raw: RawMonthStat =
+                module_query(self, self.emeter_module(), "get_monthstat", params).await?;
+            Ok(raw.month_list.into_iter().map(EmeterStat::from).collect())
+        }
+    }
+
+    /// Erase all daily and monthly stats and reset the running total
+    fn erase_stats(&mut self) -> impl Future<Output = TpResult<()>> + Send {
+        async move { module_command(self, self.emeter_module(), "erase_emeter_stat", json!({})).await }
+    }
+}
+
+/// Call a method of `module` and parse its response, for modules whose name
+/// depends on the device or that are shared by several capabilities
+async fn module_query<D: CommonCapabilities + ?Sized, T: DeserializeOwned>(
+    device: &D,
+    module: &str,
+    method: &str,
+    params: Value,
+) -> TpResult<T> {
+    let command = json!({ module: { method: params } }).to_string();
+
+    parse_response_section(
+        &device.send(&command).await?,
+        &format!("/{}/{}", module, method),
+    )
+}
+
+/// Call a method of `module` that only reports success
+async fn module_command<D: CommonCapabilities + ?Sized>(
+    device: &D,
+    module: &str,
+    method: &str,
+    params: Value,
+) -> TpResult<()> {
+    let command = json!({ module: { method: params } }).to_string();
+
+    validate_response_code(
+        &device.send(&command).await?,
+        &format!("/{}/{}/err_code", module, method),
+    )
+}
+
+/// Add a rule and return the id the device gave it
+async fn add_rule_to<D: CommonCapabilities + ?Sized>(
+    device: &D,
+    module: &str,
+    rule: Value,
+) -> TpResult<String> {
+    #[derive(serde::Deserialize)]
+    struct Added {
+        id: String,
+    }
+    let added: Added = module_query(device, module, "add_rule", rule).await?;
+    Ok(added.id)
+}
+
+/// Rules can only be edited or deleted by id
+fn require_rule_id<'a>(id: Option<&'a str>, module: &str, method: &str) -> TpResult<&'a str> {
+    id.ok_or_else(|| TpError::InvalidArgument {
+        addr: None,
+        module: module.to_string(),
+        method: method.to_string(),
+        message: Some("The rule has no id".to_string()),
+    })
+}
+
+/// Devices that keep a schedule of rules and run them on their own
+pub trait Schedulable: CommonCapabilities {
+    /// Plugs use `schedule`, dimmers and bulbs `smartlife.iot.common.schedule`
+    fn schedule_module(&self) -> &'static str {
+        "schedule"
+    }
+
+    fn get_rules(&self) -> impl Future<Output = TpResult<ScheduleRules>> + Send {
+        async move {
+            let raw: RawRuleList<RawRule> =
+                module_query(self, self.schedule_module(), "get_rules", json!({})).await?;
+            Ok(ScheduleRules {
+                enabled: raw.enable.is_none_or(|enable| enable > 0),
+                rules: raw.rule_list.into_iter().map(ScheduleRule::from).collect(),
+            })
+        }
+    }
+
+    /// Add a rule, ignoring its `id`, and return the id the device gave it
+    fn add_rule(&mut self, rule: &ScheduleRule) -> impl Future<Output = TpResult<String>> + Send {
+        async move {
+            let rule = ScheduleRule {
+                id: None,
+                ..rule.clone()
+            };
+            add_rule_to(self, self.schedule_module(), json!(RawRule::from(&rule))).await
+        }
+    }
+
+    /// Replace the rule with the same `id`
+    fn edit_rule(&mut self, rule: &ScheduleRule) -> impl Future<Output = TpResult<()>> + Send {
+        async move {
+            let module = self.schedule_module();
+            require_rule_id(rule.id.as_deref(), module, "edit_rule")?;
+            module_command(self, module, "edit_rule", json!(RawRule::from(rule))).await
+        }
+    }
+
+    fn delete_rule(&mut self, id: &str) -> impl Future<Output = TpResult<()>> + Send {
+        async move {
+            let params = json!({ "id": id });
+            module_command(self, self.schedule_module(), "delete_rule", params).await
+        }
+    }
+
+    fn delete_all_rules(&mut self) -> impl Future<Output = TpResult<()>> + Send {
+        async move { module_command(self, self.schedule_module(), "delete_all_rules", json!({})).await }
+    }
+
+    /// Enable or disable a single rule
+    fn set_rule_enabled(
+        &mut self,
+        id: &str,
+        enabled: bool,
+    ) -> impl Future<Output = TpResult<()>> + Send {
+        async move {
+            let mut rule = self
+                .get_rules()
+                .await?
+                .rules
+                .into_iter()
+                .find(|rule| rule.id.as_deref() == Some(id))
+                .ok_or_else(|| TpError::InvalidArgument {
+                    addr: None,
+                    module: self.schedule_module().to_string(),
+                    method: "edit_rule".to_string(),
+                    message: Some(format!("No rule with id {:?}", id)),
+                })?;
+            rule.enabled = enabled;
+            self.edit_rule(&rule).await
+        }
+    }
+
+    /// Enable or disable the whole schedule without touching its rules
+    fn set_rules_enabled(&mut self, enabled: bool) -> impl Future<Output = TpResult<()>> + Send {
+        async move {
+            let params = json!({ "enable": u8::from(enabled) });
+            module_command(self, self.schedule_module(), "set_overall_enable", params).await
+        }
+    }
+
+    fn get_next_action(&self) -> impl Future<Output = TpResult<NextAction>> + Send {
+        async move { module_query(self, self.schedule_module(), "get_next_action", json!({})).await }
+    }
+}
+
+const COUNTDOWN: &str = "count_down";
+
+/// Plugs and switches that can switch themselves after a delay. Devices hold
+/// a single timer at a time.
+pub trait Countdown: CommonCapabilities {
+    fn get_countdowns(&self) -> impl Future<Output = TpResult<Vec<CountdownRule>>> + Send {
+        async move {
+            let raw: RawRuleList<RawCountdownRule> =
+                module_query(self, COUNTDOWN, "get_rules", json!({})).await?;
+            Ok(raw.rule_list.into_iter().map(CountdownRule::from).collect())
+        }
+    }
+
+    /// Add a timer, ignoring its `id`, and return the id the device gave it
+    fn add_countdown(
+        &mut self,
+        rule: &CountdownRule,
+    ) -> impl Future<Output = TpResult<String>> + Send {
+        async move {
+            let rule = CountdownRule {
+                id: None,
+                ..rule.clone()
+            };
+            add_rule_to(self, COUNTDOWN, json!(RawCountdownRule::from(&rule))).await
+        }
+    }
+
+    /// Replace the timer with the same `id`, which restarts it
+    fn edit_countdown(
+        &mut self,
+        rule: &CountdownRule,
+    ) -> impl Future<Output = TpResult<()>> + Send {
+        async move {
+            require_rule_id(rule.id.as_deref(), COUNTDOWN, "edit_rule")?;
+            module_command(
+                self,
+                COUNTDOWN,
+                "edit_rule",
+                json!(RawCountdownRule::from(rule)),
+            )
+            .await
+        }
+    }
+
+    fn delete_countdown(&mut self, id: &str) -> impl Future<Output = TpResult<()>> + Send {
+        async move { module_command(self, COUNTDOWN, "delete_rule", json!({ "id": id })).await }
+    }
+
+    fn delete_all_countdowns(&mut self) -> impl Future<Output = TpResult<()>> + Send {
+        module_command(self, COUNTDOWN, "delete_all_rules", json!({}))
+    }
+
+    /// Switch on or off once `delay` has passed, replacing any running timer
+    fn switch_after(
+        &mut self,
+        delay: Duration,
+        on: bool,
+    ) -> impl Future<Output = TpResult<String>> + Send {
+        async move {
+            self.delete_all_countdowns().await?;
+            self.add_countdown(&CountdownRule {
+                id: None,
+                name: format!(
+                    "Turn {} in {}s",
+                    if on { "on" } else { "off" },
+                    delay.as_secs()
+                ),
+                enabled: true,
+                delay: delay.as_secs() as u32,
+                on,
+                remaining: None,
+            })
+            .await
+        }
+    }
+}
+
+const ANTI_THEFT: &str = "anti_theft";
+
+/// Plugs and switches with away mode, which switches them on and off at
+/// random while nobody is home
+pub trait AwayMode: CommonCapabilities {
+    fn get_away_rules(&self) -> impl Future<Output = TpResult<AwayModeRules>> + Send {
+        async move {
+            let raw: RawRuleList<RawAwayModeRule> =
+                module_query(self, ANTI_THEFT, "get_rules", json!({})).await?;
+            Ok(AwayModeRules {
+                enabled: raw.enable.is_none_or(|enable| enable > 0),
+                rules: raw.rule_list.into_iter().map(AwayModeRule::from).collect(),
+            })
+        }
+    }
+
+    /// Add a rule, ignoring its `id`, and return the id the device gave it
+    fn add_away_rule(
+        &mut self,
+        rule: &AwayModeRule,
+    ) -> impl Future<Output = TpResult<String>> + Send {
+        async move {
+            let rule = AwayModeRule {
+                id: None,
+                ..rule.clone()
+            };
+            add_rule_to(self, ANTI_THEFT, json!(RawAwayModeRule::from(&rule))).await
+        }
+    }
+
+    /// Replace the rule with the same `id`
+    fn edit_away_rule(&mut self, rule: &AwayModeRule) -> impl Future<Output = TpResult<()>> + Send {
+        async move {
+            require_rule_id(rule.id.as_deref(), ANTI_THEFT, "edit_rule")?;
+            module_command(
+                self,
+                ANTI_THEFT,
+                "edit_rule",
+                json!(RawAwayModeRule::from(rule)),
+            )
+            .await
+        }
+    }
+
+    fn delete_away_rule(&mut self, id: &str) -> impl Future<Output = TpResult<()>> + Send {
+        async move { module_command(self, ANTI_THEFT, "delete_rule", json!({ "id": id })).await }
+    }
+
+    fn delete_all_away_rules(&mut self) -> impl Future<Output = TpResult<()>> + Send {
+        module_command(self, ANTI_THEFT, "delete_all_rules", json!({}))
+    }
+
+    /// Turn away mode on or off without touching its rules
+    fn set_away_mode_enabled(
+        &mut self,
+        enabled: bool,
+    ) -> impl Future<Output = TpResult<()>> + Send {
+        async move {
+            let params = json!({ "enable": u8::from(enabled) });
+            module_command(self, ANTI_THEFT, "set_overall_enable", params).await
+        }
+    }
+}
+
+/// Devices with a clock, which their schedule and timers run on
+pub trait Timesetting: CommonCapabilities {
+    /// Plugs use `time`, dimmers and bulbs `smartlife.iot.common.timesetting`
+    fn timesetting_module(&self) -> &'static str {
+        "time"
+    }
+
+    fn get_time(&self) -> impl Future<Output = TpResult<DeviceTime>> + Send {
+        async move { module_query(self, self.timesetting_module(), "get_time", json!({})).await }
+    }
+
+    fn get_timezone(&self) -> impl Future<Output = TpResult<DeviceTimezone>> + Send {
+        async move { module_query(self, self.timesetting_module(), "get_timezone", json!({})).await }
+    }
+
+    /// Read the time and timezone in one round trip
+    fn get_clock(&self) -> impl Future<Output = TpResult<(DeviceTime, DeviceTimezone)>> + Send {
+        async move {
+            let [time, timezone] = ["get_time", "get_timezone"].map(|method| Request::Custom {
+                module: self.timesetting_module().to_string(),
+                method: method.to_string(),
+                params: json!({}),
+            });
+            let response = self
+                .batch(&Batch::new().with(time.clone()).with(timezone.clone()))
+                .await?;
+            Ok((response.get(&time)?, response.get(&timezone)?))
+        }
+    }
+
+    /// Set the timezone by its index, see [`timezones`](super::timezones),
+    /// along with the wall clock time in that zone
+    fn set_timezone(
+        &mut self,
+        index: u8,
+        time: &DeviceTime,
+    ) -> impl Future<Output = TpResult<()>> + Send {
+        async move {
+            let mut params = json!(time);
+            params["index"] = json!(index);
+            module_command(self, self.timesetting_module(), "set_timezone", params).await
+        }
+    }
+
+    /// Move the device to an IANA zone such as `Europe/Amsterdam`, with its
+    /// clock set to the current time there
+    fn set_zone(&mut self, zone: &str) -> impl Future<Output = TpResult<()>> + Send {
+        async move {
+            let index = timezones::index_of(zone).ok_or_else(|| TpError::InvalidArgument {
+                addr: None,
+                module: self.timesetting_module().to_string(),
+                method: "set_timezone".to_string(),
+                message: Some(format!("Unknown timezone {:?}", zone)),
+            })?;
+            self.sync_clock(index).await
+        }
+    }
+
+    /// Set the clock to the current time in the zone with `index`
+    fn sync_clock(&mut self, index: u8) -> impl Future<Output = TpResult<()>> + Send {
+        async move {
+            let time = timezones::device_time_at(index, Utc::now()).ok_or_else(|| {
+                TpError::InvalidArgument {
+                    addr: None,
+                    module: self.timesetting_module().to_string(),
+                    method: "set_timezone".to_string(),
+                    message: Some(format!("Unknown timezone index {}", index)),
+                }
+            })?;
+            self.set_timezone(index, &time).await
+        }
+    }
+}
+
+/// Dimmers and bulbs keep four presets, the one-tap brightness (and for bulbs
+/// color) choices of the Kasa app, in the `preferred_state` field of sysinfo
+pub trait Presets: CommonCapabilities {
+    /// The module that stores presets
+    fn preset_module(&self) -> &'static str {
+        DIMMER
+    }
+
+    fn get_presets(&mut self) -> impl Future<Output = TpResult<Vec<PreferredState>>> + Send {
+        async move {
+            Ok(self
+                .get_sysinfo()
+                .await?
+                .preferred_state
+                .unwrap_or_default())
+        }
+    }
+
+    /// Overwrite the preset in slot `preset.index`
+    fn save_preset(
+        &mut self,
+        preset: &PreferredState,
+    ) -> impl Future<Output = TpResult<()>> + Send {
+        let module = self.preset_module();
+        let params = serde_json::to_value(preset);
+        async move { module_command(self, module, "set_preferred_state", params?).await }
+    }
+
+    /// Switch on with the preset in slot `index` and return it
+    fn apply_preset(&mut self, index: u8) -> impl Future<Output = TpResult<PreferredState>> + Send {
+        async move {
+            let preset = self
+                .get_presets()
+                .await?
+                .into_iter()
+                .find(|preset| preset.index == index)
+                .ok_or_else(|| TpError::InvalidArgument {
+                    addr: None,
+                    module: self.preset_module().to_string(),
+                    method: "preferred_state".to_string(),
+                    message: Some(format!("No preset {}", index)),
+                })?;
+            self.set_preset_state(&preset).await?;
+            Ok(preset)
+        }
+    }
+
+    /// Switch on in the state `preset` describes. Dimmers only take the
+    /// brightness, in one round trip with switching on.
+    fn set_preset_state(
+        &mut self,
+        preset: &PreferredState,
+    ) -> impl Future<Output = TpResult<()>> + Send {
+        switch_on_at(self, preset.brightness)
+    }
+}
+
+const PIR: &str = "smartlife.iot.PIR";
+const LAS: &str = "smartlife.iot.LAS";
+
+/// The passive infrared motion sensor of an ES20M, which switches the light
+/// on when someone walks by
+pub trait MotionSensor: CommonCapabilities {
+    fn get_motion_config(&self) -> impl Future<Output = TpResult<MotionConfig>> + Send {
+        async move {
+            let raw: RawMotionConfig = module_query(self, PIR, "get_config", json!({})).await?;
+            Ok(raw.into())
+        }
+    }
+
+    /// Whether motion switches the light on at all
+    fn set_motion_enabled(&self, enabled: bool) -> impl Future<Output = TpResult<()>> + Send {
+        module_command(
+            self,
+            PIR,
+            "set_enable",
+            json!({ "enable": u8::from(enabled) }),
+        )
+    }
+
+    /// Use one of the sensor's ranges, or its custom threshold
+    fn set_motion_range(&self, range: MotionRange) -> impl Future<Output = TpResult<()>> + Send {
+        module_command(
+            self,
+            PIR,
+            "set_trigger_sens",
+            json!({ "index": range.index() }),
+        )
+    }
+
+    /// Set the custom threshold, in percent, and switch to it
+    fn set_motion_threshold(&self, threshold: u8) -> impl Future<Output = TpResult<()>> + Send {
+        module_command(
+            self,
+            PIR,
+            "set_trigger_sens",
+            json!({ "index": MotionRange::Custom.index(), "value": threshold.min(100) }),
+        )
+    }
+
+    /// How long the light stays on after the last motion
+    fn set_cold_time(&self, cold_time: Duration) -> impl Future<Output = TpResult<()>> + Send {
+        module_command(
+            self,
+            PIR,
+            "set_cold_time",
+            json!({ "cold_time": cold_time.as_millis() as u64 }),
+        )
+    }
+}
+
+/// The ambient light sensor of an ES20M, which keeps motion from switching
+/// the light on while the room is bright enough
+pub trait AmbientLightSensor: CommonCapabilities {
+    fn get_ambient_light_config(
+        &self,
+    ) -> impl Future<Output = TpResult<AmbientLightConfig>> + Send {
+        async move {
+            let raw: RawAmbientLightConfig =
+                module_query(self, LAS, "get_config", json!({})).await?;
+            raw.devs
+                .into_iter()
+                .next()
+                .map(AmbientLightConfig::from)
+                .ok_or_else(|| TpError::from("No ambient light sensor".to_string()))
+        }
+    }
+
+    /// Whether motion only switches the light on in the dark
+    fn set_ambient_light_enabled(
+        &self,
+        enabled: bool,
+    ) -> impl Future<Output = TpResult<()>> + Send {
+        module_command(
+            self,
+            LAS,
+            "set_enable",
+            json!({ "enable": u8::from(enabled) }),
+        )
+    }
+
+    /// Set the brightness, in percent, below which motion switches the light
+    /// on
+    fn set_ambient_light_threshold(
+        &self,
+        threshold: u8,
+    ) -> impl Future<Output = TpResult<()>> + Send {
+        module_command(
+            self,
+            LAS,
+            "set_brt_level",
+            json!({ "index": 0, "value": threshold.min(100) }),
+        )
+    }
+
+    /// How bright the room is now, in percent of the sensor's span
+    fn get_ambient_light(&self) -> impl Future<Output = TpResult<u8>> + Send {
+        async move {
+            let raw: RawSensorValue = module_query(self, LAS, "get_current_brt", json!({})).await?;
+            Ok(raw.value.min(100) as u8)
+        }
+    }
+}
@@ -0,0 +1,500 @@
+use super::{
+    error::{TpError, TpResult},
+    models::{DeviceCapabilities, LightTransition, PowerState, PreferredState},
+    prelude::*,
+    protocol::{send, with_child_context},
+    requests::COMMON_EMETER,
+};
+use log::*;
+use serde::de::DeserializeOwned;
+use std::{future::Future, net::SocketAddr};
+
+macro_rules! device_struct {
+    ( $model:ident ) => {
+        #[derive(Clone, Debug)]
+        pub struct $model {
+            pub addr: SocketAddr,
+            /// Set when this addresses one outlet of a power strip
+            pub child_id: Option<String>,
+        }
+
+        impl $model {
+            pub fn new(addr: SocketAddr) -> Self {
+                Self {
+                    addr: addr.clone(),
+                    child_id: None,
+                }
+            }
+        }
+    };
+}
+
+macro_rules! new_device {
+    ( $model:ident ) => {
+        new_device!($model, "schedule", "time");
+    };
+    ( $model:ident, $schedule:literal, $time:literal ) => {
+        device_struct!($model);
+
+        impl CommonCapabilities for $model {
+            async fn send<D: DeserializeOwned>(&self, msg: &str) -> TpResult<D> {
+                send_as(self.addr, self.child_id(), msg).await
+            }
+
+            fn child_id(&self) -> Option<&str> {
+                self.child_id.as_deref()
+            }
+        }
+
+        impl Schedulable for $model {
+            fn schedule_module(&self) -> &'static str {
+                $schedule
+            }
+        }
+
+        impl Timesetting for $model {
+            fn timesetting_module(&self) -> &'static str {
+                $time
+            }
+        }
+
+        impl Countdown for $model {}
+        impl AwayMode for $model {}
+    };
+}
+
+/// Bulbs have no relay, so switching goes through the lighting service
+macro_rules! new_bulb {
+    ( $model:ident ) => {
+        device_struct!($model);
+
+        impl CommonCapabilities for $model {
+            async fn send<D: DeserializeOwned>(&self, msg: &str) -> TpResult<D> {
+                send_as(self.addr, self.child_id(), msg).await
+            }
+
+            fn child_id(&self) -> Option<&str> {
+                self.child_id.as_deref()
+            }
+
+            fn switch_on(&mut self) -> impl Future<Output = TpResult<()>> + Send {
+                switch_bulb(self, true)
+            }
+
+            fn switch_off(&mut self) -> impl Future<Output = TpResult<()>> + Send {
+                switch_bulb(self, false)
+            }
+        }
+
+        impl SmartBulb for $model {}
+
+        impl EnergyMeter for $model {
+            fn emeter_module(&self) -> &'static str {
+                COMMON_EMETER
+            }
+        }
+
+        impl Schedulable for $model {
+            fn schedule_module(&self) -> &'static str {
+                "smartlife.iot.common.schedule"
+            }
+        }
+
+        impl Timesetting for $model {
+            fn timesetting_module(&self) -> &'static str {
+                "smartlife.iot.common.timesetting"
+            }
+        }
+
+        impl Presets for $model {
+            fn preset_module(&self) -> &'static str {
+                "smartlife.iot.smartbulb.lightingservice"
+            }
+
+            fn set_preset_state(
+                &mut self,
+                preset: &PreferredState,
+            ) -> impl Future<Output = TpResult<()>> + Send {
+                let transition = LightTransition::from(preset);
+                async move { self.transition_light_state(&transition).await.map(drop) }
+            }
+        }
+    };
+}
+
+async fn send_as<D: DeserializeOwned>(
+    addr: SocketAddr,
+    child_id: Option<&str>,
+    msg: &str,
+) -> TpResult<D> {
+    let msg = with_child_context(msg, child_id)?;
+    debug!("Sending {}", msg);
+    Ok(serde_json::from_str::<D>(&send(addr, &msg).await?)?)
+}
+
+async fn switch_bulb(bulb: &mut impl SmartBulb, on: bool) -> TpResult<()> {
+    let transition = LightTransition {
+        on: Some(on),
+        ..Default::default()
+    };
+    bulb.transition_light_state(&transition).await.map(drop)
+}
+
+new_device!(EP10);
+new_device!(HS110);
+impl EnergyMeter for HS110 {}
+new_device!(KP303);
+new_device!(
+    HS220,
+    "smartlife.iot.common.schedule",
+    "smartlife.iot.common.timesetting"
+);
+impl Dimmable for HS220 {}
+impl Presets for HS220 {}
+new_device!(
+    ES20M,
+    "smartlife.iot.common.schedule",
+    "smartlife.iot.common.timesetting"
+);
+impl Dimmable for ES20M {}
+impl Presets for ES20M {}
+impl MotionSensor for ES20M {}
+impl AmbientLightSensor for ES20M {}
+
+new_bulb!(KL130);
+
+/// Forward a call to whichever device a [`Device`] wraps
+macro_rules! dispatch {
+    ( $device:expr, $d:ident => $call:expr ) => {
+        match $device {
+            Device::EP10($d) => $call,
+            Device::ES20M($d) => $call,
+            Device::HS110($d) => $call,
+            Device::HS220($d) => $call,
+            Device::KL130($d) => $call,
+            Device::KP303($d) => $call,
+        }
+    };
+}
+
+/// A borrowed device of one of the types a capability is limited to, so the
+/// capability's methods can be called on it without knowing which
+macro_rules! device_ref {
+    ( $(#[$meta:meta])* $name:ident { $( $model:ident ),+ } ) => {
+        $(#[$meta])*
+        #[derive(Debug)]
+        pub enum $name<'a> {
+            $( $model(&'a mut $model), )+
+        }
+
+        impl CommonCapabilities for $name<'_> {
+            async fn send<D: DeserializeOwned>(&self, msg: &str) -> TpResult<D> {
+                match self {
+                    $( $name::$model(d) => d.send(msg).await, )+
+                }
+            }
+
+            fn child_id(&self) -> Option<&str> {
+                match self {
+                    $( $name::$model(d) => d.child_id(), )+
+                }
+            }
+
+            async fn switch_on(&mut self) -> TpResult<()> {
+                match self {
+                    $( $name::$model(d) => d.switch_on().await, )+
+                }
+            }
+
+            async fn switch_off(&mut self) -> TpResult<()> {
+                match self {
+                    $( $name::$model(d) => d.switch_off().await, )+
+                }
+            }
+        }
+    };
+}
+
+device_ref!(
+    /// A dimmable switch, see [`Device::try_into_dimmable`]
+    DimmerRef { ES20M, HS220 }
+);
+impl Dimmable for DimmerRef<'_> {}
+
+device_ref!(
+    /// A plug or bulb that meters energy, see
+    /// [`Device::try_into_energy_meter`]
+    EnergyMeterRef { HS110, KL130 }
+);
+
+impl EnergyMeter for EnergyMeterRef<'_> {
+    fn emeter_module(&self) -> &'static str {
+        match self {
+            EnergyMeterRef::HS110(d) => d.emeter_module(),
+            EnergyMeterRef::KL130(d) => d.emeter_module(),
+        }
+    }
+}
+
+device_ref!(
+    /// A plug or switch, which unlike a bulb has a relay that can be timed,
+    /// see [`Device::try_into_countdown`] and [`Device::try_into_away_mode`]
+    RelayRef { EP10, ES20M, HS110, HS220, KP303 }
+);
+impl Countdown for RelayRef<'_> {}
+impl AwayMode for RelayRef<'_> {}
+
+device_ref!(
+    /// A dimmer or bulb, see [`Device::try_into_presets`]
+    PresetsRef { ES20M, HS220, KL130 }
+);
+
+impl Presets for PresetsRef<'_> {
+    fn preset_module(&self) -> &'static str {
+        match self {
+            PresetsRef::ES20M(d) => d.preset_module(),
+            PresetsRef::HS220(d) => d.preset_module(),
+            PresetsRef::KL130(d) => d.preset_module(),
+        }
+    }
+
+    async fn set_preset_state(&mut self, preset: &PreferredState) -> TpResult<()> {
+        match self {
+            PresetsRef::ES20M(d) => d.set_preset_state(preset).await,
+            PresetsRef::HS220(d) => d.set_preset_state(preset).await,
+            PresetsRef::KL130(d) => d.set_preset_state(preset).await,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Device {
+    /// Device variant for an EP10 smart plug
+    EP10(EP10),
+    /// Dimmable switch with motion and ambient light sensors
+    ES20M(ES20M),
+    /// Smart plug or power strip with energy monitoring
+    HS110(HS110),
+    /// Dimmable switch
+    HS220(HS220),
+    /// Smart bulb. Whether it does color or color temperature is reported
+    /// in its sysinfo.
+    KL130(KL130),
+    /// Power strip or multi-outlet plug without energy monitoring
+    KP303(KP303),
+}
+
+impl Device {
+    pub fn try_new(addr: SocketAddr, model: &str) -> TpResult<Device> {
+        // let model = &device_data.sysinfo().model;
+        if model.contains("EP10") {
+            Ok(Device::EP10(EP10::new(addr)))
+        } else if ["HS110", "KP115", "KP125", "HS300"]
+            .iter()
+            .any(|m| model.contains(m))
+        {
+            Ok(Device::HS110(HS110::new(addr)))
+        } else if model.contains("ES20M") {
+            Ok(Device::ES20M(ES20M::new(addr)))
+        } else if model.contains("HS220") || model.contains("KP405") {
+            Ok(Device::HS220(HS220::new(addr)))
+        } else if ["KL110", "KL130", "LB130"]
+            .iter()
+            .any(|m| model.contains(m))
+        {
+            Ok(Device::KL130(KL130::new(addr)))
+        } else if ["KP303", "KP400", "HS107"]
+            .iter()
+            .any(|m| model.contains(m))
+        {
+            Ok(Device::KP303(KP303::new(addr)))
+        } else {
+            Err(TpError::UnknownModel(model.to_string()))
+            // warn!("Unknown device model {:?}", model);
+            // None
+        }
+    }
+
+    /// Pick the device type from what a probe found the device can do, which
+    /// works for models and regional variants `try_new` doesn't know
+    pub fn from_capabilities(addr: SocketAddr, capabilities: &DeviceCapabilities) -> Device {
+        if capabilities.lighting {
+            Device::KL130(KL130::new(addr))
+        } else if capabilities.dimmer && capabilities.motion {
+            Device::ES20M(ES20M::new(addr))
+        } else if capabilities.dimmer {
+            Device::HS220(HS220::new(addr))
+        } else if capabilities.emeter {
+            Device::HS110(HS110::new(addr))
+        } else if capabilities.outlets {
+            Device::KP303(KP303::new(addr))
+        } else {
+            Device::EP10(EP10::new(addr))
+        }
+    }
+
+    /// Address one outlet of a power strip. `child_id` is the outlet's full
+    /// id, see [`ChildInfo::full_id`](super::models::ChildInfo::full_id).
+    pub fn try_new_child(addr: SocketAddr, model: &str, child_id: &str) -> TpResult<Device> {
+        if !["HS300", "KP303", "KP400", "HS107"]
+            .iter()
+            .any(|m| model.contains(m))
+        {
+            return Err(TpError::Unsupported("outlets".to_string()));
+        }
+        Device::try_new(addr, model)?.with_child(child_id)
+    }
+
+    /// Address one outlet of this power strip
+    pub fn with_child(mut self, child_id: &str) -> TpResult<Device> {
+        match &mut self {
+            Device::HS110(d) => d.child_id = Some(child_id.to_string()),
+            Device::KP303(d) => d.child_id = Some(child_id.to_string()),
+            _ => return Err(TpError::Unsupported("outlets".to_string())),
+        }
+        Ok(self)
+    }
+
+    /// Whether the device, or the outlet this addresses, is on and how bright
+    pub async fn get_power_state(&mut self) -> TpResult<PowerState> {
+        let sysinfo = self.get_sysinfo().await?;
+        match self.child_id() {
+            Some(child_id) => {
+                let child = sysinfo
+                    .child(child_id)
+                    .ok_or_else(|| TpError::from(format!("No outlet with id {}", child_id)))?;
+                Ok(PowerState {
+                    on: child.is_on(),
+                    brightness: None,
+                })
+            }
+            None => Ok(PowerState {
+                on: sysinfo.is_on(),
+                brightness: sysinfo.brightness.or_else(|| {
+                    sysinfo
+                        .light_state
+                        .as_ref()
+                        .and_then(|light_state| light_state.brightness())
+                }),
+            }),
+        }
+    }
+
+    /// Switch to `state`. Devices that don't dim just switch on.
+    pub async fn set_power_state(&mut self, state: &PowerState) -> TpResult<()> {
+        match (state.on, state.brightness, self) {
+            (false, _, device) => device.switch_off().await,
+            (true, Some(brightness), Device::HS220(d)) => d.switch_on_at(brightness).await,
+            (true, Some(brightness), Device::ES20M(d)) => d.switch_on_at(brightness).await,
+            (true, Some(brightness), Device::KL130(d)) => {
+                let transition = LightTransition {
+                    on: Some(true),
+                    brightness: Some(brightness),
+                    ..Default::default()
+                };
+                d.transition_light_state(&transition).await.map(drop)
+            }
+            (true, _, device) => device.switch_on().await,
+        }
+    }
+
+    pub fn try_into_dimmable(&mut self) -> TpResult<DimmerRef<'_>> {
+        match self {
+            Device::HS220(d) => Ok(DimmerRef::HS220(d)),
+            Device::ES20M(d) => Ok(DimmerRef::ES20M(d)),
+            _ => Err(TpError::Unsupported("dimmable".to_string())),
+        }
+    }
+
+    pub fn try_into_energy_meter(&mut self) -> TpResult<EnergyMeterRef<'_>> {
+        match self {
+            Device::HS110(d) => Ok(EnergyMeterRef::HS110(d)),
+            Device::KL130(d) => Ok(EnergyMeterRef::KL130(d)),
+            _ => Err(TpError::Unsupported("energy meter".to_string())),
+        }
+    }
+
+    /// Bulbs have no countdown timer
+    pub fn try_into_countdown(&mut self) -> TpResult<RelayRef<'_>> {
+        self.try_into_relay()
+            .ok_or_else(|| TpError::Unsupported("countdown".to_string()))
+    }
+
+    /// Bulbs have no away mode
+    pub fn try_into_away_mode(&mut self) -> TpResult<RelayRef<'_>> {
+        self.try_into_relay()
+            .ok_or_else(|| TpError::Unsupported("away mode".to_string()))
+    }
+
+    fn try_into_relay(&mut self) -> Option<RelayRef<'_>> {
+        match self {
+            Device::EP10(d) => Some(RelayRef::EP10(d)),
+            Device::ES20M(d) => Some(RelayRef::ES20M(d)),
+            Device::HS110(d) => Some(RelayRef::HS110(d)),
+            Device::HS220(d) => Some(RelayRef::HS220(d)),
+            Device::KP303(d) => Some(RelayRef::KP303(d)),
+            Device::KL130(_) => None,
+        }
+    }
+
+    /// Only dimmers and bulbs have presets
+    pub fn try_into_presets(&mut self) -> TpResult<PresetsRef<'_>> {
+        match self {
+            Device::ES20M(d) => Ok(PresetsRef::ES20M(d)),
+            Device::HS220(d) => Ok(PresetsRef::HS220(d)),
+            Device::KL130(d) => Ok(PresetsRef::KL130(d)),
+            _ => Err(TpError::Unsupported("presets".to_string())),
+        }
+    }
+
+    pub fn try_into_motion_sensor(&mut self) -> TpResult<&mut impl MotionSensor> {
+        match self {
+            Device::ES20M(d) => Ok(d),
+            _ => Err(TpError::Unsupported("motion sensor".to_string())),
+        }
+    }
+
+    pub fn try_into_ambient_light_sensor(&mut self) -> TpResult<&mut impl AmbientLightSensor> {
+        match self {
+            Device::ES20M(d) => Ok(d),
+            _ => Err(TpError::Unsupported("ambient light sensor".to_string())),
+        }
+    }
+
+    pub fn try_into_smart_bulb(&mut self) -> TpResult<&mut impl SmartBulb> {
+        match self {
+            Device::KL130(d) => Ok(d),
+            _ => Err(TpError::Unsupported("smart bulb".to_string())),
+        }
+    }
+}
+
+impl CommonCapabilities for Device {
+    async fn send<D: DeserializeOwned>(&self, msg: &str) -> TpResult<D> {
+        dispatch!(self, d => d.send(msg).await)
+    }
+
+    fn child_id(&self) -> Option<&str> {
+        dispatch!(self, d => d.child_id())
+    }
+
+    async fn switch_on(&mut self) -> TpResult<()> {
+        dispatch!(self, d => d.switch_on().await)
+    }
+
+    async fn switch_off(&mut self) -> TpResult<()> {
+        dispatch!(self, d => d.switch_off().await)
+    }
+}
+
+impl Schedulable for Device {
+    fn schedule_module(&self) -> &'static str {
+        dispatch!(self, d => d.schedule_module())
+    }
+}
+
+impl Timesetting for Device {
+    fn timesetting_module(&self) -> &'static str {
+        dispatch!(self, d => d.timesetting_module())
+    }
+}
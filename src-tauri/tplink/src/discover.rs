@@ -0,0 +1,258 @@
+use super::{
+    error::{TpError, TpResult},
+    models::DeviceResponse,
+    protocol::{decrypt, encrypt},
+};
+use log::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeSet, HashMap},
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    str::FromStr,
+    time::Duration,
+};
+use tokio::{
+    net::UdpSocket,
+    time::{timeout_at, Instant},
+};
+
+/// The port that devices listen on for discovery packets
+pub const DISCOVERY_PORT: u16 = 9999;
+
+/// Refuse to sweep ranges larger than this many hosts (a /22)
+const MAX_RANGE_HOSTS: u32 = 1024;
+
+/// Where discovery packets get sent
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[serde(default, rename_all = "camelCase")]
+pub struct DiscoveryConfig {
+    /// Send to the directed broadcast address of every local IPv4 interface
+    pub scan_interfaces: bool,
+    /// Send to the limited broadcast address 255.255.255.255
+    pub global_broadcast: bool,
+    /// Extra addresses to probe, either single hosts (`10.0.4.20`) or CIDR
+    /// ranges (`192.168.50.0/24`), which are swept host by host
+    pub targets: Vec<String>,
+}
+
+impl Default for DiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            scan_interfaces: true,
+            global_broadcast: true,
+            targets: vec![],
+        }
+    }
+}
+
+impl DiscoveryConfig {
+    /// Check that every user-supplied target parses
+    pub fn validate(&self) -> TpResult<()> {
+        self.targets
+            .iter()
+            .try_for_each(|target| target.parse::<DiscoveryTarget>().map(|_| ()))
+    }
+
+    /// Resolve the config into the full, deduplicated list of addresses that
+    /// discovery packets should be sent to
+    pub fn resolve(&self) -> TpResult<Vec<SocketAddr>> {
+        let mut addrs = BTreeSet::new();
+
+        if self.global_broadcast {
+            addrs.insert(Ipv4Addr::BROADCAST);
+        }
+
+        if self.scan_interfaces {
+            addrs.extend(interface_broadcast_addrs());
+        }
+
+        for target in &self.targets {
+            addrs.extend(target.parse::<DiscoveryTarget>()?.hosts());
+        }
+
+        Ok(addrs
+            .into_iter()
+            .map(|ip| SocketAddr::new(IpAddr::V4(ip), DISCOVERY_PORT))
+            .collect())
+    }
+}
+
+/// A single user-supplied discovery target
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiscoveryTarget {
+    /// A single address, which may itself be a broadcast address
+    Host(Ipv4Addr),
+    /// A CIDR range
+    Range { network: Ipv4Addr, prefix_len: u8 },
+}
+
+impl DiscoveryTarget {
+    /// Every address that should receive a discovery packet for this target
+    pub fn hosts(&self) -> Vec<Ipv4Addr> {
+        match *self {
+            DiscoveryTarget::Host(ip) => vec![ip],
+            // Point-to-point and single host ranges have no network or
+            // broadcast address to skip
+            DiscoveryTarget::Range {
+                network,
+                prefix_len: prefix_len @ 31..=32,
+            } => {
+                let first = u32::from(network);
+                (first..=first | host_mask(prefix_len))
+                    .map(Ipv4Addr::from)
+                    .collect()
+            }
+            DiscoveryTarget::Range {
+                network,
+                prefix_len,
+            } => {
+                let first = u32::from(network);
+                (first + 1..first | host_mask(prefix_len))
+                    .map(Ipv4Addr::from)
+                    .collect()
+            }
+        }
+    }
+}
+
+impl FromStr for DiscoveryTarget {
+    type Err = TpError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || TpError::from(format!("Invalid discovery target: {:?}", s));
+        let s = s.trim();
+
+        match s.split_once('/') {
+            None => s.parse().map(DiscoveryTarget::Host).map_err(|_| invalid()),
+            Some((ip, prefix_len)) => {
+                let ip = ip.parse::<Ipv4Addr>().map_err(|_| invalid())?;
+                let prefix_len = prefix_len
+                    .parse::<u8>()
+                    .ok()
+                    .filter(|len| *len <= 32)
+                    .ok_or_else(invalid)?;
+
+                if host_mask(prefix_len) >= MAX_RANGE_HOSTS {
+                    return Err(TpError::from(format!(
+                        "Discovery range {:?} is too large, the limit is /22",
+                        s
+                    )));
+                }
+
+                Ok(DiscoveryTarget::Range {
+                    network: Ipv4Addr::from(u32::from(ip) & !host_mask(prefix_len)),
+                    prefix_len,
+                })
+            }
+        }
+    }
+}
+
+/// The host portion of a network with the given prefix length
+fn host_mask(prefix_len: u8) -> u32 {
+    u32::MAX.checked_shr(prefix_len as u32).unwrap_or(0)
+}
+
+/// The directed broadcast address of every non-loopback IPv4 interface
+fn interface_broadcast_addrs() -> Vec<Ipv4Addr> {
+    let interfaces = match if_addrs::get_if_addrs() {
+        Ok(interfaces) => interfaces,
+        Err(err) => {
+            error!("Failed to enumerate network interfaces {:?}", err);
+            return vec![];
+        }
+    };
+
+    interfaces
+        .into_iter()
+        .filter(|iface| !iface.is_loopback())
+        .filter_map(|iface| match iface.addr {
+            if_addrs::IfAddr::V4(addr) => {
+                let broadcast = Ipv4Addr::from(u32::from(addr.ip) | !u32::from(addr.netmask));
+                debug!("Interface {} broadcasts to {}", iface.name, broadcast);
+                Some(broadcast)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+// "schedule": {"get_next_action": {}},
+// "cnCloud": {"get_info": {}},
+// "emeter": {"get_realtime": null},
+// "smartlife.iot.common.emeter": {"get_realtime": null},
+// "smartlife.iot.smartbulb.lightingservice": {"get_light_state": null},
+// "smartlife.iot.smartbulb.lightingservice": {"get_default_behavior":""}
+// "smartlife.iot.common.timesetting":{"get_time":{}},
+// "smartlife.iot.dimmer": {"get_default_behavior": null},
+// "smartlife.iot.dimmer": {"get_dimmer_parameters": null},
+// "smartlife.iot.smartbulb.lightingservice": {"get_light_details": null},
+const QUERY: &str = r#"{ "system": {"get_sysinfo": null} }"#;
+
+/// How long to keep listening for responses after the discovery packet is sent
+pub const DISCOVERY_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Discover devices on every network described by `config`
+pub async fn discover_devices(
+    config: &DiscoveryConfig,
+) -> TpResult<Vec<(SocketAddr, DeviceResponse)>> {
+    discover_at(&config.resolve()?).await
+}
+
+/// Send a discovery packet to each of `targets` and collect the responses.
+/// Devices that answer more than one target are only reported once.
+pub async fn discover_at(targets: &[SocketAddr]) -> TpResult<Vec<(SocketAddr, DeviceResponse)>> {
+    debug!("Begin discovery");
+    let socket_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0);
+    let udp_socket = UdpSocket::bind(socket_addr).await?;
+    udp_socket.set_broadcast(true)?;
+
+    let request = encrypt(QUERY).unwrap();
+    let mut buf = [0_u8; 4096];
+    let mut devices = HashMap::new();
+
+    for target in targets {
+        if let Err(err) = udp_socket.send_to(&request[4..request.len()], target).await {
+            // Unreachable networks shouldn't spoil the rest of the scan
+            error!("udp_socket.send_to {:?} error {:?}", target, err);
+        }
+    }
+
+    let deadline = Instant::now() + DISCOVERY_TIMEOUT;
+
+    while let Ok(received) = timeout_at(deadline, udp_socket.recv_from(&mut buf)).await {
+        // Some platforms report ICMP errors for earlier sends here, which
+        // shouldn't stop the replies that are still on their way
+        let (size, addr) = match received {
+            Ok(received) => received,
+            Err(err) => {
+                warn!("udp_socket.recv_from error {:?}", err);
+                continue;
+            }
+        };
+        debug!("Socket recvd {} bytes from {:?}", size, addr);
+
+        if devices.contains_key(&addr) {
+            info!("Already have device at {:?}", addr);
+            continue;
+        }
+
+        let data = decrypt(&mut buf[0..size]);
+        // info!("\n\nDecrypted buffer\n{}", data);
+
+        match serde_json::from_str::<DeviceResponse>(&data) {
+            Ok(device_data) => {
+                devices.insert(addr, device_data);
+                debug!("Device discovered at {:?}", addr);
+            }
+            Err(err) => {
+                error!("Device data (address {:?}) parse error {:#?}", addr, err);
+            }
+        }
+    }
+
+    debug!("Discovery complete");
+    Ok(devices.into_iter().collect())
+}
@@ -0,0 +1,276 @@
+#![allow(unused)]
+//! Error types
+use serde::{Deserialize, Serialize};
+use std::{convert::From, error, fmt, io, net::SocketAddr, result};
+
+/// TPLinker result type with [Error](enum.Error.html)
+pub type TpResult<T> = result::Result<T, TpError>;
+
+/// Error type for TPLinker
+///
+/// Errors that come from a device carry its address once it is known. The
+/// transport fills it in; errors decoded from a response can have it added
+/// with [`TpError::at`].
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub enum TpError {
+    /// The device didn't answer within the timeout
+    Timeout {
+        #[cfg_attr(feature = "utoipa", schema(value_type = Option<String>))]
+        addr: Option<SocketAddr>,
+        message: String,
+    },
+    /// Nothing is listening at the address
+    ConnectionRefused {
+        #[cfg_attr(feature = "utoipa", schema(value_type = Option<String>))]
+        addr: Option<SocketAddr>,
+    },
+    /// Any other error from std::io while talking to the device
+    IO {
+        #[cfg_attr(feature = "utoipa", schema(value_type = Option<String>))]
+        addr: Option<SocketAddr>,
+        message: String,
+    },
+    /// The response wasn't a well-formed length-prefixed frame
+    MalformedFrame {
+        #[cfg_attr(feature = "utoipa", schema(value_type = Option<String>))]
+        addr: Option<SocketAddr>,
+        message: String,
+    },
+    /// Wrapped errors from serde_json
+    Serde(String),
+    /// The device doesn't have the module (`err_code` -1)
+    ModuleNotSupported {
+        #[cfg_attr(feature = "utoipa", schema(value_type = Option<String>))]
+        addr: Option<SocketAddr>,
+        module: String,
+    },
+    /// The module doesn't have the method (`err_code` -2)
+    MethodNotSupported {
+        #[cfg_attr(feature = "utoipa", schema(value_type = Option<String>))]
+        addr: Option<SocketAddr>,
+        module: String,
+        method: String,
+    },
+    /// The device rejected the parameters (`err_code` -3)
+    InvalidArgument {
+        #[cfg_attr(feature = "utoipa", schema(value_type = Option<String>))]
+        addr: Option<SocketAddr>,
+        module: String,
+        method: String,
+        message: Option<String>,
+    },
+    /// Any other non-zero `err_code` in a section of the response
+    TPLink {
+        #[cfg_attr(feature = "utoipa", schema(value_type = Option<String>))]
+        addr: Option<SocketAddr>,
+        module: String,
+        method: Option<String>,
+        error: SectionError,
+    },
+    /// Unknown device model
+    UnknownModel(String),
+    /// Tried to use a feature that is not supported by the device
+    Unsupported(String),
+    /// A generic error
+    Unknown(String),
+}
+
+impl TpError {
+    /// Classify the error a device reported for `module`, or for `method`
+    /// within it
+    pub fn from_section(module: &str, method: Option<&str>, error: SectionError) -> Self {
+        let module = module.to_string();
+        match (error.err_code, method) {
+            (Some(-1), _) => TpError::ModuleNotSupported { addr: None, module },
+            (Some(-2), Some(method)) => TpError::MethodNotSupported {
+                addr: None,
+                module,
+                method: method.to_string(),
+            },
+            (Some(-3), Some(method)) => TpError::InvalidArgument {
+                addr: None,
+                module,
+                method: method.to_string(),
+                message: error.err_msg,
+            },
+            _ => TpError::TPLink {
+                addr: None,
+                module,
+                method: method.map(str::to_string),
+                error,
+            },
+        }
+    }
+
+    /// Record the address of the device the error came from, unless it
+    /// already has one
+    pub fn at(mut self, device: SocketAddr) -> Self {
+        match &mut self {
+            TpError::Timeout { addr, .. }
+            | TpError::ConnectionRefused { addr }
+            | TpError::IO { addr, .. }
+            | TpError::MalformedFrame { addr, .. }
+            | TpError::ModuleNotSupported { addr, .. }
+            | TpError::MethodNotSupported { addr, .. }
+            | TpError::InvalidArgument { addr, .. }
+            | TpError::TPLink { addr, .. } => {
+                addr.get_or_insert(device);
+            }
+            _ => {}
+        }
+        self
+    }
+
+    /// The address of the device the error came from, if known
+    pub fn addr(&self) -> Option<SocketAddr> {
+        match self {
+            TpError::Timeout { addr, .. }
+            | TpError::ConnectionRefused { addr }
+            | TpError::IO { addr, .. }
+            | TpError::MalformedFrame { addr, .. }
+            | TpError::ModuleNotSupported { addr, .. }
+            | TpError::MethodNotSupported { addr, .. }
+            | TpError::InvalidArgument { addr, .. }
+            | TpError::TPLink { addr, .. } => *addr,
+            _ => None,
+        }
+    }
+
+    /// Whether the device couldn't be reached at all, as opposed to
+    /// answering with an error
+    pub fn is_unreachable(&self) -> bool {
+        matches!(
+            self,
+            TpError::Timeout { .. } | TpError::ConnectionRefused { .. } | TpError::IO { .. }
+        )
+    }
+}
+
+impl fmt::Display for TpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TpError::Timeout { message, .. } => write!(f, "Timed out: {}", message),
+            TpError::ConnectionRefused { .. } => f.write_str("The device refused the connection"),
+            TpError::IO { message, .. } => write!(f, "Error connecting to the device: {}", message),
+            TpError::MalformedFrame { message, .. } => {
+                write!(f, "Malformed response from the device: {}", message)
+            }
+            TpError::Serde(_) => {
+                f.write_str("Could not parse the response received from the device")
+            }
+            TpError::ModuleNotSupported { module, .. } => {
+                write!(f, "Module not supported: {:?}", module)
+            }
+            TpError::MethodNotSupported { module, method, .. } => {
+                write!(f, "Method not supported: {:?} in {:?}", method, module)
+            }
+            TpError::InvalidArgument { module, method, .. } => {
+                write!(f, "Invalid argument to {:?} in {:?}", method, module)
+            }
+            TpError::TPLink {
+                module,
+                error: SectionError { err_code, err_msg },
+                ..
+            } => f.write_str(&format!(
+                "Response data error in {:?}: ({:?}) {:?}",
+                module, err_code, err_msg,
+            )),
+            TpError::Unknown(err) => f.write_str(err),
+            TpError::UnknownModel(model) => {
+                f.write_str(&format!("Unknown device model: {:?}", model))
+            }
+            TpError::Unsupported(feature) => {
+                f.write_str(&format!("Unsupported feature: {:?}", feature))
+            }
+        }?;
+
+        match self.addr() {
+            Some(addr) => write!(f, " ({})", addr),
+            None => Ok(()),
+        }
+    }
+}
+
+impl error::Error for TpError {
+    fn description(&self) -> &str {
+        match self {
+            TpError::Timeout { .. } => "Timed out",
+            TpError::ConnectionRefused { .. } => "Connection refused",
+            TpError::IO { .. } => "Error connecting to the device",
+            TpError::MalformedFrame { .. } => "Malformed response",
+            TpError::Serde(_) => "Could not parse the response received from the device",
+            TpError::ModuleNotSupported { .. } => "Module not supported",
+            TpError::MethodNotSupported { .. } => "Method not supported",
+            TpError::InvalidArgument { .. } => "Invalid argument",
+            TpError::TPLink { .. } => "Response data error",
+            TpError::Unknown(_) => "Unknown error",
+            TpError::UnknownModel(_) => "Unknown device model",
+            TpError::Unsupported(feature) => "Unsupported feature",
+        }
+    }
+}
+
+impl From<io::Error> for TpError {
+    fn from(error: io::Error) -> Self {
+        let message = error.to_string();
+        match error.kind() {
+            io::ErrorKind::TimedOut => TpError::Timeout {
+                addr: None,
+                message,
+            },
+            io::ErrorKind::ConnectionRefused => TpError::ConnectionRefused { addr: None },
+            io::ErrorKind::UnexpectedEof => TpError::MalformedFrame {
+                addr: None,
+                message,
+            },
+            _ => TpError::IO {
+                addr: None,
+                message,
+            },
+        }
+    }
+}
+
+impl From<serde_json::Error> for TpError {
+    fn from(error: serde_json::Error) -> Self {
+        TpError::Serde(format!("{:#?}", error))
+    }
+}
+
+impl From<&str> for TpError {
+    fn from(error: &str) -> Self {
+        TpError::Unknown(String::from(error))
+    }
+}
+
+impl From<String> for TpError {
+    fn from(error: String) -> Self {
+        TpError::Unknown(error)
+    }
+}
+
+/// Error response for a section of the JSON response
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct SectionError {
+    /// The error code. Zero if no error.
+    pub err_code: Option<i16>,
+
+    /// The error message.
+    pub err_msg: Option<String>,
+}
+
+impl fmt::Display for SectionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&format!("{:?}: {:?}", self.err_code, self.err_msg))
+    }
+}
+
+impl error::Error for SectionError {
+    fn description(&self) -> &str {
+        "TPLink section error"
+    }
+}
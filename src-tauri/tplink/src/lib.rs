@@ -0,0 +1,28 @@
+//! Control TP-Link Kasa smart plugs, switches, power strips and bulbs over
+//! the local network.
+//!
+//! [`devices::Device`] talks to any supported model, with what each model
+//! can do split into the capability traits in [`prelude`]. [`discover`] finds
+//! devices by broadcast or by probing addresses, and [`protocol`] has the
+//! codec devices speak on both TCP and UDP.
+//!
+//! The `tokio` feature, on by default, provides everything that does network
+//! IO. Without it only the models and codec are available. The `specta` and
+//! `utoipa` features derive TypeScript and OpenAPI types for the models.
+pub mod capabilities;
+#[cfg(feature = "tokio")]
+pub mod devices;
+#[cfg(feature = "tokio")]
+pub mod discover;
+pub mod error;
+pub mod models;
+pub mod protocol;
+pub mod requests;
+#[cfg(feature = "tokio")]
+pub mod simulator;
+pub mod solar;
+pub mod timezones;
+
+pub mod prelude {
+    pub use super::capabilities::*;
+}
@@ -0,0 +1,1228 @@
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Timelike};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+type ErrCode = i16;
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct DeviceResponse {
+    pub system: System,
+}
+
+impl DeviceResponse {
+    pub fn sysinfo(&self) -> &SysInfo {
+        &self.system.sysinfo
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct System {
+    #[serde(rename = "get_sysinfo")]
+    pub sysinfo: SysInfo,
+}
+
+/// Everything a device reports about itself in `system.get_sysinfo`.
+///
+/// Fields differ between device types and firmware. Anything not modelled
+/// here is kept in `other`, so a `SysInfo` serializes back to what the
+/// device sent.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct SysInfo {
+    /// What is currently driving the relay
+    pub active_mode: Option<ActiveMode>,
+    pub alias: String,
+    pub brightness: Option<u8>,
+    /// The outlets of a power strip
+    pub children: Option<Vec<ChildInfo>>,
+    /// The product name, e.g. "Smart Wi-Fi Dimmer"
+    pub dev_name: Option<String>,
+    #[serde(rename = "deviceId")]
+    pub device_id: String,
+    pub err_code: ErrCode,
+    /// Colon-separated feature flags, e.g. `TIM:ENE` for a device with
+    /// timers and an energy meter
+    pub feature: Option<String>,
+    #[serde(rename = "hwId")]
+    pub hw_id: String,
+    #[serde(alias = "type")]
+    #[serde(alias = "mic_type")]
+    pub hw_type: String,
+    pub hw_ver: String,
+    pub icon_hash: Option<String>,
+    /// Set on bulbs that can change hue and saturation
+    pub is_color: Option<u8>,
+    /// In degrees. Sent as `latitude_i` in ten-thousandths of a degree, or as
+    /// `latitude` in degrees by older firmware.
+    #[serde(
+        rename = "latitude_i",
+        alias = "latitude",
+        default,
+        with = "coordinate",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub latitude: Option<f64>,
+    /// Whether the status LED is turned off
+    #[serde(default, with = "flag", skip_serializing_if = "Option::is_none")]
+    pub led_off: Option<bool>,
+    /// Present on smart bulbs, which have no relay
+    pub light_state: Option<LightState>,
+    /// In degrees, see `latitude`
+    #[serde(
+        rename = "longitude_i",
+        alias = "longitude",
+        default,
+        with = "coordinate",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub longitude: Option<f64>,
+    #[serde(alias = "mic_mac")]
+    pub mac: String,
+    pub model: String,
+    /// What the device's schedule will do next
+    pub next_action: Option<NextAction>,
+    /// Thermistor state on switches that have one
+    pub ntc_state: Option<u8>,
+    /// Where the device was set up from, e.g. `tplink`
+    pub obd_src: Option<String>,
+    #[serde(rename = "oemId")]
+    pub oem_id: Option<String>,
+    pub on_time: Option<i64>,
+    /// Brightness presets of a dimmer, selectable from the Kasa app
+    pub preferred_state: Option<Vec<PreferredState>>,
+    pub relay_state: Option<u8>,
+    pub rssi: i32,
+    /// `new` until the device has been set up with the Kasa app
+    pub status: Option<String>,
+    pub sw_ver: String,
+    pub updating: Option<u8>,
+    /// Fields this version doesn't know about
+    #[serde(flatten)]
+    #[cfg_attr(feature = "specta", specta(skip))]
+    pub other: Map<String, Value>,
+}
+
+impl SysInfo {
+    /// If the device isn't dimmable, returns 0 or 100 based solely on relay
+    /// state
+    #[allow(unused)]
+    pub fn brightness(&self) -> u8 {
+        self.brightness
+            .or_else(|| self.light_state.as_ref().and_then(LightState::brightness))
+            .unwrap_or(if self.is_on() { 100 } else { 0 })
+    }
+
+    /// The full id of every outlet, in the form requests address them by
+    pub fn child_ids(&self) -> Vec<String> {
+        self.children
+            .iter()
+            .flatten()
+            .map(|child| child.full_id(&self.device_id))
+            .collect()
+    }
+
+    pub fn child(&self, child_id: &str) -> Option<&ChildInfo> {
+        self.children
+            .iter()
+            .flatten()
+            .find(|child| child.full_id(&self.device_id) == child_id)
+    }
+
+    pub fn is_color(&self) -> bool {
+        self.is_color.unwrap_or(0) > 0
+    }
+
+    /// Where the device was set up, as (latitude, longitude) in degrees
+    pub fn location(&self) -> Option<(f64, f64)> {
+        self.latitude.zip(self.longitude)
+    }
+
+    /// Whether the status LED is lit while the device is on
+    pub fn led_on(&self) -> bool {
+        !self.led_off.unwrap_or(false)
+    }
+
+    pub fn is_on(&self) -> bool {
+        match (self.relay_state, &self.light_state) {
+            (Some(relay_state), _) => relay_state > 0,
+            (None, Some(light_state)) => light_state.is_on(),
+            (None, None) => false,
+        }
+    }
+}
+
+/// Which feature last switched the relay, from sysinfo's `active_mode`
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ActiveMode {
+    None,
+    Schedule,
+    CountDown,
+    AntiTheft,
+    #[serde(other)]
+    Unknown,
+}
+
+/// A brightness preset of a dimmer, or a light preset of a bulb
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct PreferredState {
+    pub index: u8,
+    pub brightness: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hue: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub saturation: Option<u8>,
+    /// In kelvin
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color_temp: Option<u16>,
+}
+
+/// Whether a device or outlet is on and, for dimmers and bulbs, how bright
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct PowerState {
+    pub on: bool,
+    /// 1-100. Ignored by devices that don't dim.
+    pub brightness: Option<u8>,
+}
+
+impl From<&PreferredState> for LightTransition {
+    fn from(preset: &PreferredState) -> Self {
+        Self {
+            on: Some(true),
+            hue: preset.hue,
+            saturation: preset.saturation,
+            color_temp: preset.color_temp,
+            brightness: Some(preset.brightness),
+            transition_period: None,
+        }
+    }
+}
+
+/// Coordinates sent either as integer ten-thousandths of a degree or, by
+/// older firmware, as float degrees
+mod coordinate {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde_json::Number;
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<f64>, D::Error> {
+        Ok(
+            Option::<Number>::deserialize(d)?.and_then(|n| match n.as_i64() {
+                Some(i) => Some(i as f64 / 10_000.0),
+                None => n.as_f64(),
+            }),
+        )
+    }
+
+    pub fn serialize<S: Serializer>(degrees: &Option<f64>, s: S) -> Result<S::Ok, S::Error> {
+        match degrees {
+            Some(degrees) => s.serialize_i64((degrees * 10_000.0).round() as i64),
+            None => s.serialize_none(),
+        }
+    }
+}
+
+/// Booleans sent as 0 or 1
+mod flag {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<bool>, D::Error> {
+        Ok(Option::<u8>::deserialize(d)?.map(|flag| flag > 0))
+    }
+
+    pub fn serialize<S: Serializer>(flag: &Option<bool>, s: S) -> Result<S::Ok, S::Error> {
+        match flag {
+            Some(flag) => s.serialize_u8((*flag).into()),
+            None => s.serialize_none(),
+        }
+    }
+}
+
+/// Dimmer tuning from `smartlife.iot.dimmer.get_dimmer_parameters`.
+/// All times are in milliseconds.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct DimmerParameters {
+    /// The lowest brightness the connected load can be driven at
+    #[serde(rename = "minThreshold")]
+    pub min_threshold: u8,
+    /// Fade time when switched on from the app
+    #[serde(rename = "fadeOnTime")]
+    pub fade_on_time: u32,
+    /// Fade time when switched off from the app
+    #[serde(rename = "fadeOffTime")]
+    pub fade_off_time: u32,
+    /// Fade time when switched on with a press of the switch
+    #[serde(rename = "gentleOnTime")]
+    pub gentle_on_time: u32,
+    /// Fade time when switched off with a press of the switch
+    #[serde(rename = "gentleOffTime")]
+    pub gentle_off_time: u32,
+    /// How quickly brightness changes while the switch is held
+    #[serde(rename = "rampRate")]
+    pub ramp_rate: u32,
+    pub bulb_type: u8,
+    pub calibration_type: Option<u8>,
+}
+
+/// What a dimmer does in response to each kind of input, from
+/// `smartlife.iot.dimmer.get_default_behavior`
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct DefaultBehavior {
+    pub hard_on: Option<BehaviorAction>,
+    pub soft_on: Option<BehaviorAction>,
+    pub long_press: Option<BehaviorAction>,
+    pub double_click: Option<BehaviorAction>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct BehaviorAction {
+    pub mode: BehaviorMode,
+    /// The preset slot to apply when `mode` is `customize_preset`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<u8>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum BehaviorMode {
+    None,
+    LastStatus,
+    InstantOnOff,
+    GentleOnOff,
+    CustomizePreset,
+    /// A mode introduced by newer firmware
+    #[serde(other)]
+    Unknown,
+}
+
+/// The buttons on a dimmer whose action can be reassigned
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum DimmerButton {
+    LongPress,
+    DoubleClick,
+}
+
+/// How `set_dimmer_transition` moves to the new brightness
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum TransitionMode {
+    GentleOnOff,
+    InstantOnOff,
+}
+
+/// A motion sensor's settings, from `smartlife.iot.PIR.get_config`
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct MotionConfig {
+    pub enabled: bool,
+    /// Which of `thresholds` is in use
+    pub range: MotionRange,
+    /// How long after the last motion the light stays on, in milliseconds
+    pub cold_time: u32,
+    /// The trigger threshold for each range, far to near then custom, as a
+    /// percentage of the sensor's span
+    pub thresholds: Vec<u8>,
+    /// The span of raw sensor readings
+    pub min_adc: u32,
+    pub max_adc: u32,
+}
+
+/// How far away motion is noticed. Nearer ranges need more motion.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub enum MotionRange {
+    Far,
+    Mid,
+    Near,
+    /// The threshold set with `set_trigger_sens`
+    Custom,
+}
+
+impl MotionRange {
+    /// The sensor's `trigger_index` for this range
+    pub fn index(self) -> u8 {
+        self as u8
+    }
+}
+
+impl From<u8> for MotionRange {
+    fn from(index: u8) -> Self {
+        match index {
+            0 => MotionRange::Far,
+            1 => MotionRange::Mid,
+            2 => MotionRange::Near,
+            _ => MotionRange::Custom,
+        }
+    }
+}
+
+/// `smartlife.iot.PIR.get_config` as sent by the device
+#[derive(Debug, Deserialize)]
+pub(crate) struct RawMotionConfig {
+    enable: u8,
+    trigger_index: u8,
+    cold_time: u32,
+    #[serde(default)]
+    array: Vec<u8>,
+    #[serde(default)]
+    min_adc: u32,
+    #[serde(default)]
+    max_adc: u32,
+}
+
+impl From<RawMotionConfig> for MotionConfig {
+    fn from(raw: RawMotionConfig) -> Self {
+        Self {
+            enabled: raw.enable > 0,
+            range: raw.trigger_index.into(),
+            cold_time: raw.cold_time,
+            thresholds: raw.array,
+            min_adc: raw.min_adc,
+            max_adc: raw.max_adc,
+        }
+    }
+}
+
+/// An ambient light sensor's settings, from `smartlife.iot.LAS.get_config`.
+/// Motion only switches the light on while it is darker than the level at
+/// `dark_index`.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct AmbientLightConfig {
+    pub enabled: bool,
+    /// Which of `levels` is the threshold
+    pub dark_index: u8,
+    pub levels: Vec<AmbientLightLevel>,
+    /// The span of raw sensor readings
+    pub min_adc: u32,
+    pub max_adc: u32,
+}
+
+impl AmbientLightConfig {
+    /// The brightness, in percent, below which motion switches the light on
+    pub fn threshold(&self) -> Option<u8> {
+        self.levels
+            .get(usize::from(self.dark_index))
+            .map(|level| level.value)
+    }
+}
+
+/// A named light level such as "dawn" or "twilight"
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct AmbientLightLevel {
+    pub name: String,
+    /// The raw sensor reading
+    pub adc: u32,
+    /// In percent of the sensor's span
+    pub value: u8,
+}
+
+/// `smartlife.iot.LAS.get_config` as sent by the device, which has a list of
+/// sensors although an ES20M has just the one
+#[derive(Debug, Deserialize)]
+pub(crate) struct RawAmbientLightConfig {
+    pub devs: Vec<RawAmbientLightSensor>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct RawAmbientLightSensor {
+    enable: u8,
+    dark_index: u8,
+    #[serde(default)]
+    level_array: Vec<AmbientLightLevel>,
+    #[serde(default)]
+    min_adc: u32,
+    #[serde(default)]
+    max_adc: u32,
+}
+
+impl From<RawAmbientLightSensor> for AmbientLightConfig {
+    fn from(raw: RawAmbientLightSensor) -> Self {
+        Self {
+            enabled: raw.enable > 0,
+            dark_index: raw.dark_index,
+            levels: raw.level_array,
+            min_adc: raw.min_adc,
+            max_adc: raw.max_adc,
+        }
+    }
+}
+
+/// A sensor reading that is just a `value`, such as
+/// `smartlife.iot.LAS.get_current_brt`
+#[derive(Debug, Deserialize)]
+pub(crate) struct RawSensorValue {
+    pub value: u32,
+}
+
+/// An outlet of a power strip, from the `children` of its sysinfo
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct ChildInfo {
+    /// Either the full child id or just its two digit index, depending on
+    /// firmware. See [`ChildInfo::full_id`].
+    pub id: String,
+    pub alias: String,
+    pub state: u8,
+    pub on_time: Option<i64>,
+    /// What the outlet's schedule will do next
+    pub next_action: Option<NextAction>,
+}
+
+impl ChildInfo {
+    /// Outlets are addressed by the strip's device id followed by their
+    /// index, which some firmware reports on its own
+    pub fn full_id(&self, parent_id: &str) -> String {
+        if self.id.starts_with(parent_id) {
+            self.id.clone()
+        } else {
+            format!("{}{}", parent_id, self.id)
+        }
+    }
+
+    pub fn is_on(&self) -> bool {
+        self.state > 0
+    }
+}
+
+/// The state of a smart bulb's light, from
+/// `smartlife.iot.smartbulb.lightingservice.get_light_state`.
+///
+/// While the bulb is off only `on_off` and `dft_on_state` are reported.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct LightState {
+    pub on_off: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hue: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub saturation: Option<u8>,
+    /// In kelvin
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color_temp: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub brightness: Option<u8>,
+    /// The state the bulb returns to when it is switched back on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dft_on_state: Option<LightPreset>,
+}
+
+impl LightState {
+    pub fn is_on(&self) -> bool {
+        self.on_off > 0
+    }
+
+    /// The current brightness, or the brightness the bulb will return to
+    /// when it is off
+    pub fn brightness(&self) -> Option<u8> {
+        self.brightness.or_else(|| {
+            self.dft_on_state
+                .as_ref()
+                .and_then(|preset| preset.brightness)
+        })
+    }
+}
+
+/// A color and brightness a bulb can be in. Color temperature takes
+/// precedence over hue and saturation when it is non-zero.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct LightPreset {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hue: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub saturation: Option<u8>,
+    /// In kelvin
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color_temp: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub brightness: Option<u8>,
+}
+
+/// A change to a bulb's light state. Fields left as `None` are unchanged.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct LightTransition {
+    pub on: Option<bool>,
+    /// 0-360
+    pub hue: Option<u16>,
+    /// 0-100
+    pub saturation: Option<u8>,
+    /// In kelvin, or 0 to switch to hue and saturation
+    pub color_temp: Option<u16>,
+    /// 0-100
+    pub brightness: Option<u8>,
+    /// How long the change should take, in milliseconds
+    pub transition_period: Option<u32>,
+}
+
+/// The hardware details of a bulb, from
+/// `smartlife.iot.smartbulb.lightingservice.get_light_details`
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct LightDetails {
+    pub lamp_beam_angle: u16,
+    pub min_voltage: u16,
+    pub max_voltage: u16,
+    pub wattage: u16,
+    pub incandescent_equivalent: u16,
+    pub max_lumens: u16,
+    pub color_rendering_index: u16,
+}
+
+/// A reading from `emeter.get_realtime`, in volts, amps, watts and
+/// kilowatt-hours regardless of which units the firmware reports
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct EmeterRealtime {
+    pub voltage: f64,
+    pub current: f64,
+    pub power: f64,
+    /// Energy used since the stats were last erased
+    pub total: f64,
+}
+
+/// `emeter.get_realtime` as sent by the device. Older firmware reports
+/// `voltage`/`current`/`power`/`total` in V/A/W/kWh, newer firmware reports
+/// `voltage_mv`/`current_ma`/`power_mw`/`total_wh`.
+#[derive(Debug, Deserialize)]
+pub(crate) struct RawEmeterRealtime {
+    voltage: Option<f64>,
+    voltage_mv: Option<f64>,
+    current: Option<f64>,
+    current_ma: Option<f64>,
+    power: Option<f64>,
+    power_mw: Option<f64>,
+    total: Option<f64>,
+    total_wh: Option<f64>,
+}
+
+impl From<RawEmeterRealtime> for EmeterRealtime {
+    fn from(raw: RawEmeterRealtime) -> Self {
+        let milli = |si: Option<f64>, milli: Option<f64>| {
+            si.or(milli.map(|value| value / 1000.0)).unwrap_or_default()
+        };
+
+        Self {
+            voltage: milli(raw.voltage, raw.voltage_mv),
+            current: milli(raw.current, raw.current_ma),
+            power: milli(raw.power, raw.power_mw),
+            total: milli(raw.total, raw.total_wh),
+        }
+    }
+}
+
+/// Energy used over a day or a month, from `emeter.get_daystat` and
+/// `emeter.get_monthstat`
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct EmeterStat {
+    pub year: u16,
+    pub month: u8,
+    /// Only set for daily stats
+    pub day: Option<u8>,
+    /// In kilowatt-hours
+    pub energy: f64,
+}
+
+/// A day or month in `day_list` or `month_list`, in kWh (`energy`) or Wh
+/// (`energy_wh`) depending on firmware
+#[derive(Debug, Deserialize)]
+pub(crate) struct RawEmeterStat {
+    year: u16,
+    month: u8,
+    day: Option<u8>,
+    energy: Option<f64>,
+    energy_wh: Option<f64>,
+}
+
+impl From<RawEmeterStat> for EmeterStat {
+    fn from(raw: RawEmeterStat) -> Self {
+        Self {
+            year: raw.year,
+            month: raw.month,
+            day: raw.day,
+            energy: raw
+                .energy
+                .or(raw.energy_wh.map(|wh| wh / 1000.0))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct RawDayStat {
+    pub day_list: Vec<RawEmeterStat>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct RawMonthStat {
+    pub month_list: Vec<RawEmeterStat>,
+}
+
+/// What a device will do next on its own, from `get_next_action` or the
+/// `next_action` field of sysinfo
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(from = "RawNextAction", into = "RawNextAction")]
+pub enum NextAction {
+    /// Nothing is scheduled
+    None,
+    /// A schedule rule will switch the device on or off
+    Schedule {
+        /// The rule that will fire
+        id: Option<String>,
+        /// Seconds after midnight, device local time
+        at: u32,
+        on: bool,
+    },
+    /// Something else, such as a timer, which firmware reports with other
+    /// types
+    Other {
+        action_type: i8,
+        id: Option<String>,
+        at: Option<u32>,
+        on: Option<bool>,
+    },
+}
+
+impl NextAction {
+    pub fn is_scheduled(&self) -> bool {
+        !matches!(self, NextAction::None)
+    }
+}
+
+/// A [`NextAction`] in the form the device sends it, which is also how the
+/// frontend sees it
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "specta", specta(remote = NextAction, rename = "NextAction"))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub(crate) struct RawNextAction {
+    /// -1 when nothing is scheduled, 1 for a schedule rule
+    #[serde(rename = "type")]
+    action_type: i8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    /// Seconds after midnight, device local time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    schd_sec: Option<u32>,
+    /// 1 to switch on, 0 to switch off
+    #[serde(skip_serializing_if = "Option::is_none")]
+    action: Option<i8>,
+}
+
+#[cfg(feature = "utoipa")]
+impl utoipa::PartialSchema for NextAction {
+    fn schema() -> utoipa::openapi::RefOr<utoipa::openapi::schema::Schema> {
+        <RawNextAction as utoipa::PartialSchema>::schema()
+    }
+}
+
+#[cfg(feature = "utoipa")]
+impl utoipa::ToSchema for NextAction {}
+
+impl From<RawNextAction> for NextAction {
+    fn from(raw: RawNextAction) -> Self {
+        match (raw.action_type, raw.schd_sec, raw.action) {
+            (..=-1, ..) => NextAction::None,
+            (1, Some(at), Some(action)) => NextAction::Schedule {
+                id: raw.id,
+                at,
+                on: action > 0,
+            },
+            (action_type, at, action) => NextAction::Other {
+                action_type,
+                id: raw.id,
+                at,
+                on: action.map(|action| action > 0),
+            },
+        }
+    }
+}
+
+impl From<NextAction> for RawNextAction {
+    fn from(next: NextAction) -> Self {
+        match next {
+            NextAction::None => Self {
+                action_type: -1,
+                id: None,
+                schd_sec: None,
+                action: None,
+            },
+            NextAction::Schedule { id, at, on } => Self {
+                action_type: 1,
+                id,
+                schd_sec: Some(at),
+                action: Some(on.into()),
+            },
+            NextAction::Other {
+                action_type,
+                id,
+                at,
+                on,
+            } => Self {
+                action_type,
+                id,
+                schd_sec: at,
+                action: on.map(i8::from),
+            },
+        }
+    }
+}
+
+/// The rules of the `schedule` module, and whether they run at all
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct ScheduleRules {
+    pub enabled: bool,
+    pub rules: Vec<ScheduleRule>,
+}
+
+/// A rule that switches the device on or off at a time of day
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleRule {
+    /// Assigned by the device when the rule is added
+    pub id: Option<String>,
+    pub name: String,
+    pub enabled: bool,
+    /// The days the rule runs on, Sunday first
+    pub weekdays: [bool; 7],
+    /// Run every week rather than once
+    pub repeat: bool,
+    pub time: RuleTime,
+    /// Switch on rather than off
+    pub on: bool,
+    /// For dimmers and bulbs, the brightness to switch on at
+    pub brightness: Option<u8>,
+}
+
+/// When a rule fires, in device local time
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum RuleTime {
+    /// Minutes after midnight
+    At { minute: u16 },
+    /// Minutes after sunrise, negative for before
+    Sunrise { offset: i16 },
+    /// Minutes after sunset, negative for before
+    Sunset { offset: i16 },
+}
+
+impl RuleTime {
+    /// From a rule's `*time_opt`, `*min` and `*offset`
+    pub(crate) fn from_raw(opt: i8, min: u16, offset: i16) -> Self {
+        match opt {
+            1 => RuleTime::Sunrise { offset },
+            2 => RuleTime::Sunset { offset },
+            _ => RuleTime::At { minute: min },
+        }
+    }
+
+    /// As a rule's `*time_opt`, `*min` and `*offset`
+    pub(crate) fn to_raw(self) -> (i8, u16, i16) {
+        match self {
+            RuleTime::At { minute } => (0, minute, 0),
+            RuleTime::Sunrise { offset } => (1, 0, offset),
+            RuleTime::Sunset { offset } => (2, 0, offset),
+        }
+    }
+}
+
+/// A rule as sent to and from the device. Only the start of a rule (`s*`) is
+/// used; the end (`e*`) is always disabled.
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct RawRule {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(default)]
+    name: String,
+    enable: u8,
+    wday: [u8; 7],
+    repeat: u8,
+    /// 0 for a fixed time, 1 for sunrise, 2 for sunset
+    stime_opt: i8,
+    smin: u16,
+    #[serde(default)]
+    soffset: i16,
+    /// 0 to switch off, 1 to switch on, 2 to switch on with `s_light`
+    sact: i8,
+    etime_opt: i8,
+    emin: u16,
+    eact: i8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    s_light: Option<LightPreset>,
+    #[serde(default)]
+    year: u16,
+    #[serde(default)]
+    month: u8,
+    #[serde(default)]
+    day: u8,
+}
+
+impl From<RawRule> for ScheduleRule {
+    fn from(raw: RawRule) -> Self {
+        Self {
+            id: raw.id,
+            name: raw.name,
+            enabled: raw.enable > 0,
+            weekdays: raw.wday.map(|day| day > 0),
+            repeat: raw.repeat > 0,
+            time: RuleTime::from_raw(raw.stime_opt, raw.smin, raw.soffset),
+            on: raw.sact > 0,
+            brightness: raw.s_light.and_then(|light| light.brightness),
+        }
+    }
+}
+
+impl From<&ScheduleRule> for RawRule {
+    fn from(rule: &ScheduleRule) -> Self {
+        let (stime_opt, smin, soffset) = rule.time.to_raw();
+        let s_light = rule
+            .brightness
+            .filter(|_| rule.on)
+            .map(|brightness| LightPreset {
+                brightness: Some(brightness.clamp(1, 100)),
+                ..Default::default()
+            });
+
+        Self {
+            id: rule.id.clone(),
+            name: rule.name.clone(),
+            enable: rule.enabled.into(),
+            wday: rule.weekdays.map(u8::from),
+            repeat: rule.repeat.into(),
+            stime_opt,
+            smin,
+            soffset,
+            sact: match (rule.on, &s_light) {
+                (false, _) => 0,
+                (true, None) => 1,
+                (true, Some(_)) => 2,
+            },
+            etime_opt: -1,
+            emin: 0,
+            eact: -1,
+            s_light,
+            year: 0,
+            month: 0,
+            day: 0,
+        }
+    }
+}
+
+/// The `get_rules` response of the `schedule`, `count_down` and
+/// `anti_theft` modules
+#[derive(Debug, Deserialize)]
+pub(crate) struct RawRuleList<R> {
+    pub rule_list: Vec<R>,
+    pub enable: Option<u8>,
+}
+
+/// A timer that switches the device once its delay has passed
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct CountdownRule {
+    /// Assigned by the device when the rule is added
+    pub id: Option<String>,
+    pub name: String,
+    pub enabled: bool,
+    /// Seconds from when the rule is added or enabled
+    pub delay: u32,
+    /// Switch on rather than off when the timer runs out
+    pub on: bool,
+    /// Seconds left, if the timer is running
+    pub remaining: Option<u32>,
+}
+
+/// `count_down` rule as sent to and from the device
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct RawCountdownRule {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(default)]
+    name: String,
+    enable: u8,
+    delay: u32,
+    act: u8,
+    #[serde(default, skip_serializing)]
+    remain: Option<u32>,
+}
+
+impl From<RawCountdownRule> for CountdownRule {
+    fn from(raw: RawCountdownRule) -> Self {
+        Self {
+            id: raw.id,
+            name: raw.name,
+            enabled: raw.enable > 0,
+            delay: raw.delay,
+            on: raw.act > 0,
+            remaining: raw.remain.filter(|_| raw.enable > 0),
+        }
+    }
+}
+
+impl From<&CountdownRule> for RawCountdownRule {
+    fn from(rule: &CountdownRule) -> Self {
+        Self {
+            id: rule.id.clone(),
+            name: rule.name.clone(),
+            enable: rule.enabled.into(),
+            delay: rule.delay,
+            act: rule.on.into(),
+            remain: None,
+        }
+    }
+}
+
+/// The rules of the `anti_theft` module, and whether away mode is on at all
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct AwayModeRules {
+    pub enabled: bool,
+    pub rules: Vec<AwayModeRule>,
+}
+
+/// A window in which the device switches on and off at random, so the house
+/// looks occupied
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct AwayModeRule {
+    /// Assigned by the device when the rule is added
+    pub id: Option<String>,
+    pub name: String,
+    pub enabled: bool,
+    /// The days the rule runs on, Sunday first
+    pub weekdays: [bool; 7],
+    /// Run every week rather than once
+    pub repeat: bool,
+    pub start: RuleTime,
+    pub end: RuleTime,
+}
+
+/// `anti_theft` rule as sent to and from the device
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct RawAwayModeRule {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(default)]
+    name: String,
+    enable: u8,
+    wday: [u8; 7],
+    repeat: u8,
+    stime_opt: i8,
+    smin: u16,
+    #[serde(default)]
+    soffset: i16,
+    etime_opt: i8,
+    emin: u16,
+    #[serde(default)]
+    eoffset: i16,
+    /// How often the device switches, firmware default is 5
+    #[serde(default = "default_frequency")]
+    frequency: u8,
+    #[serde(default)]
+    year: u16,
+    #[serde(default)]
+    month: u8,
+    #[serde(default)]
+    day: u8,
+}
+
+fn default_frequency() -> u8 {
+    5
+}
+
+impl From<RawAwayModeRule> for AwayModeRule {
+    fn from(raw: RawAwayModeRule) -> Self {
+        Self {
+            id: raw.id,
+            name: raw.name,
+            enabled: raw.enable > 0,
+            weekdays: raw.wday.map(|day| day > 0),
+            repeat: raw.repeat > 0,
+            start: RuleTime::from_raw(raw.stime_opt, raw.smin, raw.soffset),
+            end: RuleTime::from_raw(raw.etime_opt, raw.emin, raw.eoffset),
+        }
+    }
+}
+
+impl From<&AwayModeRule> for RawAwayModeRule {
+    fn from(rule: &AwayModeRule) -> Self {
+        let (stime_opt, smin, soffset) = rule.start.to_raw();
+        let (etime_opt, emin, eoffset) = rule.end.to_raw();
+
+        Self {
+            id: rule.id.clone(),
+            name: rule.name.clone(),
+            enable: rule.enabled.into(),
+            wday: rule.weekdays.map(u8::from),
+            repeat: rule.repeat.into(),
+            stime_opt,
+            smin,
+            soffset,
+            etime_opt,
+            emin,
+            eoffset,
+            frequency: default_frequency(),
+            year: 0,
+            month: 0,
+            day: 0,
+        }
+    }
+}
+
+/// A device's wall clock, from `get_time`. It is in the device's own
+/// timezone and carries no offset.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct DeviceTime {
+    pub year: u16,
+    pub month: u8,
+    pub mday: u8,
+    pub hour: u8,
+    pub min: u8,
+    pub sec: u8,
+}
+
+impl DeviceTime {
+    pub fn to_naive(&self) -> Option<NaiveDateTime> {
+        NaiveDate::from_ymd_opt(self.year.into(), self.month.into(), self.mday.into())?.and_hms_opt(
+            self.hour.into(),
+            self.min.into(),
+            self.sec.into(),
+        )
+    }
+}
+
+impl From<&NaiveDateTime> for DeviceTime {
+    fn from(time: &NaiveDateTime) -> Self {
+        Self {
+            year: time.year() as u16,
+            month: time.month() as u8,
+            mday: time.day() as u8,
+            hour: time.hour() as u8,
+            min: time.minute() as u8,
+            sec: time.second() as u8,
+        }
+    }
+}
+
+/// A device's timezone, from `get_timezone`
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct DeviceTimezone {
+    /// See [`timezones`](super::timezones)
+    pub index: u8,
+    /// The name the Kasa app shows, on newer firmware
+    pub zone_str: Option<String>,
+    /// POSIX TZ string, on newer firmware
+    pub tz_str: Option<String>,
+    /// Minutes added during daylight saving time
+    pub dst_offset: Option<i16>,
+}
+
+/// Which optional modules a device answers, found by probing it rather than
+/// by its model name
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceCapabilities {
+    /// `smartlife.iot.dimmer`
+    pub dimmer: bool,
+    /// `emeter` or `smartlife.iot.common.emeter`, on the device itself or on
+    /// its outlets
+    pub emeter: bool,
+    /// `smartlife.iot.smartbulb.lightingservice`
+    pub lighting: bool,
+    /// `schedule` or `smartlife.iot.common.schedule`
+    pub schedule: bool,
+    /// `count_down`
+    pub countdown: bool,
+    /// `time` or `smartlife.iot.common.timesetting`
+    pub timesetting: bool,
+    /// `cnCloud` or `smartlife.iot.common.cloud`
+    pub cloud: bool,
+    /// The device is a power strip with individually switched outlets
+    pub outlets: bool,
+    /// `smartlife.iot.PIR`, a motion sensor. Absent from capabilities probed
+    /// by older versions.
+    #[serde(default)]
+    pub motion: bool,
+}
+
+/// Everything worth knowing about a device, read in one round trip. Modules
+/// the device doesn't have are `None`.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct DeviceSnapshot {
+    pub sysinfo: SysInfo,
+    pub realtime: Option<EmeterRealtime>,
+    pub light_state: Option<LightState>,
+    pub dimmer_parameters: Option<DimmerParameters>,
+}
+
+impl From<SysInfo> for DeviceResponse {
+    fn from(sysinfo: SysInfo) -> Self {
+        Self {
+            system: System { sysinfo },
+        }
+    }
+}
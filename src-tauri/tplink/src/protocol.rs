@@ -0,0 +1,190 @@
+use super::error::{SectionError, TpError, TpResult};
+use byteorder::{BigEndian, WriteBytesExt};
+use serde::de::DeserializeOwned;
+use std::time::Duration;
+#[cfg(feature = "tokio")]
+use {
+    byteorder::ByteOrder,
+    log::*,
+    std::{future::Future, io, net::SocketAddr},
+    tokio::{net::TcpStream, time::timeout},
+};
+
+/// Encrypt a message with the autokey cipher devices expect, prefixed with
+/// its length as TCP frames are
+pub fn encrypt(plain: &str) -> Result<Vec<u8>, TpError> {
+    let len = plain.len();
+    let msgbytes = plain.as_bytes();
+    let mut cipher = vec![];
+    cipher.write_u32::<BigEndian>(len as u32)?;
+    let mut key = 0xAB;
+    let mut payload: Vec<u8> = Vec::with_capacity(len);
+
+    for i in 0..len {
+        payload.push(msgbytes[i] ^ key);
+        key = payload[i];
+    }
+
+    for i in &payload {
+        cipher.write_u8(*i).unwrap();
+    }
+
+    Ok(cipher)
+}
+
+/// Decrypt a message in place, without a length prefix
+pub fn decrypt(cipher: &mut [u8]) -> String {
+    let len = cipher.len();
+    let mut key = 0xAB;
+    let mut next: u8;
+
+    for item in cipher.iter_mut().take(len) {
+        next = *item;
+        *item ^= key;
+        key = next;
+    }
+
+    String::from_utf8_lossy(cipher).into_owned()
+}
+
+/// How long to wait for a device to accept a TCP connection
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long to wait for a single write or read to complete
+pub const IO_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Responses larger than this are assumed to be garbage rather than JSON
+#[cfg(feature = "tokio")]
+const MAX_RESPONSE_LEN: usize = 64 * 1024;
+
+/// Await `fut`, failing with a `TimedOut` IO error if it takes longer than
+/// `duration`
+#[cfg(feature = "tokio")]
+async fn with_timeout<T>(
+    duration: Duration,
+    what: &str,
+    fut: impl Future<Output = io::Result<T>>,
+) -> TpResult<T> {
+    match timeout(duration, fut).await {
+        Ok(result) => Ok(result?),
+        Err(_) => Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            format!("{} timed out after {:?}", what, duration),
+        )
+        .into()),
+    }
+}
+
+/// Send a message to a device over TCP and return its decrypted response
+#[cfg(feature = "tokio")]
+pub async fn send(addr: SocketAddr, msg: &str) -> TpResult<String> {
+    exchange(addr, msg).await.map_err(|err| err.at(addr))
+}
+
+#[cfg(feature = "tokio")]
+async fn exchange(addr: SocketAddr, msg: &str) -> TpResult<String> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    debug!("Sending to IP {:?} this message: {:?}", addr, msg);
+    let payload = encrypt(msg)?;
+    let mut stream = with_timeout(CONNECT_TIMEOUT, "connect", TcpStream::connect(addr)).await?;
+    with_timeout(IO_TIMEOUT, "write", stream.write_all(&payload)).await?;
+
+    let mut header = [0_u8; 4];
+    with_timeout(IO_TIMEOUT, "read", stream.read_exact(&mut header)).await?;
+    let length = BigEndian::read_u32(&header) as usize;
+    if length > MAX_RESPONSE_LEN {
+        return Err(TpError::MalformedFrame {
+            addr: Some(addr),
+            message: format!(
+                "response length {} exceeds maximum of {}",
+                length, MAX_RESPONSE_LEN
+            ),
+        });
+    }
+
+    let mut resp = vec![0_u8; length];
+    with_timeout(IO_TIMEOUT, "read", stream.read_exact(&mut resp)).await?;
+
+    let result = decrypt(&mut resp);
+    debug!("Decrypted response:\n{}", result);
+    Ok(result)
+}
+
+/// Address a message to one outlet of a power strip by adding
+/// `context.child_ids` to it
+pub fn with_child_context(msg: &str, child_id: Option<&str>) -> TpResult<String> {
+    let Some(child_id) = child_id else {
+        return Ok(msg.to_string());
+    };
+
+    let mut value: serde_json::Value = serde_json::from_str(msg)?;
+    match value.as_object_mut() {
+        Some(modules) => {
+            modules.insert(
+                "context".to_string(),
+                serde_json::json!({ "child_ids": [child_id] }),
+            );
+            Ok(value.to_string())
+        }
+        None => Err(TpError::from(format!("Not a request object: {}", msg))),
+    }
+}
+
+/// Check the error code of the section at `pointer` and deserialize it
+pub(crate) fn parse_response_section<T: DeserializeOwned>(
+    value: &serde_json::Value,
+    pointer: &str,
+) -> TpResult<T> {
+    validate_response_code(value, &format!("{}/err_code", pointer))?;
+    match value.pointer(pointer) {
+        Some(section) => Ok(T::deserialize(section)?),
+        None => Err(TpError::from(format!("Invalid response format: {}", value))),
+    }
+}
+
+/// Check the error code of a standard command
+/// A "pointer" is a path to a value in the JSON object, of the form
+/// `/module/method/err_code`. A module the device doesn't have answers with
+/// an error code at `/module/err_code` instead, which is checked first.
+pub(crate) fn validate_response_code(value: &serde_json::Value, pointer: &str) -> TpResult<()> {
+    let mut path = pointer.trim_start_matches('/').split('/');
+    let module = path.next().unwrap_or_default();
+    let method = path.next().filter(|method| *method != "err_code");
+
+    check_err_code(value.get(module), module, None)?;
+    match value.pointer(pointer) {
+        Some(err_code) if err_code == 0 => Ok(()),
+        Some(_) => {
+            let section = method.and_then(|method| value.get(module)?.get(method));
+            check_err_code(section, module, method)
+        }
+        None => Err(TpError::from(format!("Invalid response format: {}", value))),
+    }
+}
+
+/// Fail with the error a section reports, if it reports one
+pub(crate) fn check_err_code(
+    section: Option<&serde_json::Value>,
+    module: &str,
+    method: Option<&str>,
+) -> TpResult<()> {
+    let Some(section) = section else {
+        return Ok(());
+    };
+
+    match section.get("err_code").and_then(serde_json::Value::as_i64) {
+        Some(err_code) if err_code != 0 => Err(TpError::from_section(
+            module,
+            method,
+            SectionError {
+                err_code: Some(err_code as i16),
+                err_msg: section
+                    .get("err_msg")
+                    .and_then(serde_json::Value::as_str)
+                    .map(str::to_string),
+            },
+        )),
+        _ => Ok(()),
+    }
+}
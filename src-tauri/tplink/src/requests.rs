@@ -0,0 +1,223 @@
+//! Requests that can be composed into a single message, so that reading
+//! several modules costs one round trip
+use super::{
+    error::{SectionError, TpError, TpResult},
+    protocol::check_err_code,
+};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Map, Value};
+use std::{fmt, time::Duration};
+
+/// The energy meter module of plugs and power strips
+pub const EMETER: &str = "emeter";
+/// The energy meter module of bulbs
+pub const COMMON_EMETER: &str = "smartlife.iot.common.emeter";
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone, PartialEq)]
+pub enum Request {
+    GetSysinfo,
+    SetAlias(String),
+    SetBrightness(u8),
+    SetRelayState(bool),
+    /// Turn the status LED off, or back on with `false`
+    SetLedOff(bool),
+    Reboot,
+    RebootWithDelay(Duration),
+    GetDimmerParameters,
+    GetDimmerDefaultBehavior,
+    GetLightState,
+    GetLightDetails,
+    GetLightDefaultBehavior,
+    /// `module` is [`EMETER`] or [`COMMON_EMETER`], depending on the device
+    GetRealtime {
+        module: String,
+    },
+    GetDailyStats {
+        module: String,
+        year: u16,
+        month: u8,
+    },
+    GetMonthlyStats {
+        module: String,
+        year: u16,
+    },
+    /// Any other module and method
+    Custom {
+        module: String,
+        method: String,
+        params: Value,
+    },
+}
+
+impl Request {
+    pub fn module(&self) -> &str {
+        match self {
+            Request::GetSysinfo
+            | Request::SetAlias(_)
+            | Request::SetRelayState(_)
+            | Request::SetLedOff(_)
+            | Request::Reboot
+            | Request::RebootWithDelay(_) => "system",
+            Request::SetBrightness(_)
+            | Request::GetDimmerParameters
+            | Request::GetDimmerDefaultBehavior => "smartlife.iot.dimmer",
+            Request::GetLightState
+            | Request::GetLightDetails
+            | Request::GetLightDefaultBehavior => "smartlife.iot.smartbulb.lightingservice",
+            Request::GetRealtime { module }
+            | Request::GetDailyStats { module, .. }
+            | Request::GetMonthlyStats { module, .. }
+            | Request::Custom { module, .. } => module,
+        }
+    }
+
+    pub fn method(&self) -> &str {
+        match self {
+            Request::GetSysinfo => "get_sysinfo",
+            Request::SetAlias(_) => "set_dev_alias",
+            Request::SetBrightness(_) => "set_brightness",
+            Request::SetRelayState(_) => "set_relay_state",
+            Request::SetLedOff(_) => "set_led_off",
+            Request::Reboot | Request::RebootWithDelay(_) => "reboot",
+            Request::GetDimmerParameters => "get_dimmer_parameters",
+            Request::GetDimmerDefaultBehavior | Request::GetLightDefaultBehavior => {
+                "get_default_behavior"
+            }
+            Request::GetLightState => "get_light_state",
+            Request::GetLightDetails => "get_light_details",
+            Request::GetRealtime { .. } => "get_realtime",
+            Request::GetDailyStats { .. } => "get_daystat",
+            Request::GetMonthlyStats { .. } => "get_monthstat",
+            Request::Custom { method, .. } => method,
+        }
+    }
+
+    pub fn params(&self) -> Value {
+        match self {
+            Request::GetSysinfo => Value::Null,
+            Request::SetAlias(alias) => json!({ "alias": alias }),
+            Request::SetBrightness(brightness) => {
+                json!({ "brightness": (*brightness).clamp(0, 100) })
+            }
+            Request::SetRelayState(state) => json!({ "state": if *state { 1 } else { 0 } }),
+            Request::SetLedOff(off) => json!({ "off": if *off { 1 } else { 0 } }),
+            Request::Reboot => json!({ "delay": 0 }),
+            Request::RebootWithDelay(delay) => json!({ "delay": delay.as_secs() }),
+            Request::GetDailyStats { year, month, .. } => json!({ "year": year, "month": month }),
+            Request::GetMonthlyStats { year, .. } => json!({ "year": year }),
+            Request::Custom { params, .. } => params.clone(),
+            _ => json!({}),
+        }
+    }
+}
+
+impl fmt::Display for Request {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = json!({ self.module(): { self.method(): self.params() } });
+
+        write!(f, "{}", value)
+    }
+}
+
+/// Several requests sent as one message. Requests for the same module share
+/// its object; a second request for the same module and method replaces the
+/// first.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Batch {
+    requests: Vec<Request>,
+}
+
+impl Batch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, request: Request) -> Self {
+        self.push(request);
+        self
+    }
+
+    pub fn push(&mut self, request: Request) {
+        self.requests
+            .retain(|r| (r.module(), r.method()) != (request.module(), request.method()));
+        self.requests.push(request);
+    }
+
+    pub fn requests(&self) -> &[Request] {
+        &self.requests
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+}
+
+impl fmt::Display for Batch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut modules = Map::new();
+        for request in &self.requests {
+            modules.entry(request.module()).or_insert_with(|| json!({}))[request.method()] =
+                request.params();
+        }
+
+        write!(f, "{}", Value::Object(modules))
+    }
+}
+
+/// The response to a [`Batch`]. Each request's section is checked on its
+/// own, so one unsupported module doesn't fail the rest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchResponse {
+    value: Value,
+}
+
+impl BatchResponse {
+    pub fn new(value: Value) -> Self {
+        Self { value }
+    }
+
+    /// The raw section answering `request`, or the error the device reported
+    /// for its module or method
+    pub fn section(&self, request: &Request) -> TpResult<&Value> {
+        let missing = || TpError::from(format!("No response to {}", request));
+
+        let module = self.value.get(request.module()).ok_or_else(missing)?;
+        check_err_code(Some(module), request.module(), None)?;
+        let section = module.get(request.method()).ok_or_else(missing)?;
+        check_err_code(Some(section), request.module(), Some(request.method()))?;
+
+        Ok(section)
+    }
+
+    /// Deserialize the section answering `request`
+    pub fn get<T: DeserializeOwned>(&self, request: &Request) -> TpResult<T> {
+        Ok(T::deserialize(self.section(request)?)?)
+    }
+
+    /// Whether the device answered for `module` at all, rather than
+    /// reporting that it doesn't have it
+    pub fn has_module(&self, module: &str) -> bool {
+        self.value
+            .get(module)
+            .is_some_and(|section| section_error(section).is_none())
+    }
+
+    /// The error code the device reported for `request`, if any
+    pub fn error(&self, request: &Request) -> Option<SectionError> {
+        let module = self.value.get(request.module())?;
+        section_error(module).or_else(|| section_error(module.get(request.method())?))
+    }
+}
+
+fn section_error(section: &Value) -> Option<SectionError> {
+    match section.get("err_code").and_then(Value::as_i64) {
+        Some(err_code) if err_code != 0 => Some(SectionError {
+            err_code: Some(err_code as i16),
+            err_msg: section
+                .get("err_msg")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+        }),
+        _ => None,
+    }
+}
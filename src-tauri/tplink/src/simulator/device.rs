@@ -0,0 +1,649 @@
+use crate::error::{TpError, TpResult};
+use serde_json::{json, Map, Value};
+
+const DIMMER: &str = "smartlife.iot.dimmer";
+const EMETER: &str = "emeter";
+const COMMON_EMETER: &str = "smartlife.iot.common.emeter";
+const LIGHTING: &str = "smartlife.iot.smartbulb.lightingservice";
+/// Motion sensor
+const PIR: &str = "smartlife.iot.PIR";
+/// Ambient light sensor
+const LAS: &str = "smartlife.iot.LAS";
+const SYSTEM: &str = "system";
+/// Modules that keep a list of rules with the same methods. Plugs and
+/// switches use `schedule`, dimmers and bulbs `smartlife.iot.common.schedule`.
+const RULE_MODULES: [&str; 4] = [
+    "schedule",
+    "smartlife.iot.common.schedule",
+    COUNTDOWN,
+    "anti_theft",
+];
+/// Holds a single timer at a time
+const COUNTDOWN: &str = "count_down";
+/// Plugs and switches use the first, dimmers and bulbs the second
+const TIMESETTINGS: [&str; 2] = ["time", "smartlife.iot.common.timesetting"];
+
+/// Dimmer setters as (method, request param, `get_dimmer_parameters` field)
+const DIMMER_PARAMETER_SETTERS: &[(&str, &str, &str)] = &[
+    ("set_fade_on_time", "fadeTime", "fadeOnTime"),
+    ("set_fade_off_time", "fadeTime", "fadeOffTime"),
+    ("set_gentle_on_time", "duration", "gentleOnTime"),
+    ("set_gentle_off_time", "duration", "gentleOffTime"),
+    ("set_button_ramp_rate", "rampRate", "rampRate"),
+    ("calibrate_brightness", "minThreshold", "minThreshold"),
+];
+
+/// The state of a single simulated device.
+///
+/// State is seeded from a fixture in the same shape as a device response
+/// (see `devices.json`): `get_*` methods answer with whatever the fixture
+/// holds for that module and method, and the setters that the crate uses
+/// update it in place so that subsequent reads reflect the change.
+///
+/// Power strips may also have a top level `children` object in the fixture,
+/// mapping each outlet's `id` to the modules it answers for itself (such as
+/// `emeter`) when a request is addressed to it with `context.child_ids`.
+#[derive(Debug, Clone)]
+pub struct SimulatedDevice {
+    state: Map<String, Value>,
+    children: Map<String, Value>,
+    handled: usize,
+}
+
+impl SimulatedDevice {
+    pub fn new(fixture: Value) -> TpResult<Self> {
+        match fixture {
+            Value::Object(mut state) if state.contains_key(SYSTEM) => {
+                let children = match state.remove("children") {
+                    Some(Value::Object(children)) => children,
+                    _ => Map::new(),
+                };
+                let device = Self {
+                    state,
+                    children,
+                    handled: 0,
+                };
+                if device.sysinfo().is_object() {
+                    Ok(device)
+                } else {
+                    Err(TpError::from("Fixture is missing system.get_sysinfo"))
+                }
+            }
+            _ => Err(TpError::from(
+                "Fixture must be an object with a system module",
+            )),
+        }
+    }
+
+    pub fn sysinfo(&self) -> &Value {
+        &self.state[SYSTEM]["get_sysinfo"]
+    }
+
+    fn sysinfo_mut(&mut self) -> &mut Value {
+        &mut self.state[SYSTEM]["get_sysinfo"]
+    }
+
+    pub fn alias(&self) -> &str {
+        self.sysinfo()["alias"].as_str().unwrap_or_default()
+    }
+
+    pub fn is_on(&self) -> bool {
+        let sysinfo = self.sysinfo();
+        sysinfo["relay_state"]
+            .as_u64()
+            .or_else(|| sysinfo["light_state"]["on_off"].as_u64())
+            .unwrap_or(0)
+            > 0
+    }
+
+    pub fn brightness(&self) -> Option<u8> {
+        let sysinfo = self.sysinfo();
+        sysinfo["brightness"]
+            .as_u64()
+            .or_else(|| self.light_preset()["brightness"].as_u64())
+            .map(|b| b as u8)
+    }
+
+    /// A bulb's current light state, or the state it will return to if off
+    pub fn light_preset(&self) -> &Value {
+        let light_state = &self.sysinfo()["light_state"];
+        match light_state.get("dft_on_state") {
+            Some(preset) => preset,
+            None => light_state,
+        }
+    }
+
+    /// Override the response to a `get_*` method
+    pub fn set_response(&mut self, module: &str, method: &str, response: Value) {
+        self.state.entry(module).or_insert_with(|| json!({}))[method] = response;
+    }
+
+    /// The stored response to a `get_*` method
+    pub fn response(&self, module: &str, method: &str) -> Option<&Value> {
+        self.state.get(module).and_then(|m| m.get(method))
+    }
+
+    /// Whether the fixture has the module and doesn't mark it unsupported
+    pub fn supports(&self, module: &str) -> bool {
+        self.state
+            .get(module)
+            .is_some_and(|m| m.get("err_code").is_none())
+    }
+
+    /// The sysinfo entry for the outlet with `id`, either its full id or its
+    /// index
+    pub fn child(&self, id: &str) -> Option<&Value> {
+        let index = self.child_index(id)?;
+        self.sysinfo()["children"].get(index)
+    }
+
+    fn child_index(&self, id: &str) -> Option<usize> {
+        let device_id = self.sysinfo()["deviceId"].as_str().unwrap_or_default();
+        self.sysinfo()["children"]
+            .as_array()?
+            .iter()
+            .position(|child| {
+                let child_id = child["id"].as_str().unwrap_or_default();
+                child_id == id || format!("{}{}", device_id, child_id) == id
+            })
+    }
+
+    /// How many requests have been answered, over TCP or UDP
+    pub fn requests_handled(&self) -> usize {
+        self.handled
+    }
+
+    /// Answer a complete request, module by module
+    pub fn handle(&mut self, request: &Value) -> Value {
+        self.handled += 1;
+        let Some(modules) = request.as_object() else {
+            return error(-3, "invalid argument");
+        };
+
+        let children = match request.pointer("/context/child_ids") {
+            None => None,
+            Some(ids) => {
+                let indices = ids.as_array().and_then(|ids| {
+                    ids.iter()
+                        .map(|id| id.as_str().and_then(|id| self.child_index(id)))
+                        .collect::<Option<Vec<_>>>()
+                });
+                match indices {
+                    Some(indices) if !indices.is_empty() => Some(indices),
+                    _ => return error(-3, "invalid argument"),
+                }
+            }
+        };
+
+        Value::Object(
+            modules
+                .iter()
+                .filter(|(module, _)| *module != "context")
+                .map(|(module, methods)| {
+                    let response = match &children {
+                        Some(children) => self.handle_child_module(children, module, methods),
+                        None => self.handle_module(module, methods),
+                    };
+                    (module.clone(), response)
+                })
+                .collect(),
+        )
+    }
+
+    /// Answer a module addressed to one or more outlets
+    fn handle_child_module(&mut self, children: &[usize], module: &str, methods: &Value) -> Value {
+        let Some(methods) = methods.as_object() else {
+            return error(-3, "invalid argument");
+        };
+
+        let mut responses = Map::new();
+        for (method, params) in methods {
+            let response = match (module, method.as_str()) {
+                (SYSTEM, "get_sysinfo") => self.sysinfo().clone(),
+                (SYSTEM, "set_relay_state") => match params["state"].as_u64() {
+                    Some(state @ 0..=1) => {
+                        for &index in children {
+                            let child = &mut self.sysinfo_mut()["children"][index];
+                            if child["state"] != json!(state) {
+                                child["state"] = json!(state);
+                                child["on_time"] = json!(0);
+                            }
+                        }
+                        ok()
+                    }
+                    _ => error(-3, "invalid argument"),
+                },
+                (SYSTEM, "set_dev_alias") => match params["alias"].as_str() {
+                    Some(alias) => {
+                        for &index in children {
+                            self.sysinfo_mut()["children"][index]["alias"] = json!(alias);
+                        }
+                        ok()
+                    }
+                    None => error(-3, "invalid argument"),
+                },
+                _ => {
+                    // Outlet specific modules answer for the first outlet only
+                    let id = self.sysinfo()["children"][children[0]]["id"].clone();
+                    match id.as_str().and_then(|id| self.children.get(id)) {
+                        Some(modules) if modules.get(module).is_some() => modules[module]
+                            .get(method)
+                            .cloned()
+                            .unwrap_or_else(|| error(-2, "member not support")),
+                        _ => error(-1, "module not support"),
+                    }
+                }
+            };
+            responses.insert(method.clone(), response);
+        }
+
+        Value::Object(responses)
+    }
+
+    fn handle_module(&mut self, module: &str, methods: &Value) -> Value {
+        if !self.supports(module) {
+            return error(-1, "module not support");
+        }
+
+        let Some(methods) = methods.as_object() else {
+            return error(-3, "invalid argument");
+        };
+
+        Value::Object(
+            methods
+                .iter()
+                .map(|(method, params)| {
+                    (method.clone(), self.handle_method(module, method, params))
+                })
+                .collect(),
+        )
+    }
+
+    fn handle_method(&mut self, module: &str, method: &str, params: &Value) -> Value {
+        match (module, method) {
+            (SYSTEM, "get_sysinfo") => self.sysinfo().clone(),
+            (SYSTEM, "set_relay_state") if self.sysinfo().get("light_state").is_some() => {
+                error(-2, "member not support")
+            }
+            (SYSTEM, "set_relay_state") | (DIMMER, "set_switch_state") => {
+                match params["state"].as_u64() {
+                    Some(state @ 0..=1) => {
+                        self.set_relay_state(state);
+                        ok()
+                    }
+                    _ => error(-3, "invalid argument"),
+                }
+            }
+            (SYSTEM, "set_dev_alias") => match params["alias"].as_str() {
+                Some(alias) => {
+                    self.sysinfo_mut()["alias"] = json!(alias);
+                    ok()
+                }
+                None => error(-3, "invalid argument"),
+            },
+            (SYSTEM, "reboot") => ok(),
+            (SYSTEM, "set_led_off") if self.sysinfo().get("led_off").is_some() => {
+                match params["off"].as_u64() {
+                    Some(off @ 0..=1) => {
+                        self.sysinfo_mut()["led_off"] = json!(off);
+                        ok()
+                    }
+                    _ => error(-3, "invalid argument"),
+                }
+            }
+            (DIMMER, "set_brightness") => match params["brightness"].as_u64() {
+                Some(brightness @ 0..=100) => {
+                    self.sysinfo_mut()["brightness"] = json!(brightness);
+                    ok()
+                }
+                _ => error(-3, "invalid argument"),
+            },
+            (DIMMER, "set_dimmer_transition") => match params["brightness"].as_u64() {
+                Some(0) => {
+                    self.set_relay_state(0);
+                    ok()
+                }
+                Some(brightness @ 1..=100) => {
+                    self.sysinfo_mut()["brightness"] = json!(brightness);
+                    self.set_relay_state(1);
+                    ok()
+                }
+                _ => error(-3, "invalid argument"),
+            },
+            (LIGHTING, "get_light_state") => {
+                let mut light_state = self.sysinfo()["light_state"].clone();
+                light_state["err_code"] = json!(0);
+                light_state
+            }
+            (LIGHTING, "transition_light_state") => self.transition_light_state(params),
+            (EMETER | COMMON_EMETER, "get_daystat") => {
+                self.emeter_stats(module, params, &["year", "month"], "day")
+            }
+            (EMETER | COMMON_EMETER, "get_monthstat") => {
+                self.emeter_stats(module, params, &["year"], "month")
+            }
+            (EMETER | COMMON_EMETER, "erase_emeter_stat") => {
+                let emeter = &mut self.state[module];
+                if let Some(days) = emeter["get_daystat"]["day_list"].as_array_mut() {
+                    days.clear();
+                }
+                for total in ["total", "total_wh"] {
+                    if let Some(value) = emeter["get_realtime"].get_mut(total) {
+                        *value = json!(0);
+                    }
+                }
+                ok()
+            }
+            (module, "add_rule" | "edit_rule" | "delete_rule" | "delete_all_rules")
+            | (module, "set_overall_enable")
+                if RULE_MODULES.contains(&module) =>
+            {
+                self.edit_rules(module, method, params)
+            }
+            (module, "set_timezone") if TIMESETTINGS.contains(&module) => {
+                self.set_timezone(module, params)
+            }
+            (DIMMER | LIGHTING, "set_preferred_state") => self.set_preferred_state(params),
+            (PIR, "set_enable") => self.set_motion_config("enable", params["enable"].as_u64()),
+            (PIR, "set_cold_time") => {
+                self.set_motion_config("cold_time", params["cold_time"].as_u64())
+            }
+            (PIR, "set_trigger_sens") => self.set_trigger_sens(params),
+            (LAS, "set_enable" | "set_brt_level") => self.set_ambient_light(method, params),
+            (DIMMER, "set_double_click_action") => self.set_behavior("double_click", params),
+            (DIMMER, "set_long_press_action") => self.set_behavior("long_press", params),
+            _ => match DIMMER_PARAMETER_SETTERS
+                .iter()
+                .find(|(m, ..)| module == DIMMER && *m == method)
+            {
+                Some(&(_, param, field)) => self.set_dimmer_parameter(param, field, params),
+                None => self
+                    .response(module, method)
+                    .cloned()
+                    .unwrap_or_else(|| error(-2, "member not support")),
+            },
+        }
+    }
+
+    fn set_dimmer_parameter(&mut self, param: &str, field: &str, params: &Value) -> Value {
+        let Some(value) = params[param].as_u64() else {
+            return error(-3, "invalid argument");
+        };
+
+        match self.state[DIMMER].get_mut("get_dimmer_parameters") {
+            Some(parameters) => {
+                parameters[field] = json!(value);
+                ok()
+            }
+            None => error(-2, "member not support"),
+        }
+    }
+
+    /// Overwrite one of the presets in sysinfo's `preferred_state`
+    fn set_preferred_state(&mut self, params: &Value) -> Value {
+        if params["brightness"]
+            .as_u64()
+            .is_none_or(|brightness| brightness > 100)
+        {
+            return error(-3, "invalid argument");
+        }
+        let slot = self.sysinfo_mut()["preferred_state"]
+            .as_array_mut()
+            .and_then(|presets| {
+                presets
+                    .iter_mut()
+                    .find(|preset| preset["index"] == params["index"])
+            });
+
+        match slot {
+            Some(slot) => {
+                *slot = params.clone();
+                ok()
+            }
+            None => error(-3, "invalid argument"),
+        }
+    }
+
+    fn set_motion_config(&mut self, field: &str, value: Option<u64>) -> Value {
+        let Some(value) = value else {
+            return error(-3, "invalid argument");
+        };
+
+        match self.state[PIR].get_mut("get_config") {
+            Some(config) => {
+                config[field] = json!(value);
+                ok()
+            }
+            None => error(-2, "member not support"),
+        }
+    }
+
+    /// Pick one of the ranges, or set the custom threshold with `value`
+    fn set_trigger_sens(&mut self, params: &Value) -> Value {
+        let Some(config) = self.state[PIR].get_mut("get_config") else {
+            return error(-2, "member not support");
+        };
+
+        match (params["index"].as_u64(), params["value"].as_u64()) {
+            (Some(index @ 0..=2), None) => config["trigger_index"] = json!(index),
+            (Some(3), value) => {
+                if let (Some(value), Some(custom)) = (value, config["array"].get_mut(3)) {
+                    *custom = json!(value);
+                }
+                config["trigger_index"] = json!(3);
+            }
+            _ => return error(-3, "invalid argument"),
+        }
+        ok()
+    }
+
+    /// `set_enable` and `set_brt_level`, the latter changing the level that
+    /// is the current threshold
+    fn set_ambient_light(&mut self, method: &str, params: &Value) -> Value {
+        let sensor = self.state[LAS]
+            .get_mut("get_config")
+            .and_then(|config| config["devs"].get_mut(0));
+        let Some(sensor) = sensor else {
+            return error(-2, "member not support");
+        };
+
+        match (method, params["enable"].as_u64(), params["value"].as_u64()) {
+            ("set_enable", Some(enable @ 0..=1), _) => sensor["enable"] = json!(enable),
+            ("set_brt_level", _, Some(value @ 0..=100)) => {
+                let index = sensor["dark_index"].as_u64().unwrap_or_default() as usize;
+                match sensor["level_array"].get_mut(index) {
+                    Some(level) => level["value"] = json!(value),
+                    None => return error(-3, "invalid argument"),
+                }
+            }
+            _ => return error(-3, "invalid argument"),
+        }
+        ok()
+    }
+
+    fn set_behavior(&mut self, input: &str, params: &Value) -> Value {
+        if params["mode"].as_str().is_none() {
+            return error(-3, "invalid argument");
+        }
+
+        match self.state[DIMMER].get_mut("get_default_behavior") {
+            Some(behavior) => {
+                behavior[input] = params.clone();
+                ok()
+            }
+            None => error(-2, "member not support"),
+        }
+    }
+
+    /// Change the fixture's `get_rules`, creating it if the fixture only
+    /// has the module
+    fn edit_rules(&mut self, module: &str, method: &str, params: &Value) -> Value {
+        // Unique for as long as the simulator runs
+        let new_id = format!("{:032X}", self.handled);
+        let Some(methods) = self.state.get_mut(module).and_then(Value::as_object_mut) else {
+            return error(-2, "member not support");
+        };
+        let table = methods
+            .entry("get_rules")
+            .or_insert_with(|| json!({ "rule_list": [], "enable": 1, "err_code": 0 }));
+        let Some(rules) = table["rule_list"].as_array_mut() else {
+            return error(-2, "member not support");
+        };
+        let position = |rules: &[Value]| rules.iter().position(|r| r["id"] == params["id"]);
+
+        match method {
+            "add_rule" if module == COUNTDOWN && !rules.is_empty() => error(-10, "table is full"),
+            "add_rule" => {
+                let mut rule = params.clone();
+                rule["id"] = json!(new_id);
+                if module == COUNTDOWN {
+                    // Timers start as soon as they are added
+                    rule["remain"] = rule["delay"].clone();
+                }
+                rules.push(rule);
+                json!({ "id": new_id, "err_code": 0 })
+            }
+            "edit_rule" => match position(rules) {
+                Some(index) => {
+                    rules[index] = params.clone();
+                    ok()
+                }
+                None => error(-14, "entry not exist"),
+            },
+            "delete_rule" => match position(rules) {
+                Some(index) => {
+                    rules.remove(index);
+                    ok()
+                }
+                None => error(-14, "entry not exist"),
+            },
+            "delete_all_rules" => {
+                rules.clear();
+                ok()
+            }
+            _ => match params["enable"].as_u64() {
+                Some(enable @ 0..=1) => {
+                    table["enable"] = json!(enable);
+                    ok()
+                }
+                _ => error(-3, "invalid argument"),
+            },
+        }
+    }
+
+    /// Set the clock and timezone index. The clock doesn't run, so
+    /// `get_time` answers with exactly the time that was set.
+    fn set_timezone(&mut self, module: &str, params: &Value) -> Value {
+        let fields = ["year", "month", "mday", "hour", "min", "sec"];
+        if params["index"].as_u64().is_none_or(|index| index > 109)
+            || fields.iter().any(|field| params[field].as_u64().is_none())
+        {
+            return error(-3, "invalid argument");
+        }
+
+        let mut time = json!({ "err_code": 0 });
+        for field in fields {
+            time[field] = params[field].clone();
+        }
+        self.set_response(module, "get_time", time);
+        let Some(methods) = self.state.get_mut(module).and_then(Value::as_object_mut) else {
+            return error(-2, "member not support");
+        };
+        let timezone = methods
+            .entry("get_timezone")
+            .or_insert_with(|| json!({ "err_code": 0 }));
+        timezone["index"] = params["index"].clone();
+        ok()
+    }
+
+    /// Answer `get_daystat` or `get_monthstat` from the fixture's
+    /// `get_daystat.day_list`, which holds every day the device knows about.
+    /// Months are totalled from their days.
+    fn emeter_stats(&self, module: &str, params: &Value, filter: &[&str], period: &str) -> Value {
+        if filter.iter().any(|field| params[field].as_u64().is_none()) {
+            return error(-3, "invalid argument");
+        }
+        let Some(days) = self.state[module]["get_daystat"]["day_list"].as_array() else {
+            return error(-2, "member not support");
+        };
+
+        let mut list = Vec::<Value>::new();
+        for day in days
+            .iter()
+            .filter(|day| filter.iter().all(|field| day[field] == params[field]))
+        {
+            if period == "day" {
+                list.push(day.clone());
+                continue;
+            }
+
+            let month = match list.iter_mut().find(|m| m["month"] == day["month"]) {
+                Some(month) => month,
+                None => {
+                    list.push(json!({ "year": day["year"], "month": day["month"] }));
+                    list.last_mut().unwrap()
+                }
+            };
+            for energy in ["energy", "energy_wh"] {
+                if let Some(value) = day[energy].as_f64() {
+                    month[energy] = json!(month[energy].as_f64().unwrap_or(0.0) + value);
+                }
+            }
+        }
+
+        json!({ format!("{}_list", period): list, "err_code": 0 })
+    }
+
+    fn transition_light_state(&mut self, params: &Value) -> Value {
+        let limits = [
+            ("hue", 360),
+            ("saturation", 100),
+            ("color_temp", 9000),
+            ("brightness", 100),
+        ];
+        let Some(on_off @ 0..=1) = params["on_off"].as_u64() else {
+            return error(-3, "invalid argument");
+        };
+
+        let mut preset = self.light_preset().clone();
+        if let Some(preset) = preset.as_object_mut() {
+            preset.remove("on_off");
+        }
+        for (field, max) in limits {
+            match params.get(field).map(Value::as_u64) {
+                Some(Some(value)) if value <= max => preset[field] = json!(value),
+                Some(_) => return error(-3, "invalid argument"),
+                None => {}
+            }
+        }
+
+        let light_state = if on_off == 1 {
+            let mut light_state = preset;
+            light_state["on_off"] = json!(1);
+            light_state
+        } else {
+            json!({ "on_off": 0, "dft_on_state": preset })
+        };
+        self.sysinfo_mut()["light_state"] = light_state.clone();
+
+        let mut response = light_state;
+        response["err_code"] = json!(0);
+        response
+    }
+
+    fn set_relay_state(&mut self, state: u64) {
+        let sysinfo = self.sysinfo_mut();
+        if sysinfo["relay_state"] != json!(state) {
+            sysinfo["relay_state"] = json!(state);
+            sysinfo["on_time"] = json!(0);
+        }
+    }
+}
+
+fn ok() -> Value {
+    json!({ "err_code": 0 })
+}
+
+fn error(err_code: i16, err_msg: &str) -> Value {
+    json!({ "err_code": err_code, "err_msg": err_msg })
+}
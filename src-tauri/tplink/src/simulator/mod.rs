@@ -0,0 +1,178 @@
+//! A stand-in for real TP-Link hardware.
+//!
+//! Each simulated device answers the XOR "autokey" protocol over TCP and UDP
+//! discovery on the same port, just like a real plug, so the rest of the crate
+//! can be exercised on loopback.
+use super::{
+    error::TpResult,
+    protocol::{decrypt, encrypt},
+};
+use byteorder::{BigEndian, ByteOrder};
+use log::*;
+use serde_json::Value;
+use std::{
+    io,
+    net::{IpAddr, SocketAddr},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream, UdpSocket},
+    task::JoinHandle,
+};
+
+mod device;
+pub use device::SimulatedDevice;
+
+/// Requests larger than this are assumed to be garbage rather than JSON
+const MAX_REQUEST_LEN: usize = 64 * 1024;
+
+/// Read a fixture file containing either a single device response or an
+/// array of them, like `devices.json`
+pub fn load_fixtures(path: impl AsRef<Path>) -> TpResult<Vec<SimulatedDevice>> {
+    let fixtures = match serde_json::from_str(&std::fs::read_to_string(path)?)? {
+        Value::Array(fixtures) => fixtures,
+        fixture => vec![fixture],
+    };
+
+    fixtures.into_iter().map(SimulatedDevice::new).collect()
+}
+
+/// A simulated device that is listening for requests. The device stops
+/// listening when this is dropped.
+pub struct Simulator {
+    /// The address the device answers on, for both TCP and UDP
+    pub addr: SocketAddr,
+    device: Arc<Mutex<SimulatedDevice>>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl Simulator {
+    /// Start serving `device` on `ip`. If `port` is 0 an ephemeral port is
+    /// chosen, and UDP discovery listens on the same port number as TCP.
+    pub async fn spawn(device: SimulatedDevice, ip: IpAddr, port: u16) -> io::Result<Self> {
+        let (listener, socket) = bind(ip, port).await?;
+        let addr = listener.local_addr()?;
+        let device = Arc::new(Mutex::new(device));
+
+        let tasks = vec![
+            tokio::spawn(serve_tcp(listener, device.clone())),
+            tokio::spawn(serve_udp(socket, device.clone())),
+        ];
+
+        Ok(Self {
+            addr,
+            device,
+            tasks,
+        })
+    }
+
+    /// A snapshot of the device's current state
+    pub fn device(&self) -> SimulatedDevice {
+        self.device.lock().unwrap().clone()
+    }
+
+    /// Mutate the device's state directly, as if someone pressed its button
+    pub fn update<T>(&self, f: impl FnOnce(&mut SimulatedDevice) -> T) -> T {
+        f(&mut self.device.lock().unwrap())
+    }
+}
+
+impl Drop for Simulator {
+    fn drop(&mut self) {
+        self.tasks.iter().for_each(JoinHandle::abort);
+    }
+}
+
+/// Bind TCP and UDP sockets that share a port number
+async fn bind(ip: IpAddr, port: u16) -> io::Result<(TcpListener, UdpSocket)> {
+    const ATTEMPTS: usize = 16;
+
+    for _ in 0..ATTEMPTS {
+        let listener = TcpListener::bind(SocketAddr::new(ip, port)).await?;
+        match UdpSocket::bind(listener.local_addr()?).await {
+            Ok(socket) => return Ok((listener, socket)),
+            // Somebody else has the UDP side of this ephemeral port, try another
+            Err(err) if port == 0 && err.kind() == io::ErrorKind::AddrInUse => continue,
+            Err(err) => return Err(err),
+        }
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::AddrInUse,
+        "could not find a port free for both TCP and UDP",
+    ))
+}
+
+fn respond(device: &Mutex<SimulatedDevice>, cipher: &mut [u8]) -> TpResult<Vec<u8>> {
+    let request = serde_json::from_str::<Value>(&decrypt(cipher))?;
+    debug!("Simulator received {}", request);
+    let response = device.lock().unwrap().handle(&request);
+    encrypt(&response.to_string())
+}
+
+async fn serve_tcp(listener: TcpListener, device: Arc<Mutex<SimulatedDevice>>) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, peer)) => {
+                debug!("Simulator accepted connection from {}", peer);
+                tokio::spawn(serve_connection(stream, device.clone()));
+            }
+            Err(err) => error!("Simulator accept error {:?}", err),
+        }
+    }
+}
+
+async fn serve_connection(mut stream: TcpStream, device: Arc<Mutex<SimulatedDevice>>) {
+    let mut header = [0_u8; 4];
+
+    // Keep answering until the client hangs up
+    while stream.read_exact(&mut header).await.is_ok() {
+        let length = BigEndian::read_u32(&header) as usize;
+        if length > MAX_REQUEST_LEN {
+            error!("Simulator dropping request of {} bytes", length);
+            break;
+        }
+        let mut cipher = vec![0_u8; length];
+        if stream.read_exact(&mut cipher).await.is_err() {
+            break;
+        }
+
+        match respond(&device, &mut cipher) {
+            Ok(payload) => {
+                if stream.write_all(&payload).await.is_err() {
+                    break;
+                }
+            }
+            Err(err) => {
+                error!("Simulator could not answer request {:?}", err);
+                break;
+            }
+        }
+    }
+}
+
+async fn serve_udp(socket: UdpSocket, device: Arc<Mutex<SimulatedDevice>>) {
+    let mut buf = [0_u8; 4096];
+
+    loop {
+        let (size, peer) = match socket.recv_from(&mut buf).await {
+            Ok(received) => received,
+            Err(err) => {
+                error!("Simulator recv_from error {:?}", err);
+                continue;
+            }
+        };
+
+        // Discovery packets are sent without the length header
+        match respond(&device, &mut buf[0..size]) {
+            Ok(payload) => {
+                if let Err(err) = socket.send_to(&payload[4..], peer).await {
+                    error!("Simulator send_to error {:?}", err);
+                }
+            }
+            Err(err) => error!("Simulator could not answer discovery {:?}", err),
+        }
+    }
+}
@@ -0,0 +1,60 @@
+//! Sunrise and sunset, which devices use for `sunrise` and `sunset` rules and
+//! the app uses for its own automations.
+//!
+//! This is the sunrise equation with the usual corrections for refraction
+//! and the size of the sun's disc, which is accurate to a minute or two away
+//! from the poles.
+use chrono::{DateTime, NaiveDate, Utc};
+
+/// The Julian date of 2000-01-01 12:00 UTC
+const J2000: f64 = 2_451_545.0;
+/// The Julian date of the Unix epoch
+const UNIX_EPOCH: f64 = 2_440_587.5;
+/// The sun's altitude at sunrise and sunset, in degrees
+const HORIZON: f64 = -0.833;
+/// The tilt of the earth's axis, in degrees
+const OBLIQUITY: f64 = 23.4397;
+
+/// When the sun rises and sets on one day
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SunTimes {
+    pub sunrise: DateTime<Utc>,
+    pub sunset: DateTime<Utc>,
+}
+
+/// Sunrise and sunset around solar noon on `date` at a location in degrees,
+/// north and east positive. `None` during polar day or night.
+pub fn sun_times(date: NaiveDate, latitude: f64, longitude: f64) -> Option<SunTimes> {
+    let epoch = NaiveDate::from_ymd_opt(2000, 1, 1)?;
+    let days = (date - epoch).num_days() as f64;
+
+    // Mean solar noon, then the sun's position along its orbit
+    let noon = days - longitude / 360.0;
+    let anomaly = (357.5291 + 0.985_600_28 * noon)
+        .rem_euclid(360.0)
+        .to_radians();
+    let center =
+        1.9148 * anomaly.sin() + 0.02 * (2.0 * anomaly).sin() + 0.0003 * (3.0 * anomaly).sin();
+    let ecliptic = (anomaly.to_degrees() + center + 180.0 + 102.9372)
+        .rem_euclid(360.0)
+        .to_radians();
+    let transit = J2000 + noon + 0.0053 * anomaly.sin() - 0.0069 * (2.0 * ecliptic).sin();
+
+    let declination = (ecliptic.sin() * OBLIQUITY.to_radians().sin()).asin();
+    let latitude = latitude.to_radians();
+    let hour_angle = (HORIZON.to_radians().sin() - latitude.sin() * declination.sin())
+        / (latitude.cos() * declination.cos());
+    if !(-1.0..=1.0).contains(&hour_angle) {
+        return None;
+    }
+    let half_day = hour_angle.acos().to_degrees() / 360.0;
+
+    Some(SunTimes {
+        sunrise: from_julian(transit - half_day)?,
+        sunset: from_julian(transit + half_day)?,
+    })
+}
+
+fn from_julian(date: f64) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(((date - UNIX_EPOCH) * 86_400.0).round() as i64, 0)
+}
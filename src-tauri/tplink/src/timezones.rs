@@ -0,0 +1,161 @@
+//! TP-Link devices identify their timezone by an index into a fixed table
+//! rather than by name. This maps between those indices and IANA zones.
+use super::models::DeviceTime;
+use chrono::{DateTime, Datelike, NaiveDate, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
+
+/// The zone each index stands for, as shown in the Kasa app
+const ZONES: [&str; 110] = [
+    "Etc/GMT+12",
+    "Pacific/Samoa",
+    "US/Hawaii",
+    "US/Alaska",
+    "Mexico/BajaNorte",
+    "Etc/GMT+8",
+    "PST8PDT",
+    "US/Arizona",
+    "America/Mazatlan",
+    "MST",
+    "MST7MDT",
+    "Mexico/General",
+    "Etc/GMT+6",
+    "CST6CDT",
+    "America/Monterrey",
+    "Canada/Saskatchewan",
+    "America/Bogota",
+    "Etc/GMT+5",
+    "EST5EDT",
+    "America/Indiana/Indianapolis",
+    "America/Caracas",
+    "America/Asuncion",
+    "Etc/GMT+4",
+    "Canada/Atlantic",
+    "America/Cuiaba",
+    "Brazil/West",
+    "America/Santiago",
+    "Canada/Newfoundland",
+    "America/Sao_Paulo",
+    "America/Argentina/Buenos_Aires",
+    "America/Cayenne",
+    "America/Miquelon",
+    "America/Montevideo",
+    "Chile/Continental",
+    "Etc/GMT+2",
+    "Atlantic/Azores",
+    "Atlantic/Cape_Verde",
+    "Africa/Casablanca",
+    "UCT",
+    "GB",
+    "Africa/Monrovia",
+    "Europe/Amsterdam",
+    "Europe/Belgrade",
+    "Europe/Brussels",
+    "Europe/Sarajevo",
+    "Africa/Lagos",
+    "Africa/Windhoek",
+    "Asia/Amman",
+    "Europe/Athens",
+    "Asia/Beirut",
+    "Africa/Cairo",
+    "Asia/Damascus",
+    "EET",
+    "Africa/Harare",
+    "Europe/Helsinki",
+    "Asia/Istanbul",
+    "Asia/Jerusalem",
+    "Europe/Kaliningrad",
+    "Africa/Tripoli",
+    "Asia/Baghdad",
+    "Asia/Kuwait",
+    "Europe/Minsk",
+    "Europe/Moscow",
+    "Africa/Nairobi",
+    "Asia/Tehran",
+    "Asia/Muscat",
+    "Asia/Baku",
+    "Europe/Samara",
+    "Indian/Mauritius",
+    "Asia/Tbilisi",
+    "Asia/Yerevan",
+    "Asia/Kabul",
+    "Asia/Ashgabat",
+    "Asia/Yekaterinburg",
+    "Asia/Karachi",
+    "Asia/Kolkata",
+    "Asia/Colombo",
+    "Asia/Kathmandu",
+    "Asia/Almaty",
+    "Asia/Dhaka",
+    "Asia/Novosibirsk",
+    "Asia/Rangoon",
+    "Asia/Bangkok",
+    "Asia/Krasnoyarsk",
+    "Asia/Chongqing",
+    "Asia/Irkutsk",
+    "Asia/Singapore",
+    "Australia/Perth",
+    "Asia/Taipei",
+    "Asia/Ulaanbaatar",
+    "Asia/Tokyo",
+    "Asia/Seoul",
+    "Asia/Yakutsk",
+    "Australia/Adelaide",
+    "Australia/Darwin",
+    "Australia/Brisbane",
+    "Australia/Canberra",
+    "Pacific/Guam",
+    "Australia/Hobart",
+    "Antarctica/DumontDUrville",
+    "Asia/Magadan",
+    "Asia/Srednekolymsk",
+    "Etc/GMT-11",
+    "Asia/Anadyr",
+    "Pacific/Auckland",
+    "Etc/GMT-12",
+    "Pacific/Fiji",
+    "Etc/GMT-13",
+    "Asia/Kamchatka",
+    "Etc/GMT-14",
+];
+
+/// The IANA zone a device timezone index stands for
+pub fn zone_of(index: u8) -> Option<Tz> {
+    ZONES.get(index as usize)?.parse().ok()
+}
+
+/// The device timezone index for an IANA zone. Zones that aren't in the
+/// table get the first index that keeps the same time all year round.
+pub fn index_of(zone: &str) -> Option<u8> {
+    let tz: Tz = zone.parse().ok()?;
+    if let Some(index) = ZONES.iter().position(|z| *z == tz.name()) {
+        return Some(index as u8);
+    }
+
+    let offsets = |tz: Tz| {
+        let year = Utc::now().year();
+        [1, 7].map(|month| {
+            let noon = NaiveDate::from_ymd_opt(year, month, 15)
+                .and_then(|date| date.and_hms_opt(12, 0, 0))
+                .unwrap_or_default();
+            tz.offset_from_utc_datetime(&noon).fix()
+        })
+    };
+    let wanted = offsets(tz);
+    (0..ZONES.len() as u8).find(|&index| zone_of(index).is_some_and(|z| offsets(z) == wanted))
+}
+
+/// The device's wall clock at `now` in the zone with `index`
+pub fn device_time_at(index: u8, now: DateTime<Utc>) -> Option<DeviceTime> {
+    Some(DeviceTime::from(
+        &now.with_timezone(&zone_of(index)?).naive_local(),
+    ))
+}
+
+/// How far ahead of `now` a device's clock is, in seconds, given the zone
+/// it thinks it is in. Negative when it is behind.
+pub fn clock_drift(time: &DeviceTime, index: u8, now: DateTime<Utc>) -> Option<i64> {
+    let local = zone_of(index)?
+        .from_local_datetime(&time.to_naive()?)
+        .earliest()?;
+    Some((local.with_timezone(&Utc) - now).num_seconds())
+}
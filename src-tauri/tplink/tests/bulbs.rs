@@ -0,0 +1,93 @@
+#![cfg(feature = "tokio")]
+
+mod common;
+
+use overhead_tplink::{devices::Device, models::LightTransition, prelude::*};
+
+#[tokio::test]
+async fn reads_light_state_from_sysinfo() {
+    let sim = common::spawn_model("KL130").await;
+    let mut device = Device::try_new(sim.addr, "KL130(US)").unwrap();
+
+    let sysinfo = device.get_sysinfo().await.unwrap();
+    assert!(!sysinfo.is_on());
+    assert_eq!(sysinfo.brightness(), 45);
+
+    let light_state = device
+        .try_into_smart_bulb()
+        .unwrap()
+        .get_light_state()
+        .await
+        .unwrap();
+    assert_eq!(Some(light_state), sysinfo.light_state);
+}
+
+#[tokio::test]
+async fn transitions_color_and_brightness() {
+    let sim = common::spawn_model("KL130").await;
+    let mut device = Device::try_new(sim.addr, "KL130(US)").unwrap();
+    let bulb = device.try_into_smart_bulb().unwrap();
+
+    let light_state = bulb
+        .transition_light_state(&LightTransition {
+            hue: Some(120),
+            saturation: Some(100),
+            color_temp: Some(0),
+            brightness: Some(70),
+            transition_period: Some(500),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert!(light_state.is_on());
+    assert_eq!(light_state.hue, Some(120));
+    assert_eq!(light_state.saturation, Some(100));
+    assert_eq!(light_state.brightness(), Some(70));
+    assert_eq!(sim.device().brightness(), Some(70));
+}
+
+#[tokio::test]
+async fn switching_off_keeps_the_light_preset() {
+    let sim = common::spawn_model("KL110").await;
+    let mut device = Device::try_new(sim.addr, "KL110(US)").unwrap();
+
+    assert!(!device.toggle().await.unwrap());
+    assert!(!sim.device().is_on());
+
+    let light_state = device
+        .try_into_smart_bulb()
+        .unwrap()
+        .get_light_state()
+        .await
+        .unwrap();
+    assert_eq!(light_state.brightness(), Some(80));
+    assert_eq!(
+        light_state
+            .dft_on_state
+            .and_then(|preset| preset.color_temp),
+        Some(2700)
+    );
+
+    assert!(device.toggle().await.unwrap());
+    assert!(sim.device().is_on());
+}
+
+#[tokio::test]
+async fn reads_light_details_and_default_behavior() {
+    let sim = common::spawn_model("KL110").await;
+    let mut device = Device::try_new(sim.addr, "KL110(US)").unwrap();
+    let bulb = device.try_into_smart_bulb().unwrap();
+
+    assert_eq!(bulb.get_light_details().await.unwrap().max_lumens, 800);
+    assert!(bulb.get_default_behavior().await.unwrap().hard_on.is_some());
+}
+
+#[tokio::test]
+async fn bulbs_are_not_dimmers() {
+    let sim = common::spawn_model("KL110").await;
+    let mut device = Device::try_new(sim.addr, "KL110(US)").unwrap();
+
+    assert!(device.try_into_dimmable().is_err());
+    assert!(device.try_into_smart_bulb().is_ok());
+}
@@ -0,0 +1,156 @@
+#![cfg(feature = "tokio")]
+
+mod common;
+
+use overhead_tplink::{
+    devices::Device,
+    models::{BehaviorAction, BehaviorMode, DimmerButton, TransitionMode},
+    prelude::*,
+};
+use serde_json::json;
+use std::time::Duration;
+
+#[tokio::test]
+async fn toggles_relay_state() {
+    let sim = common::spawn_model("KP405").await;
+    let mut device = Device::try_new(sim.addr, "KP405(US)").unwrap();
+
+    assert!(!device.get_is_on().await.unwrap());
+    assert!(device.toggle().await.unwrap());
+    assert!(sim.device().is_on());
+    assert!(!device.toggle().await.unwrap());
+    assert!(!sim.device().is_on());
+}
+
+#[tokio::test]
+async fn sees_changes_made_on_the_device() {
+    let sim = common::spawn_model("HS220").await;
+    let mut device = Device::try_new(sim.addr, "HS220(US)").unwrap();
+
+    sim.update(|d| d.handle(&serde_json::json!({"system": {"set_relay_state": {"state": 1}}})));
+    assert!(device.get_is_on().await.unwrap());
+}
+
+#[tokio::test]
+async fn sets_alias() {
+    let sim = common::spawn_model("ES20M").await;
+    let mut device = Device::try_new(sim.addr, "ES20M(US)").unwrap();
+
+    device.set_alias("Hallway").await.unwrap();
+    assert_eq!(device.get_alias().await.unwrap(), "Hallway");
+    assert_eq!(sim.device().alias(), "Hallway");
+}
+
+#[tokio::test]
+async fn sets_brightness() {
+    let sim = common::spawn_model("HS220").await;
+    let mut device = Device::try_new(sim.addr, "HS220(US)").unwrap();
+
+    device
+        .try_into_dimmable()
+        .unwrap()
+        .set_brightness(40)
+        .await
+        .unwrap();
+    assert_eq!(sim.device().brightness(), Some(40));
+    assert_eq!(device.get_sysinfo().await.unwrap().brightness, Some(40));
+}
+
+#[tokio::test]
+async fn tunes_dimmer_parameters() {
+    let sim = common::spawn_model("HS220").await;
+    let mut device = Device::try_new(sim.addr, "HS220(US)").unwrap();
+    let dimmer = device.try_into_dimmable().unwrap();
+
+    let before = dimmer.get_dimmer_parameters().await.unwrap();
+    dimmer.set_fade_on_time(1500).await.unwrap();
+    dimmer.set_gentle_off_time(2000).await.unwrap();
+    dimmer.set_ramp_rate(10).await.unwrap();
+    dimmer.calibrate_min_threshold(15).await.unwrap();
+
+    let after = dimmer.get_dimmer_parameters().await.unwrap();
+    assert_eq!(after.fade_on_time, 1500);
+    assert_eq!(after.fade_off_time, before.fade_off_time);
+    assert_eq!(after.gentle_off_time, 2000);
+    assert_eq!(after.ramp_rate, 10);
+    assert_eq!(after.min_threshold, 15);
+}
+
+#[tokio::test]
+async fn reassigns_button_actions() {
+    let sim = common::spawn_model("HS220").await;
+    sim.update(|d| {
+        d.set_response(
+            "smartlife.iot.dimmer",
+            "get_default_behavior",
+            json!({
+                "err_code": 0,
+                "hard_on": {"mode": "last_status"},
+                "soft_on": {"mode": "last_status"},
+                "long_press": {"mode": "instant_on_off"},
+                "double_click": {"mode": "gentle_on_off"},
+            }),
+        )
+    });
+    let mut device = Device::try_new(sim.addr, "HS220(US)").unwrap();
+    let dimmer = device.try_into_dimmable().unwrap();
+
+    let preset = BehaviorAction {
+        mode: BehaviorMode::CustomizePreset,
+        index: Some(2),
+    };
+    dimmer
+        .set_button_action(DimmerButton::DoubleClick, preset.clone())
+        .await
+        .unwrap();
+
+    let behavior = dimmer.get_default_behavior().await.unwrap();
+    assert_eq!(behavior.double_click, Some(preset));
+    assert_eq!(
+        behavior.long_press.map(|action| action.mode),
+        Some(BehaviorMode::InstantOnOff)
+    );
+}
+
+#[tokio::test]
+async fn transitions_brightness() {
+    let sim = common::spawn_model("HS220").await;
+    let mut device = Device::try_new(sim.addr, "HS220(US)").unwrap();
+    let mut dimmer = device.try_into_dimmable().unwrap();
+
+    dimmer
+        .set_dimmer_transition(60, TransitionMode::GentleOnOff, Duration::from_secs(1))
+        .await
+        .unwrap();
+    assert!(sim.device().is_on());
+    assert_eq!(sim.device().brightness(), Some(60));
+
+    dimmer
+        .set_dimmer_transition(0, TransitionMode::InstantOnOff, Duration::ZERO)
+        .await
+        .unwrap();
+    assert!(!sim.device().is_on());
+}
+
+#[tokio::test]
+async fn reports_unsupported_methods() {
+    let sim = common::spawn_model("ES20M").await;
+    let mut device = Device::try_new(sim.addr, "ES20M(US)").unwrap();
+    let dimmer = device.try_into_dimmable().unwrap();
+
+    assert!(dimmer.get_dimmer_parameters().await.is_ok());
+    // The fixture has no default behavior, so the simulator rejects it
+    assert!(dimmer.get_default_behavior().await.is_err());
+}
+
+#[tokio::test]
+async fn fails_fast_when_nothing_is_listening() {
+    let addr = {
+        let sim = common::spawn_model("HS220").await;
+        sim.addr
+    };
+    tokio::task::yield_now().await;
+
+    let mut device = Device::try_new(addr, "HS220(US)").unwrap();
+    assert!(device.get_sysinfo().await.is_err());
+}
@@ -0,0 +1,73 @@
+#![cfg(feature = "tokio")]
+
+mod common;
+
+use chrono::{TimeZone, Utc};
+use overhead_tplink::{devices::Device, error::TpError, prelude::*, timezones};
+
+#[tokio::test]
+async fn reads_clock_in_one_round_trip() {
+    let sim = common::spawn_model("HS110").await;
+    let device = Device::try_new(sim.addr, "HS110(US)").unwrap();
+
+    let (time, timezone) = device.get_clock().await.unwrap();
+    assert_eq!((time.year, time.month, time.mday), (2024, 10, 3));
+    assert_eq!((time.hour, time.min, time.sec), (18, 42, 7));
+    assert_eq!(timezone.index, 18);
+    assert_eq!(timezone.dst_offset, Some(60));
+    assert_eq!(sim.device().requests_handled(), 1);
+}
+
+#[tokio::test]
+async fn bulbs_use_the_smartlife_module() {
+    let sim = common::spawn_model("KL130").await;
+    let device = Device::try_new(sim.addr, "KL130(US)").unwrap();
+
+    assert_eq!(device.get_timezone().await.unwrap().index, 41);
+    assert_eq!(device.get_time().await.unwrap().year, 2025);
+}
+
+#[tokio::test]
+async fn measures_drift_in_the_device_zone() {
+    // 18:42:07 on 3 Oct 2024 in New York is 22:42:07 UTC
+    let time = overhead_tplink::models::DeviceTime {
+        year: 2024,
+        month: 10,
+        mday: 3,
+        hour: 18,
+        min: 42,
+        sec: 7,
+    };
+    let now = Utc.with_ymd_and_hms(2024, 10, 3, 22, 40, 0).unwrap();
+    assert_eq!(timezones::clock_drift(&time, 18, now), Some(127));
+    assert_eq!(timezones::device_time_at(18, now).unwrap().hour, 18);
+}
+
+#[tokio::test]
+async fn maps_iana_zones_to_indices() {
+    assert!((0..110).all(|index| timezones::zone_of(index).is_some()));
+    assert_eq!(timezones::zone_of(110), None);
+
+    assert_eq!(timezones::index_of("EST5EDT"), Some(18));
+    assert_eq!(timezones::index_of("Europe/Amsterdam"), Some(41));
+    // Not in the table, but keeps Eastern time all year
+    assert_eq!(timezones::index_of("America/New_York"), Some(18));
+    assert_eq!(timezones::index_of("Nowhere/Special"), None);
+}
+
+#[tokio::test]
+async fn sets_zone_and_clock() {
+    let sim = common::spawn_model("HS110").await;
+    let mut device = Device::try_new(sim.addr, "HS110(US)").unwrap();
+
+    device.set_zone("Europe/Amsterdam").await.unwrap();
+    let (time, timezone) = device.get_clock().await.unwrap();
+    assert_eq!(timezone.index, 41);
+    let drift = timezones::clock_drift(&time, timezone.index, Utc::now()).unwrap();
+    assert!(drift.abs() <= 2, "drift {}", drift);
+
+    assert!(matches!(
+        device.set_zone("Nowhere/Special").await,
+        Err(TpError::InvalidArgument { .. })
+    ));
+}
@@ -0,0 +1,44 @@
+#![allow(dead_code)]
+use overhead_tplink::simulator::{load_fixtures, Simulator};
+use std::net::{IpAddr, Ipv4Addr};
+
+/// The fixture file with real responses from an ES20M, KP405 and HS220
+pub fn devices_json() -> String {
+    format!("{}/../../devices.json", env!("CARGO_MANIFEST_DIR"))
+}
+
+/// Additional fixtures for device types that aren't in `devices.json`
+pub fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+/// Spawn every device in a fixture file on its own loopback port
+pub async fn spawn_fixtures(path: &str) -> Vec<Simulator> {
+    let mut simulators = vec![];
+    for device in load_fixtures(path).expect("fixtures should load") {
+        simulators.push(
+            Simulator::spawn(device, IpAddr::V4(Ipv4Addr::LOCALHOST), 0)
+                .await
+                .expect("simulator should bind"),
+        );
+    }
+    simulators
+}
+
+/// Spawn the device with the given model from `devices.json` or
+/// `tests/fixtures`
+pub async fn spawn_model(model: &str) -> Simulator {
+    let fixtures = std::fs::read_dir(fixture(""))
+        .expect("fixtures directory should exist")
+        .map(|entry| entry.unwrap().path().display().to_string());
+
+    let device = std::iter::once(devices_json())
+        .chain(fixtures)
+        .flat_map(|path| load_fixtures(path).expect("fixtures should load"))
+        .find(|d| d.sysinfo()["model"].as_str().unwrap().starts_with(model))
+        .expect("model should be in the fixtures");
+
+    Simulator::spawn(device, IpAddr::V4(Ipv4Addr::LOCALHOST), 0)
+        .await
+        .expect("simulator should bind")
+}
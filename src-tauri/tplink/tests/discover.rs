@@ -0,0 +1,73 @@
+#![cfg(feature = "tokio")]
+
+mod common;
+
+use overhead_tplink::discover::{discover_at, DiscoveryConfig, DiscoveryTarget};
+use std::net::Ipv4Addr;
+
+#[test]
+fn parses_hosts_and_ranges() {
+    assert_eq!(
+        "10.0.4.20".parse::<DiscoveryTarget>().unwrap(),
+        DiscoveryTarget::Host(Ipv4Addr::new(10, 0, 4, 20))
+    );
+    assert_eq!(
+        "192.168.50.77/24".parse::<DiscoveryTarget>().unwrap(),
+        DiscoveryTarget::Range {
+            network: Ipv4Addr::new(192, 168, 50, 0),
+            prefix_len: 24
+        }
+    );
+    assert!("192.168.50.0/33".parse::<DiscoveryTarget>().is_err());
+    assert!("10.0.0.0/8".parse::<DiscoveryTarget>().is_err());
+    assert!("not an address".parse::<DiscoveryTarget>().is_err());
+}
+
+#[test]
+fn ranges_skip_network_and_broadcast_addresses() {
+    let hosts = "192.168.50.0/24"
+        .parse::<DiscoveryTarget>()
+        .unwrap()
+        .hosts();
+
+    assert_eq!(hosts.len(), 254);
+    assert_eq!(hosts.first(), Some(&Ipv4Addr::new(192, 168, 50, 1)));
+    assert_eq!(hosts.last(), Some(&Ipv4Addr::new(192, 168, 50, 254)));
+}
+
+#[test]
+fn config_resolves_to_unique_targets() {
+    let config = DiscoveryConfig {
+        scan_interfaces: false,
+        global_broadcast: true,
+        targets: vec!["10.0.0.5".into(), "10.0.0.4/30".into()],
+    };
+
+    let targets = config.resolve().unwrap();
+    let ips = targets.iter().map(|addr| addr.ip()).collect::<Vec<_>>();
+    assert_eq!(
+        ips,
+        ["10.0.0.5", "10.0.0.6", "255.255.255.255"]
+            .map(|ip| ip.parse::<std::net::IpAddr>().unwrap())
+    );
+    assert!(targets.iter().all(|addr| addr.port() == 9999));
+}
+
+#[tokio::test]
+async fn discovers_simulated_devices() {
+    let simulators = common::spawn_fixtures(&common::devices_json()).await;
+    let targets = simulators.iter().map(|s| s.addr).collect::<Vec<_>>();
+
+    let mut models = discover_at(&targets)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|(addr, resp)| {
+            assert!(targets.contains(&addr));
+            resp.sysinfo().model.clone()
+        })
+        .collect::<Vec<_>>();
+    models.sort();
+
+    assert_eq!(models, ["ES20M(US)", "HS220(US)", "KP405(US)"]);
+}
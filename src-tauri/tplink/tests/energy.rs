@@ -0,0 +1,108 @@
+#![cfg(feature = "tokio")]
+
+mod common;
+
+use overhead_tplink::{devices::Device, prelude::*};
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 1e-9,
+        "expected {expected}, got {actual}"
+    );
+}
+
+#[tokio::test]
+async fn reads_realtime_in_volts_and_amps() {
+    let sim = common::spawn_model("HS110").await;
+    let mut device = Device::try_new(sim.addr, "HS110(US)").unwrap();
+
+    let realtime = device
+        .try_into_energy_meter()
+        .unwrap()
+        .get_realtime()
+        .await
+        .unwrap();
+    assert_close(realtime.voltage, 121.4);
+    assert_close(realtime.current, 0.071);
+    assert_close(realtime.power, 6.8);
+    assert_close(realtime.total, 0.801);
+}
+
+#[tokio::test]
+async fn normalizes_realtime_in_millivolts_and_milliamps() {
+    let sim = common::spawn_model("KP115").await;
+    let mut device = Device::try_new(sim.addr, "KP115(US)").unwrap();
+
+    let realtime = device
+        .try_into_energy_meter()
+        .unwrap()
+        .get_realtime()
+        .await
+        .unwrap();
+    assert_close(realtime.voltage, 120.35);
+    assert_close(realtime.current, 1.21);
+    assert_close(realtime.power, 141.3);
+    assert_close(realtime.total, 2.425);
+}
+
+#[tokio::test]
+async fn reads_a_bulbs_realtime_from_the_common_emeter() {
+    let sim = common::spawn_model("KL110").await;
+    let mut device = Device::try_new(sim.addr, "KL110(US)").unwrap();
+
+    let realtime = device
+        .try_into_energy_meter()
+        .unwrap()
+        .get_realtime()
+        .await
+        .unwrap();
+    assert_close(realtime.power, 1.8);
+}
+
+#[tokio::test]
+async fn reads_daily_and_monthly_stats() {
+    let sim = common::spawn_model("HS110").await;
+    let mut device = Device::try_new(sim.addr, "HS110(US)").unwrap();
+    let meter = device.try_into_energy_meter().unwrap();
+
+    let days = meter.get_daily_stats(2024, 10).await.unwrap();
+    assert_eq!(
+        days.iter().map(|d| d.day).collect::<Vec<_>>(),
+        [Some(1), Some(2), Some(3)]
+    );
+    assert_close(days[0].energy, 0.254);
+
+    let months = meter.get_monthly_stats(2024).await.unwrap();
+    assert_eq!(months.len(), 2);
+    assert_eq!((months[1].month, months[1].day), (10, None));
+    assert_close(months[1].energy, 0.589);
+
+    let sim = common::spawn_model("KP115").await;
+    let mut device = Device::try_new(sim.addr, "KP115(US)").unwrap();
+    let months = device
+        .try_into_energy_meter()
+        .unwrap()
+        .get_monthly_stats(2024)
+        .await
+        .unwrap();
+    assert_close(months[0].energy, 2.425);
+}
+
+#[tokio::test]
+async fn erases_stats() {
+    let sim = common::spawn_model("KP115").await;
+    let mut device = Device::try_new(sim.addr, "KP115(US)").unwrap();
+    let mut meter = device.try_into_energy_meter().unwrap();
+
+    meter.erase_stats().await.unwrap();
+    assert!(meter.get_daily_stats(2024, 10).await.unwrap().is_empty());
+    assert_close(meter.get_realtime().await.unwrap().total, 0.0);
+}
+
+#[tokio::test]
+async fn only_metered_devices_are_energy_meters() {
+    let sim = common::spawn_model("HS220").await;
+    let mut device = Device::try_new(sim.addr, "HS220(US)").unwrap();
+
+    assert!(device.try_into_energy_meter().is_err());
+}
@@ -0,0 +1,114 @@
+#![cfg(feature = "tokio")]
+
+mod common;
+
+use overhead_tplink::{devices::Device, error::TpError, models::LightTransition, prelude::*};
+use tokio::{io::AsyncWriteExt, net::TcpListener};
+
+#[tokio::test]
+async fn classifies_unsupported_modules_and_methods() {
+    let sim = common::spawn_model("ES20M").await;
+    let mut device = Device::try_new(sim.addr, "ES20M(US)").unwrap();
+
+    let err = device
+        .try_into_dimmable()
+        .unwrap()
+        .get_default_behavior()
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.at(sim.addr),
+        TpError::MethodNotSupported {
+            addr: Some(sim.addr),
+            module: "smartlife.iot.dimmer".to_string(),
+            method: "get_default_behavior".to_string(),
+        }
+    );
+
+    // Driving the dimmer as a bulb hits a module it doesn't have
+    let mut bulb = Device::try_new(sim.addr, "KL130(US)").unwrap();
+    let err = bulb
+        .try_into_smart_bulb()
+        .unwrap()
+        .get_light_state()
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        TpError::ModuleNotSupported { module, .. }
+            if module == "smartlife.iot.smartbulb.lightingservice"
+    ));
+}
+
+#[tokio::test]
+async fn classifies_invalid_arguments() {
+    let sim = common::spawn_model("KL130").await;
+    let mut device = Device::try_new(sim.addr, "KL130(US)").unwrap();
+
+    let err = device
+        .try_into_smart_bulb()
+        .unwrap()
+        .transition_light_state(&LightTransition {
+            color_temp: Some(20000),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        TpError::InvalidArgument { method, .. } if method == "transition_light_state"
+    ));
+}
+
+#[tokio::test]
+async fn reports_refused_connections_with_the_address() {
+    let addr = {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap()
+    };
+
+    let err = Device::try_new(addr, "HS220(US)")
+        .unwrap()
+        .get_sysinfo()
+        .await
+        .unwrap_err();
+    assert_eq!(err, TpError::ConnectionRefused { addr: Some(addr) });
+    assert!(err.is_unreachable());
+}
+
+#[tokio::test]
+async fn reports_timeouts() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let _server = tokio::spawn(async move {
+        // Accept and then never answer
+        let (_stream, _) = listener.accept().await.unwrap();
+        std::future::pending::<()>().await;
+    });
+
+    let err = Device::try_new(addr, "HS220(US)")
+        .unwrap()
+        .get_sysinfo()
+        .await
+        .unwrap_err();
+    assert!(matches!(err, TpError::Timeout { addr: Some(a), .. } if a == addr));
+}
+
+#[tokio::test]
+async fn reports_malformed_frames() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let _server = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        stream.write_all(&u32::MAX.to_be_bytes()).await.unwrap();
+        std::future::pending::<()>().await;
+    });
+
+    let err = Device::try_new(addr, "HS220(US)")
+        .unwrap()
+        .get_sysinfo()
+        .await
+        .unwrap_err();
+    assert!(matches!(err, TpError::MalformedFrame { addr: Some(a), .. } if a == addr));
+    assert!(!err.is_unreachable());
+}
@@ -0,0 +1,59 @@
+#![cfg(feature = "tokio")]
+
+mod common;
+
+use overhead_tplink::{devices::Device, models::PowerState};
+
+const KP400: &str = "800675C2B1E0F9A8D7C6B5A4F3E2D1C0B9A8F7E6";
+
+fn on_at(brightness: u8) -> PowerState {
+    PowerState {
+        on: true,
+        brightness: Some(brightness),
+    }
+}
+
+#[tokio::test]
+async fn dims_and_switches_on_in_one_request() {
+    let sim = common::spawn_model("HS220").await;
+    let mut device = Device::try_new(sim.addr, "HS220(US)").unwrap();
+    let before = sim.device().requests_handled();
+
+    device.set_power_state(&on_at(20)).await.unwrap();
+    assert_eq!(sim.device().requests_handled(), before + 1);
+    assert_eq!(device.get_power_state().await.unwrap(), on_at(20));
+
+    let off = PowerState {
+        on: false,
+        brightness: Some(20),
+    };
+    device.set_power_state(&off).await.unwrap();
+    assert_eq!(device.get_power_state().await.unwrap(), off);
+}
+
+#[tokio::test]
+async fn sets_bulb_brightness_through_the_lighting_service() {
+    let sim = common::spawn_model("KL130").await;
+    let mut device = Device::try_new(sim.addr, "KL130(US)").unwrap();
+
+    device.set_power_state(&on_at(60)).await.unwrap();
+    assert!(sim.device().is_on());
+    assert_eq!(sim.device().brightness(), Some(60));
+}
+
+#[tokio::test]
+async fn switches_outlets_without_brightness() {
+    let sim = common::spawn_model("KP400").await;
+    let child_id = format!("{KP400}00");
+    let mut outlet = Device::try_new_child(sim.addr, "KP400(US)", &child_id).unwrap();
+
+    outlet.set_power_state(&on_at(40)).await.unwrap();
+    assert_eq!(
+        outlet.get_power_state().await.unwrap(),
+        PowerState {
+            on: true,
+            brightness: None,
+        }
+    );
+    assert_eq!(sim.device().child("00").unwrap()["state"], 1);
+}
@@ -0,0 +1,91 @@
+#![cfg(feature = "tokio")]
+
+mod common;
+
+use overhead_tplink::{devices::Device, error::TpError, models::PreferredState, prelude::*};
+
+fn dimmer_preset(index: u8, brightness: u8) -> PreferredState {
+    PreferredState {
+        index,
+        brightness,
+        hue: None,
+        saturation: None,
+        color_temp: None,
+    }
+}
+
+#[tokio::test]
+async fn toggles_the_status_led() {
+    let sim = common::spawn_model("HS220").await;
+    let mut device = Device::try_new(sim.addr, "HS220(US)").unwrap();
+    assert!(device.get_led_on().await.unwrap());
+
+    device.set_led_on(false).await.unwrap();
+    assert!(!device.get_led_on().await.unwrap());
+    assert_eq!(sim.device().sysinfo()["led_off"], 1);
+
+    device.set_led_on(true).await.unwrap();
+    assert!(device.get_led_on().await.unwrap());
+}
+
+#[tokio::test]
+async fn bulbs_have_no_status_led() {
+    let sim = common::spawn_model("KL130").await;
+    let device = Device::try_new(sim.addr, "KL130(US)").unwrap();
+
+    assert!(matches!(
+        device.set_led_on(false).await,
+        Err(TpError::MethodNotSupported { .. })
+    ));
+}
+
+#[tokio::test]
+async fn saves_and_applies_dimmer_presets() {
+    let sim = common::spawn_model("HS220").await;
+    let mut device = Device::try_new(sim.addr, "HS220(US)").unwrap();
+    let mut dimmer = device.try_into_presets().unwrap();
+    assert_eq!(dimmer.get_presets().await.unwrap().len(), 4);
+
+    dimmer.save_preset(&dimmer_preset(2, 40)).await.unwrap();
+    assert_eq!(dimmer.get_presets().await.unwrap()[2], dimmer_preset(2, 40));
+
+    let applied = dimmer.apply_preset(2).await.unwrap();
+    assert_eq!(applied.brightness, 40);
+    assert_eq!(sim.device().brightness(), Some(40));
+    assert!(sim.device().is_on());
+
+    assert!(matches!(
+        dimmer.apply_preset(7).await,
+        Err(TpError::InvalidArgument { .. })
+    ));
+}
+
+#[tokio::test]
+async fn applies_bulb_presets_through_the_lighting_service() {
+    let sim = common::spawn_model("KL130").await;
+    let mut device = Device::try_new(sim.addr, "KL130(US)").unwrap();
+
+    let applied = device
+        .try_into_presets()
+        .unwrap()
+        .apply_preset(2)
+        .await
+        .unwrap();
+    assert_eq!(applied.hue, Some(120));
+
+    let light_state = device.get_sysinfo().await.unwrap().light_state.unwrap();
+    assert!(light_state.is_on());
+    assert_eq!(light_state.hue, Some(120));
+    assert_eq!(light_state.brightness(), Some(100));
+}
+
+#[tokio::test]
+async fn plugs_have_no_presets() {
+    let sim = common::spawn_model("HS110").await;
+    let mut device = Device::try_new(sim.addr, "HS110(US)").unwrap();
+
+    assert!(matches!(
+        device.try_into_presets().map(drop),
+        Err(TpError::Unsupported(_))
+    ));
+}
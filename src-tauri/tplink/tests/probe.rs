@@ -0,0 +1,65 @@
+#![cfg(feature = "tokio")]
+
+mod common;
+
+use overhead_tplink::{devices::Device, models::DeviceCapabilities, prelude::*};
+
+#[tokio::test]
+async fn probes_a_dimmer() {
+    let sim = common::spawn_model("HS220").await;
+    let device = Device::try_new(sim.addr, "HS220(US)").unwrap();
+
+    let capabilities = device.probe().await.unwrap();
+    assert!(capabilities.dimmer);
+    assert!(!capabilities.emeter);
+    assert!(!capabilities.lighting);
+    assert!(!capabilities.outlets);
+}
+
+#[tokio::test]
+async fn probes_a_bulb() {
+    let sim = common::spawn_model("KL130").await;
+    let device = Device::try_new(sim.addr, "KL130(US)").unwrap();
+
+    let capabilities = device.probe().await.unwrap();
+    assert!(capabilities.lighting);
+    assert!(capabilities.emeter);
+    assert!(!capabilities.dimmer);
+}
+
+#[tokio::test]
+async fn probes_a_power_strip_in_one_round_trip() {
+    let sim = common::spawn_model("HS300").await;
+    let device = Device::try_new(sim.addr, "HS300(US)").unwrap();
+
+    let capabilities = device.probe().await.unwrap();
+    assert!(capabilities.outlets);
+    assert!(capabilities.emeter);
+    assert_eq!(sim.device().requests_handled(), 1);
+}
+
+#[tokio::test]
+async fn picks_a_working_device_for_an_unknown_model() {
+    let sim = common::spawn_model("KP115").await;
+    let probe = Device::from_capabilities(sim.addr, &DeviceCapabilities::default());
+    let capabilities = probe.probe().await.unwrap();
+
+    let mut device = Device::from_capabilities(sim.addr, &capabilities);
+    let realtime = device
+        .try_into_energy_meter()
+        .unwrap()
+        .get_realtime()
+        .await
+        .unwrap();
+    assert!(realtime.power > 0.0);
+}
+
+#[tokio::test]
+async fn probes_a_power_strip_without_an_energy_meter() {
+    let sim = common::spawn_model("KP400").await;
+    let device = Device::try_new(sim.addr, "KP400(US)").unwrap();
+
+    let capabilities = device.probe().await.unwrap();
+    assert!(capabilities.outlets);
+    assert!(!capabilities.emeter);
+}
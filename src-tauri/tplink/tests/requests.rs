@@ -0,0 +1,124 @@
+#![cfg(feature = "tokio")]
+
+mod common;
+
+use overhead_tplink::{
+    devices::Device,
+    models::{DimmerParameters, SysInfo},
+    prelude::*,
+    requests::{Batch, Request, EMETER},
+};
+
+#[test]
+fn batches_share_module_objects() {
+    let batch = Batch::new()
+        .with(Request::GetSysinfo)
+        .with(Request::SetAlias("Porch".to_string()))
+        .with(Request::GetDailyStats {
+            module: EMETER.to_string(),
+            year: 2024,
+            month: 10,
+        })
+        .with(Request::SetAlias("Hallway".to_string()));
+
+    let value: serde_json::Value = serde_json::from_str(&batch.to_string()).unwrap();
+    assert_eq!(
+        value,
+        serde_json::json!({
+            "system": {
+                "get_sysinfo": null,
+                "set_dev_alias": { "alias": "Hallway" },
+            },
+            "emeter": { "get_daystat": { "year": 2024, "month": 10 } },
+        })
+    );
+    assert_eq!(batch.requests().len(), 3);
+}
+
+#[tokio::test]
+async fn reads_several_modules_in_one_round_trip() {
+    let sim = common::spawn_model("HS220").await;
+    let device = Device::try_new(sim.addr, "HS220(US)").unwrap();
+    let realtime = Request::GetRealtime {
+        module: EMETER.to_string(),
+    };
+
+    let response = device
+        .batch(
+            &Batch::new()
+                .with(Request::GetSysinfo)
+                .with(Request::GetDimmerParameters)
+                .with(realtime.clone())
+                .with(Request::GetLightState),
+        )
+        .await
+        .unwrap();
+    assert_eq!(sim.device().requests_handled(), 1);
+
+    let sysinfo: SysInfo = response.get(&Request::GetSysinfo).unwrap();
+    assert_eq!(sysinfo.model, "HS220(US)");
+    let parameters: DimmerParameters = response.get(&Request::GetDimmerParameters).unwrap();
+    assert_eq!(parameters.min_threshold, 1);
+
+    // Sections the device doesn't support fail on their own
+    let err = response.error(&realtime).unwrap();
+    assert_eq!(err.err_code, Some(-1));
+    assert!(response.error(&Request::GetLightState).is_some());
+    assert!(response.error(&Request::GetSysinfo).is_none());
+}
+
+#[tokio::test]
+async fn reports_method_errors_per_section() {
+    let sim = common::spawn_model("KP405").await;
+    let device = Device::try_new(sim.addr, "KP405(US)").unwrap();
+
+    let response = device
+        .batch(
+            &Batch::new()
+                .with(Request::SetRelayState(true))
+                .with(Request::Custom {
+                    module: "system".to_string(),
+                    method: "set_dev_alias".to_string(),
+                    params: serde_json::json!({}),
+                }),
+        )
+        .await
+        .unwrap();
+
+    assert!(response.section(&Request::SetRelayState(true)).is_ok());
+    assert_eq!(
+        response
+            .error(&Request::SetAlias(String::new()))
+            .and_then(|err| err.err_code),
+        Some(-3)
+    );
+    assert!(sim.device().is_on());
+}
+
+#[tokio::test]
+async fn snapshots_in_one_round_trip() {
+    let sim = common::spawn_model("KP115").await;
+    let mut device = Device::try_new(sim.addr, "KP115(US)").unwrap();
+
+    let snapshot = device.get_snapshot().await.unwrap();
+    assert_eq!(sim.device().requests_handled(), 1);
+    assert_eq!(snapshot.sysinfo.alias, "Space Heater");
+    assert!((snapshot.realtime.unwrap().power - 141.3).abs() < 1e-9);
+    assert!(snapshot.light_state.is_none());
+    assert!(snapshot.dimmer_parameters.is_none());
+
+    let sim = common::spawn_model("KL130").await;
+    let mut device = Device::try_new(sim.addr, "KL130(US)").unwrap();
+    let snapshot = device.get_snapshot().await.unwrap();
+    assert!(snapshot.light_state.is_some());
+}
+
+#[tokio::test]
+async fn snapshots_a_bulbs_energy_meter() {
+    let sim = common::spawn_model("KL110").await;
+    let mut device = Device::try_new(sim.addr, "KL110(US)").unwrap();
+
+    let snapshot = device.get_snapshot().await.unwrap();
+    assert_eq!(sim.device().requests_handled(), 1);
+    assert!((snapshot.realtime.unwrap().power - 1.8).abs() < 1e-9);
+}
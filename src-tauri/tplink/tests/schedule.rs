@@ -0,0 +1,131 @@
+#![cfg(feature = "tokio")]
+
+mod common;
+
+use overhead_tplink::{
+    devices::Device,
+    models::{NextAction, RuleTime, ScheduleRule},
+    prelude::*,
+};
+use serde_json::json;
+
+fn rule(name: &str, time: RuleTime, on: bool) -> ScheduleRule {
+    ScheduleRule {
+        id: None,
+        name: name.to_string(),
+        enabled: true,
+        weekdays: [true; 7],
+        repeat: true,
+        time,
+        on,
+        brightness: None,
+    }
+}
+
+#[tokio::test]
+async fn reads_rules() {
+    let sim = common::spawn_model("HS110").await;
+    let device = Device::try_new(sim.addr, "HS110(US)").unwrap();
+
+    let schedule = device.get_rules().await.unwrap();
+    assert!(schedule.enabled);
+    assert_eq!(schedule.rules.len(), 2);
+
+    let on = &schedule.rules[0];
+    assert_eq!(on.name, "Lights on");
+    assert!(on.on && on.enabled);
+    assert_eq!(on.time, RuleTime::At { minute: 420 });
+    assert_eq!(on.weekdays, [false, true, true, true, true, true, false]);
+
+    let off = &schedule.rules[1];
+    assert!(!off.on && !off.enabled);
+    assert_eq!(off.time, RuleTime::Sunset { offset: -15 });
+}
+
+#[tokio::test]
+async fn reads_next_action() {
+    let sim = common::spawn_model("HS110").await;
+    let mut device = Device::try_new(sim.addr, "HS110(US)").unwrap();
+
+    let next = device.get_next_action().await.unwrap();
+    assert!(matches!(
+        next,
+        NextAction::Schedule {
+            at: 25200,
+            on: true,
+            ..
+        }
+    ));
+    assert_eq!(device.get_sysinfo().await.unwrap().next_action, Some(next));
+}
+
+#[tokio::test]
+async fn adds_edits_and_deletes_rules() {
+    let sim = common::spawn_model("HS110").await;
+    let mut device = Device::try_new(sim.addr, "HS110(US)").unwrap();
+
+    let id = device
+        .add_rule(&rule("Sunrise", RuleTime::Sunrise { offset: 10 }, true))
+        .await
+        .unwrap();
+    let mut added = device.get_rules().await.unwrap().rules.pop().unwrap();
+    assert_eq!(added.id.as_deref(), Some(id.as_str()));
+    assert_eq!(added.time, RuleTime::Sunrise { offset: 10 });
+
+    added.time = RuleTime::At { minute: 360 };
+    device.edit_rule(&added).await.unwrap();
+    device.set_rule_enabled(&id, false).await.unwrap();
+    let edited = device.get_rules().await.unwrap().rules.pop().unwrap();
+    assert_eq!(edited.time, RuleTime::At { minute: 360 });
+    assert!(!edited.enabled);
+
+    device.delete_rule(&id).await.unwrap();
+    assert_eq!(device.get_rules().await.unwrap().rules.len(), 2);
+    assert!(device.delete_rule(&id).await.is_err());
+
+    device.delete_all_rules().await.unwrap();
+    assert!(device.get_rules().await.unwrap().rules.is_empty());
+}
+
+#[tokio::test]
+async fn disables_the_whole_schedule() {
+    let sim = common::spawn_model("HS110").await;
+    let mut device = Device::try_new(sim.addr, "HS110(US)").unwrap();
+
+    device.set_rules_enabled(false).await.unwrap();
+    let schedule = device.get_rules().await.unwrap();
+    assert!(!schedule.enabled);
+    assert_eq!(schedule.rules.len(), 2);
+}
+
+#[tokio::test]
+async fn dimmers_schedule_a_brightness() {
+    let sim = common::spawn_model("HS220").await;
+    sim.update(|d| {
+        d.set_response(
+            "smartlife.iot.common.schedule",
+            "get_next_action",
+            json!({ "type": -1, "err_code": 0 }),
+        )
+    });
+    let mut device = Device::try_new(sim.addr, "HS220(US)").unwrap();
+
+    let dim = ScheduleRule {
+        brightness: Some(30),
+        ..rule("Dim", RuleTime::At { minute: 1320 }, true)
+    };
+    device.add_rule(&dim).await.unwrap();
+
+    let rule = sim
+        .device()
+        .response("smartlife.iot.common.schedule", "get_rules")
+        .unwrap()["rule_list"][0]
+        .clone();
+    assert_eq!(rule["sact"], json!(2));
+    assert_eq!(rule["s_light"]["brightness"], json!(30));
+    assert_eq!(
+        device.get_rules().await.unwrap().rules[0].brightness,
+        Some(30)
+    );
+    assert!(!device.get_next_action().await.unwrap().is_scheduled());
+}
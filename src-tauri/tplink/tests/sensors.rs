@@ -0,0 +1,126 @@
+#![cfg(feature = "tokio")]
+
+mod common;
+
+use overhead_tplink::{
+    devices::Device,
+    models::{DeviceCapabilities, MotionRange},
+    prelude::*,
+    simulator::Simulator,
+};
+use serde_json::json;
+use std::time::Duration;
+
+/// The ES20M in `devices.json` was captured without its sensor modules
+async fn spawn_es20m() -> Simulator {
+    let sim = common::spawn_model("ES20M").await;
+    sim.update(|d| {
+        d.set_response(
+            "smartlife.iot.PIR",
+            "get_config",
+            json!({
+                "ver": "1.0",
+                "enable": 1,
+                "trigger_index": 1,
+                "cold_time": 60000,
+                "min_adc": 0,
+                "max_adc": 4095,
+                "array": [80, 50, 20, 61],
+                "err_code": 0,
+            }),
+        );
+        d.set_response(
+            "smartlife.iot.LAS",
+            "get_config",
+            json!({
+                "ver": "1.0",
+                "devs": [{
+                    "hw_id": 0,
+                    "enable": 1,
+                    "dark_index": 1,
+                    "min_adc": 0,
+                    "max_adc": 2450,
+                    "level_array": [
+                        {"name": "cloudy", "adc": 490, "value": 20},
+                        {"name": "overcast", "adc": 294, "value": 12},
+                        {"name": "dawn", "adc": 222, "value": 9},
+                    ],
+                }],
+                "err_code": 0,
+            }),
+        );
+        d.set_response(
+            "smartlife.iot.LAS",
+            "get_current_brt",
+            json!({"value": 35, "err_code": 0}),
+        );
+    });
+    sim
+}
+
+#[tokio::test]
+async fn configures_motion_sensor() {
+    let sim = spawn_es20m().await;
+    let mut device = Device::try_new(sim.addr, "ES20M(US)").unwrap();
+    let sensor = device.try_into_motion_sensor().unwrap();
+
+    let config = sensor.get_motion_config().await.unwrap();
+    assert!(config.enabled);
+    assert_eq!(config.range, MotionRange::Mid);
+    assert_eq!(config.cold_time, 60000);
+    assert_eq!(config.thresholds, [80, 50, 20, 61]);
+
+    sensor.set_motion_enabled(false).await.unwrap();
+    sensor.set_motion_range(MotionRange::Far).await.unwrap();
+    sensor
+        .set_cold_time(Duration::from_secs(300))
+        .await
+        .unwrap();
+    let config = sensor.get_motion_config().await.unwrap();
+    assert!(!config.enabled);
+    assert_eq!(config.range, MotionRange::Far);
+    assert_eq!(config.cold_time, 300_000);
+
+    sensor.set_motion_threshold(42).await.unwrap();
+    let config = sensor.get_motion_config().await.unwrap();
+    assert_eq!(config.range, MotionRange::Custom);
+    assert_eq!(config.thresholds[3], 42);
+}
+
+#[tokio::test]
+async fn configures_ambient_light_sensor() {
+    let sim = spawn_es20m().await;
+    let mut device = Device::try_new(sim.addr, "ES20M(US)").unwrap();
+    let sensor = device.try_into_ambient_light_sensor().unwrap();
+
+    assert_eq!(sensor.get_ambient_light().await.unwrap(), 35);
+    let config = sensor.get_ambient_light_config().await.unwrap();
+    assert!(config.enabled);
+    assert_eq!(config.levels.len(), 3);
+    assert_eq!(config.threshold(), Some(12));
+
+    sensor.set_ambient_light_enabled(false).await.unwrap();
+    sensor.set_ambient_light_threshold(30).await.unwrap();
+    let config = sensor.get_ambient_light_config().await.unwrap();
+    assert!(!config.enabled);
+    assert_eq!(config.threshold(), Some(30));
+}
+
+#[tokio::test]
+async fn only_es20m_has_sensors() {
+    let sim = spawn_es20m().await;
+    let capabilities = Device::from_capabilities(sim.addr, &DeviceCapabilities::default())
+        .probe()
+        .await
+        .unwrap();
+    assert!(capabilities.motion && capabilities.dimmer);
+    let mut device = Device::from_capabilities(sim.addr, &capabilities);
+    assert!(device.try_into_motion_sensor().is_ok());
+    assert!(device.try_into_dimmable().is_ok());
+
+    let sim = common::spawn_model("HS220").await;
+    let mut device = Device::try_new(sim.addr, "HS220(US)").unwrap();
+    assert!(device.try_into_motion_sensor().is_err());
+    assert!(device.try_into_ambient_light_sensor().is_err());
+    assert!(!device.probe().await.unwrap().motion);
+}
@@ -0,0 +1,36 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use overhead_tplink::solar::sun_times;
+
+fn assert_near(actual: DateTime<Utc>, expected: &str) {
+    let expected = expected.parse::<DateTime<Utc>>().unwrap();
+    assert!(
+        (actual - expected).num_seconds().abs() <= 120,
+        "{} is not within two minutes of {}",
+        actual,
+        expected
+    );
+}
+
+#[test]
+fn computes_midsummer_in_amsterdam() {
+    let date = NaiveDate::from_ymd_opt(2024, 6, 21).unwrap();
+    let times = sun_times(date, 52.3676, 4.9041).unwrap();
+
+    assert_near(times.sunrise, "2024-06-21T03:18:00Z");
+    assert_near(times.sunset, "2024-06-21T20:06:00Z");
+}
+
+#[test]
+fn computes_the_equinox_west_of_greenwich() {
+    let date = NaiveDate::from_ymd_opt(2024, 3, 20).unwrap();
+    let times = sun_times(date, 40.7128, -74.006).unwrap();
+
+    assert_near(times.sunrise, "2024-03-20T10:59:00Z");
+    assert_near(times.sunset, "2024-03-20T23:08:00Z");
+}
+
+#[test]
+fn has_no_sunset_in_polar_summer() {
+    let date = NaiveDate::from_ymd_opt(2024, 6, 21).unwrap();
+    assert_eq!(sun_times(date, 78.2232, 15.6267), None);
+}
@@ -0,0 +1,90 @@
+#![cfg(feature = "tokio")]
+
+mod common;
+
+use overhead_tplink::{devices::Device, prelude::*};
+
+const HS300: &str = "8006AF35494E7DB13DDE9B8F40D8E8C62E31CD28";
+const KP400: &str = "800675C2B1E0F9A8D7C6B5A4F3E2D1C0B9A8F7E6";
+
+#[tokio::test]
+async fn lists_outlets_by_full_id() {
+    let sim = common::spawn_model("KP400").await;
+    let mut device = Device::try_new(sim.addr, "KP400(US)").unwrap();
+
+    let sysinfo = device.get_sysinfo().await.unwrap();
+    assert_eq!(
+        sysinfo.child_ids(),
+        [format!("{KP400}00"), format!("{KP400}01")]
+    );
+}
+
+#[tokio::test]
+async fn toggles_one_outlet() {
+    let sim = common::spawn_model("KP400").await;
+    let child_id = format!("{KP400}00");
+    let mut outlet = Device::try_new_child(sim.addr, "KP400(US)", &child_id).unwrap();
+
+    assert!(!outlet.get_is_on().await.unwrap());
+    assert!(outlet.toggle().await.unwrap());
+    assert_eq!(sim.device().child("00").unwrap()["state"], 1);
+    assert_eq!(sim.device().child("01").unwrap()["state"], 1);
+
+    assert!(!outlet.toggle().await.unwrap());
+    assert_eq!(sim.device().child("00").unwrap()["state"], 0);
+    assert_eq!(sim.device().child("01").unwrap()["state"], 1);
+}
+
+#[tokio::test]
+async fn names_outlets() {
+    let sim = common::spawn_model("HS300").await;
+    let child_id = format!("{HS300}01");
+    let mut outlet = Device::try_new_child(sim.addr, "HS300(US)", &child_id).unwrap();
+
+    assert_eq!(outlet.get_alias().await.unwrap(), "Printer");
+    outlet.set_alias("Scanner").await.unwrap();
+    assert_eq!(outlet.get_alias().await.unwrap(), "Scanner");
+
+    let mut strip = Device::try_new(sim.addr, "HS300(US)").unwrap();
+    assert_eq!(strip.get_alias().await.unwrap(), "Office Strip");
+}
+
+#[tokio::test]
+async fn reads_outlet_on_time() {
+    let sim = common::spawn_model("HS300").await;
+    let child_id = format!("{HS300}00");
+    let mut outlet = Device::try_new_child(sim.addr, "HS300(US)", &child_id).unwrap();
+
+    assert_eq!(outlet.get_on_time().await.unwrap(), Some(5400));
+}
+
+#[tokio::test]
+async fn meters_each_outlet() {
+    let sim = common::spawn_model("HS300").await;
+    let mut strip = Device::try_new(sim.addr, "HS300(US)").unwrap();
+    assert!(strip
+        .try_into_energy_meter()
+        .unwrap()
+        .get_realtime()
+        .await
+        .is_err());
+
+    let child_id = format!("{HS300}00");
+    let mut outlet = Device::try_new_child(sim.addr, "HS300(US)", &child_id).unwrap();
+    let realtime = outlet
+        .try_into_energy_meter()
+        .unwrap()
+        .get_realtime()
+        .await
+        .unwrap();
+    assert!((realtime.power - 58.12).abs() < 1e-9);
+}
+
+#[tokio::test]
+async fn rejects_unknown_outlets() {
+    let sim = common::spawn_model("KP400").await;
+    let mut outlet = Device::try_new_child(sim.addr, "KP400(US)", "nope").unwrap();
+    assert!(outlet.switch_on().await.is_err());
+
+    assert!(Device::try_new_child(sim.addr, "HS220(US)", "nope").is_err());
+}
@@ -0,0 +1,110 @@
+#![cfg(feature = "tokio")]
+
+mod common;
+
+use overhead_tplink::models::{ActiveMode, NextAction, PreferredState, SysInfo};
+use serde_json::{json, Value};
+
+fn sysinfos() -> Vec<Value> {
+    let devices: Vec<Value> =
+        serde_json::from_str(&std::fs::read_to_string(common::devices_json()).unwrap()).unwrap();
+    devices
+        .into_iter()
+        .map(|device| device["system"]["get_sysinfo"].clone())
+        .collect()
+}
+
+/// The sysinfo as `SysInfo` serializes it: without missing fields, and with
+/// the names of aliased fields
+fn canonical(value: Value) -> Value {
+    let Value::Object(fields) = value else {
+        return value;
+    };
+    fields
+        .into_iter()
+        .filter(|(_, value)| !value.is_null())
+        .map(|(key, value)| match key.as_str() {
+            "type" | "mic_type" => ("hw_type".to_string(), value),
+            "mic_mac" => ("mac".to_string(), value),
+            _ => (key, value),
+        })
+        .collect()
+}
+
+#[test]
+fn round_trips_every_known_device() {
+    for raw in sysinfos() {
+        let sysinfo: SysInfo = serde_json::from_value(raw.clone()).unwrap();
+        assert_eq!(
+            canonical(serde_json::to_value(&sysinfo).unwrap()),
+            canonical(raw)
+        );
+    }
+}
+
+#[test]
+fn models_dimmer_fields() {
+    let raw = sysinfos()
+        .into_iter()
+        .find(|raw| raw["model"] == "HS220(US)")
+        .unwrap();
+    let sysinfo: SysInfo = serde_json::from_value(raw).unwrap();
+
+    assert_eq!(sysinfo.active_mode, Some(ActiveMode::None));
+    assert_eq!(sysinfo.next_action, Some(NextAction::None));
+    assert!(sysinfo.led_on());
+    assert_eq!(sysinfo.status.as_deref(), Some("new"));
+    assert_eq!(sysinfo.latitude, Some(39.0871));
+    assert_eq!(
+        sysinfo.preferred_state.unwrap()[1],
+        PreferredState {
+            index: 1,
+            brightness: 75,
+            hue: None,
+            saturation: None,
+            color_temp: None,
+        }
+    );
+}
+
+#[test]
+fn accepts_float_coordinates_and_keeps_unknown_fields() {
+    let sysinfo: SysInfo = serde_json::from_value(json!({
+        "active_mode": "holiday",
+        "alias": "Lamp",
+        "deviceId": "8006",
+        "err_code": 0,
+        "hwId": "45E2",
+        "hw_ver": "1.0",
+        "latitude": 52.3676,
+        "led_off": 1,
+        "longitude": 4.9041,
+        "mac": "50:C7:BF:00:00:01",
+        "mic_type": "IOT.SMARTPLUGSWITCH",
+        "model": "HS100(EU)",
+        "next_action": { "type": 3, "schd_sec": 600 },
+        "rssi": -60,
+        "sw_ver": "1.2.5",
+        "unreleased_field": { "x": 1 },
+    }))
+    .unwrap();
+
+    assert_eq!(sysinfo.active_mode, Some(ActiveMode::Unknown));
+    assert_eq!(sysinfo.latitude, Some(52.3676));
+    assert_eq!(sysinfo.longitude, Some(4.9041));
+    assert!(!sysinfo.led_on());
+    assert_eq!(
+        sysinfo.next_action,
+        Some(NextAction::Other {
+            action_type: 3,
+            id: None,
+            at: Some(600),
+            on: None,
+        })
+    );
+    assert_eq!(sysinfo.other["unreleased_field"], json!({ "x": 1 }));
+
+    let value = serde_json::to_value(&sysinfo).unwrap();
+    assert_eq!(value["latitude_i"], 523676);
+    assert_eq!(value["unreleased_field"], json!({ "x": 1 }));
+}
@@ -0,0 +1,116 @@
+#![cfg(feature = "tokio")]
+
+mod common;
+
+use overhead_tplink::{
+    devices::Device,
+    error::TpError,
+    models::{AwayModeRule, CountdownRule, RuleTime},
+    prelude::*,
+};
+use std::time::Duration;
+
+#[tokio::test]
+async fn switches_off_after_a_delay() {
+    let sim = common::spawn_model("KP115").await;
+    let mut device = Device::try_new(sim.addr, "KP115(US)").unwrap();
+    let mut countdown = device.try_into_countdown().unwrap();
+
+    let id = countdown
+        .switch_after(Duration::from_secs(30 * 60), false)
+        .await
+        .unwrap();
+    let timers = countdown.get_countdowns().await.unwrap();
+    assert_eq!(timers.len(), 1);
+    assert_eq!(timers[0].id.as_deref(), Some(id.as_str()));
+    assert_eq!(timers[0].delay, 1800);
+    assert_eq!(timers[0].remaining, Some(1800));
+    assert!(!timers[0].on);
+
+    // Replaces the running timer rather than failing on a full table
+    countdown
+        .switch_after(Duration::from_secs(60), true)
+        .await
+        .unwrap();
+    let timers = countdown.get_countdowns().await.unwrap();
+    assert_eq!(timers.len(), 1);
+    assert!(timers[0].on);
+}
+
+#[tokio::test]
+async fn edits_and_deletes_timers() {
+    let sim = common::spawn_model("KP115").await;
+    let mut device = Device::try_new(sim.addr, "KP115(US)").unwrap();
+    let mut countdown = device.try_into_countdown().unwrap();
+
+    let timer = CountdownRule {
+        id: None,
+        name: "Heater".to_string(),
+        enabled: true,
+        delay: 600,
+        on: false,
+        remaining: None,
+    };
+    let id = countdown.add_countdown(&timer).await.unwrap();
+    assert!(countdown.add_countdown(&timer).await.is_err());
+    assert!(countdown.edit_countdown(&timer).await.is_err());
+
+    let edited = CountdownRule {
+        id: Some(id.clone()),
+        enabled: false,
+        ..timer
+    };
+    countdown.edit_countdown(&edited).await.unwrap();
+    let timers = countdown.get_countdowns().await.unwrap();
+    assert!(!timers[0].enabled);
+    assert_eq!(timers[0].remaining, None);
+
+    countdown.delete_countdown(&id).await.unwrap();
+    assert!(countdown.get_countdowns().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn bulbs_have_no_timers() {
+    let sim = common::spawn_model("KL110").await;
+    let mut device = Device::try_new(sim.addr, "KL110(US)").unwrap();
+
+    assert!(matches!(
+        device.try_into_countdown().err(),
+        Some(TpError::Unsupported(_))
+    ));
+    assert!(device.try_into_away_mode().is_err());
+}
+
+#[tokio::test]
+async fn manages_away_mode() {
+    let sim = common::spawn_model("KP115").await;
+    let mut device = Device::try_new(sim.addr, "KP115(US)").unwrap();
+    let mut away = device.try_into_away_mode().unwrap();
+
+    let rules = away.get_away_rules().await.unwrap();
+    assert!(!rules.enabled);
+    assert_eq!(rules.rules[0].start, RuleTime::Sunset { offset: 30 });
+    assert_eq!(rules.rules[0].end, RuleTime::At { minute: 1380 });
+
+    let id = away
+        .add_away_rule(&AwayModeRule {
+            id: None,
+            name: "Weekends".to_string(),
+            enabled: true,
+            weekdays: [true, false, false, false, false, false, true],
+            repeat: true,
+            start: RuleTime::At { minute: 1080 },
+            end: RuleTime::At { minute: 1320 },
+        })
+        .await
+        .unwrap();
+    away.set_away_mode_enabled(true).await.unwrap();
+
+    let rules = away.get_away_rules().await.unwrap();
+    assert!(rules.enabled);
+    assert_eq!(rules.rules.len(), 2);
+    assert_eq!(rules.rules[1].id.as_deref(), Some(id.as_str()));
+
+    away.delete_all_away_rules().await.unwrap();
+    assert!(away.get_away_rules().await.unwrap().rules.is_empty());
+}
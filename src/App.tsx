@@ -4,29 +4,65 @@ import { PiEmpty } from 'react-icons/pi';
 import { cx } from 'class-variance-authority';
 import { useEffect, useState } from 'react';
 import { AiOutlinePoweroff } from 'react-icons/ai';
-import { commands, Device } from './bindings';
+import { commands, Device, DeviceCapabilities, events } from './bindings';
+import { describeError } from './errors';
 import './global.css';
 import { Logo } from './Logo';
 
 export default function App() {
-  async function setBrightness(socketAddr: string, brightness: number) {
-    const result = await commands.setBrightness(socketAddr, brightness);
+  async function setBrightness(id: string, brightness: number) {
+    const result = await commands.setBrightness(id, brightness);
     if (result.status !== 'ok') {
-      setError(JSON.stringify(result.error, null, 2));
+      setError(describeError(result.error));
     } else {
       setDevices(devices =>
-        devices.map(d => (d.addr === socketAddr ? { ...d, brightness } : d))
+        devices.map(d => (d.id === id ? { ...d, brightness } : d))
       );
     }
   }
 
-  async function toggle(socketAddr: string) {
-    const result = await commands.toggle(socketAddr);
+  async function setHue(id: string, hue: number) {
+    const result = await commands.transitionLightState(id, {
+      on: null,
+      hue,
+      saturation: 100,
+      colorTemp: 0,
+      brightness: null,
+      transitionPeriod: 300,
+    });
+    if (result.status !== 'ok') {
+      setError(describeError(result.error));
+    } else {
+      setDevices(devices =>
+        devices.map(d =>
+          d.id === id ? { ...d, isOn: true, lightState: result.data } : d
+        )
+      );
+    }
+  }
+
+  async function applyPreset(id: string, index: number) {
+    const result = await commands.applyPreset(id, index);
     if (result.status !== 'ok') {
-      setError(JSON.stringify(result.error, null, 2));
+      setError(describeError(result.error));
     } else {
       setDevices(devices =>
-        devices.map(d => (d.addr === socketAddr ? { ...d, isOn: !d.isOn } : d))
+        devices.map(d =>
+          d.id === id
+            ? { ...d, isOn: true, brightness: result.data.brightness }
+            : d
+        )
+      );
+    }
+  }
+
+  async function toggle(id: string) {
+    const result = await commands.toggle(id);
+    if (result.status !== 'ok') {
+      setError(describeError(result.error));
+    } else {
+      setDevices(devices =>
+        devices.map(d => (d.id === id ? { ...d, isOn: !d.isOn } : d))
       );
     }
   }
@@ -34,6 +70,18 @@ export default function App() {
   const [error, setError] = useState<string | null>(null);
   const [loading, setLoading] = useState(true);
   const [devices, setDevices] = useState<Device[]>([]);
+  /** Latest power draw in watts for devices with an energy meter */
+  const [power, setPower] = useState<Record<string, number>>({});
+  /** What each registered device was found to support, by device id */
+  const [capabilities, setCapabilities] = useState<
+    Record<string, DeviceCapabilities>
+  >({});
+
+  /** Only show a control when probing found the module, or before probing */
+  const supports = (device: Device, feature: keyof DeviceCapabilities) => {
+    const caps = capabilities[device.parentId ?? device.id];
+    return !caps || caps[feature];
+  };
 
   const discoverDevices = async () => {
     try {
@@ -42,13 +90,19 @@ export default function App() {
       if (result.status === 'ok') {
         setDevices(ds =>
           Object.values({
-            ...Object.fromEntries(ds.map(d => [d.addr, d])), // known devices
-            ...Object.fromEntries(result.data.map(d => [d.addr, d])), // discovered/updated devices
+            ...Object.fromEntries(ds.map(d => [d.id, d])), // known devices
+            ...Object.fromEntries(result.data.map(d => [d.id, d])), // discovered/updated devices
           })
         );
       } else {
-        setError(JSON.stringify(result.error, null, 2));
+        setError(describeError(result.error));
       }
+      const entries = await commands.registeredDevices();
+      setCapabilities(
+        Object.fromEntries(
+          entries.flatMap(e => (e.capabilities ? [[e.id, e.capabilities]] : []))
+        )
+      );
     } catch (err) {
       setError(err instanceof Error ? err.message : 'Failed to fetch devices');
     } finally {
@@ -76,9 +130,28 @@ export default function App() {
       discoverDevices();
     }, 30000);
 
+    /** Pick up changes pushed by the backend poller */
+    const upsert = (device: Device) =>
+      setDevices(ds =>
+        ds.some(d => d.id === device.id)
+          ? ds.map(d => (d.id === device.id ? device : d))
+          : [...ds, device]
+      );
+    const unlistenEvents = [
+      events.deviceDiscovered.listen(({ payload }) => upsert(payload.device)),
+      events.deviceUpdated.listen(({ payload }) => upsert(payload.device)),
+      events.deviceWentOffline.listen(({ payload }) =>
+        setDevices(ds => ds.filter(d => d.id !== payload.id))
+      ),
+      events.energyReading.listen(({ payload }) =>
+        setPower(p => ({ ...p, [payload.id]: payload.realtime.power }))
+      ),
+    ];
+
     return () => {
       clearInterval(interval);
       unlisten.then(fn => fn());
+      unlistenEvents.forEach(unlisten => unlisten.then(fn => fn()));
     };
   }, []);
 
@@ -105,7 +178,7 @@ export default function App() {
       </h1>
 
       {error && (
-        <pre className="text-red-500">{JSON.stringify(error, null, 2)}</pre>
+        <pre className="text-red-500 whitespace-pre-wrap">{error}</pre>
       )}
 
       {devices.length === 0 && (
@@ -119,27 +192,35 @@ export default function App() {
 
       {devices.map(device => (
         <div
-          key={device.addr}
+          key={device.id}
           className={cx(
             'flex flex-col gap-4 w-full p-4 rounded-xl transition-backgroun select-noned',
             !device.isOn ? 'bg-alpha-50' : 'bg-alpha-300'
           )}
-          onDoubleClick={() => toggle(device.addr)}
+          onDoubleClick={() => toggle(device.id)}
         >
           <div className="flex flex-row justify-between items-center w-full">
-            <h4 className="font-bold font-lg m-0 select-none">{device.name}</h4>
+            <h4 className="font-bold font-lg m-0 select-none">
+              {device.name}
+              {typeof power[device.id] === 'number' && (
+                <span className="font-normal text-sm opacity-60 ml-2">
+                  {power[device.id].toFixed(1)} W
+                </span>
+              )}
+            </h4>
             <Button
               isIconOnly
               aria-label="Toggle power"
               className=" px-2 py-1 text-sm  font-bold rounded-xl"
               color="primary"
-              onPress={() => toggle(device.addr)}
+              onPress={() => toggle(device.id)}
               variant={device.isOn ? 'solid' : 'faded'}
             >
               <AiOutlinePoweroff size={48} />
             </Button>
           </div>
-          {typeof device.brightness === 'number' && (
+          {typeof device.brightness === 'number' &&
+            (supports(device, 'dimmer') || supports(device, 'lighting')) && (
             <Slider
               aria-label="Brightness"
               className={cx(!device.isOn && '!opacity-10')}
@@ -148,7 +229,39 @@ export default function App() {
               isDisabled={!device.isOn}
               maxValue={100}
               minValue={0}
-              onChangeEnd={e => setBrightness(device.addr, +e)}
+              onChangeEnd={e => setBrightness(device.id, +e)}
+            />
+          )}
+          {device.preferredState.length > 0 && (
+            <div className="flex flex-row gap-2">
+              {device.preferredState.map(preset => (
+                <Button
+                  key={preset.index}
+                  aria-label={`Preset ${preset.index + 1}`}
+                  className="rounded-xl"
+                  onPress={() => applyPreset(device.id, preset.index)}
+                  size="sm"
+                  variant="faded"
+                >
+                  {preset.brightness}%
+                </Button>
+              ))}
+            </div>
+          )}
+          {device.isColor && supports(device, 'lighting') && (
+            <Slider
+              aria-label="Hue"
+              className={cx(!device.isOn && '!opacity-10')}
+              classNames={{
+                track:
+                  '!border-r-[transparent] bg-gradient-to-r from-red-500 via-green-500 to-red-500',
+                filler: 'bg-transparent',
+              }}
+              defaultValue={device.lightState?.hue ?? 0}
+              isDisabled={!device.isOn}
+              maxValue={360}
+              minValue={0}
+              onChangeEnd={e => setHue(device.id, +e)}
             />
           )}
         </div>
@@ -12,56 +12,1894 @@ export const commands = {
       else return { status: 'error', error: e as any };
     }
   },
+  async getDiscoveryConfig(): Promise<Result<DiscoveryConfig, AppError>> {
+    try {
+      return { status: 'ok', data: await TAURI_INVOKE('get_discovery_config') };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async setDiscoveryConfig(
+    config: DiscoveryConfig
+  ): Promise<Result<null, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('set_discovery_config', { config }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async registeredDevices(): Promise<RegistryEntry[]> {
+    return await TAURI_INVOKE('registered_devices');
+  },
+  async forgetDevice(id: string): Promise<Result<null, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('forget_device', { id }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async toggle(id: string): Promise<Result<boolean, AppError>> {
+    try {
+      return { status: 'ok', data: await TAURI_INVOKE('toggle', { id }) };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  /**
+   * Set the brightness of a dimmer or a bulb
+   */
   async setBrightness(
-    socketAddr: string,
+    id: string,
     brightness: number
   ): Promise<Result<null, AppError>> {
     try {
       return {
         status: 'ok',
-        data: await TAURI_INVOKE('set_brightness', { socketAddr, brightness }),
+        data: await TAURI_INVOKE('set_brightness', { id, brightness }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  /**
+   * Read everything about a device in one round trip
+   */
+  async getDeviceSnapshot(
+    id: string
+  ): Promise<Result<DeviceSnapshot, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('get_device_snapshot', { id }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  /**
+   * Probe which modules a device has and remember the result. An outlet is
+   * probed through its power strip.
+   */
+  async probeDevice(id: string): Promise<Result<DeviceCapabilities, AppError>> {
+    try {
+      return { status: 'ok', data: await TAURI_INVOKE('probe_device', { id }) };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async setSwitchState(
+    id: string,
+    on: boolean
+  ): Promise<Result<null, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('set_switch_state', { id, on }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async getDimmerParameters(
+    id: string
+  ): Promise<Result<DimmerParameters, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('get_dimmer_parameters', { id }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async setDimmerParameter(
+    id: string,
+    parameter: DimmerParameter
+  ): Promise<Result<null, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('set_dimmer_parameter', { id, parameter }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async getDefaultBehavior(
+    id: string
+  ): Promise<Result<DefaultBehavior, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('get_default_behavior', { id }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async setButtonAction(
+    id: string,
+    button: DimmerButton,
+    action: BehaviorAction
+  ): Promise<Result<null, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('set_button_action', { id, button, action }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async setDimmerTransition(
+    id: string,
+    brightness: number,
+    mode: TransitionMode,
+    durationMs: number
+  ): Promise<Result<null, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('set_dimmer_transition', {
+          id,
+          brightness,
+          mode,
+          durationMs,
+        }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async getMotionConfig(id: string): Promise<Result<MotionConfig, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('get_motion_config', { id }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async setMotionSetting(
+    id: string,
+    setting: MotionSetting
+  ): Promise<Result<null, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('set_motion_setting', { id, setting }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async getAmbientLightConfig(
+    id: string
+  ): Promise<Result<AmbientLightConfig, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('get_ambient_light_config', { id }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  /**
+   * How bright the room is now, in percent
+   */
+  async getAmbientLight(id: string): Promise<Result<number, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('get_ambient_light', { id }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async setAmbientLightSetting(
+    id: string,
+    setting: AmbientLightSetting
+  ): Promise<Result<null, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('set_ambient_light_setting', { id, setting }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async getLightState(id: string): Promise<Result<LightState, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('get_light_state', { id }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async transitionLightState(
+    id: string,
+    transition: LightTransition
+  ): Promise<Result<LightState, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('transition_light_state', { id, transition }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async getLightDetails(id: string): Promise<Result<LightDetails, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('get_light_details', { id }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async getLightDefaultBehavior(
+    id: string
+  ): Promise<Result<DefaultBehavior, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('get_light_default_behavior', { id }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async getEmeterRealtime(
+    id: string
+  ): Promise<Result<EmeterRealtime, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('get_emeter_realtime', { id }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async getEmeterDailyStats(
+    id: string,
+    year: number,
+    month: number
+  ): Promise<Result<EmeterStat[], AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('get_emeter_daily_stats', { id, year, month }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async getEmeterMonthlyStats(
+    id: string,
+    year: number
+  ): Promise<Result<EmeterStat[], AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('get_emeter_monthly_stats', { id, year }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async eraseEmeterStats(id: string): Promise<Result<null, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('erase_emeter_stats', { id }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async getScheduleRules(id: string): Promise<Result<ScheduleRules, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('get_schedule_rules', { id }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  /**
+   * Add a rule and return the id the device gave it
+   */
+  async addScheduleRule(
+    id: string,
+    rule: ScheduleRule
+  ): Promise<Result<string, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('add_schedule_rule', { id, rule }),
       };
     } catch (e) {
       if (e instanceof Error) throw e;
       else return { status: 'error', error: e as any };
     }
   },
-  async toggle(socketAddr: string): Promise<Result<boolean, AppError>> {
+  async editScheduleRule(
+    id: string,
+    rule: ScheduleRule
+  ): Promise<Result<null, AppError>> {
     try {
       return {
         status: 'ok',
-        data: await TAURI_INVOKE('toggle', { socketAddr }),
+        data: await TAURI_INVOKE('edit_schedule_rule', { id, rule }),
       };
     } catch (e) {
       if (e instanceof Error) throw e;
       else return { status: 'error', error: e as any };
     }
   },
+  async deleteScheduleRule(
+    id: string,
+    ruleId: string
+  ): Promise<Result<null, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('delete_schedule_rule', { id, ruleId }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async deleteAllScheduleRules(id: string): Promise<Result<null, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('delete_all_schedule_rules', { id }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async setScheduleRuleEnabled(
+    id: string,
+    ruleId: string,
+    enabled: boolean
+  ): Promise<Result<null, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('set_schedule_rule_enabled', {
+          id,
+          ruleId,
+          enabled,
+        }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  /**
+   * Enable or disable every rule at once, keeping the rules themselves
+   */
+  async setScheduleEnabled(
+    id: string,
+    enabled: boolean
+  ): Promise<Result<null, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('set_schedule_enabled', { id, enabled }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async getNextAction(id: string): Promise<Result<NextAction, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('get_next_action', { id }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async getCountdowns(id: string): Promise<Result<CountdownRule[], AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('get_countdowns', { id }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  /**
+   * Add a timer and return the id the device gave it
+   */
+  async addCountdown(
+    id: string,
+    rule: CountdownRule
+  ): Promise<Result<string, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('add_countdown', { id, rule }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async editCountdown(
+    id: string,
+    rule: CountdownRule
+  ): Promise<Result<null, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('edit_countdown', { id, rule }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async deleteCountdown(
+    id: string,
+    ruleId: string
+  ): Promise<Result<null, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('delete_countdown', { id, ruleId }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async deleteAllCountdowns(id: string): Promise<Result<null, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('delete_all_countdowns', { id }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  /**
+   * Switch the device on or off in `seconds`, replacing any running timer
+   */
+  async switchAfter(
+    id: string,
+    seconds: number,
+    on: boolean
+  ): Promise<Result<string, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('switch_after', { id, seconds, on }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async getAwayRules(id: string): Promise<Result<AwayModeRules, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('get_away_rules', { id }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  /**
+   * Add an away mode rule and return the id the device gave it
+   */
+  async addAwayRule(
+    id: string,
+    rule: AwayModeRule
+  ): Promise<Result<string, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('add_away_rule', { id, rule }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async editAwayRule(
+    id: string,
+    rule: AwayModeRule
+  ): Promise<Result<null, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('edit_away_rule', { id, rule }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async deleteAwayRule(
+    id: string,
+    ruleId: string
+  ): Promise<Result<null, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('delete_away_rule', { id, ruleId }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async deleteAllAwayRules(id: string): Promise<Result<null, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('delete_all_away_rules', { id }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  /**
+   * Turn away mode on or off, keeping its rules
+   */
+  async setAwayModeEnabled(
+    id: string,
+    enabled: boolean
+  ): Promise<Result<null, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('set_away_mode_enabled', { id, enabled }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async getDeviceTime(id: string): Promise<Result<DeviceTime, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('get_device_time', { id }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async getDeviceTimezone(
+    id: string
+  ): Promise<Result<DeviceTimezone, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('get_device_timezone', { id }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  /**
+   * Move a device to an IANA zone such as `Europe/Amsterdam` and set its clock
+   */
+  async setDeviceTimezone(
+    id: string,
+    zone: string
+  ): Promise<Result<null, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('set_device_timezone', { id, zone }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  /**
+   * Compare every device clock to the host's. With `fix`, the ones that are
+   * off are reset.
+   */
+  async syncDeviceClocks(fix: boolean): Promise<ClockReport[]> {
+    return await TAURI_INVOKE('sync_device_clocks', { fix });
+  },
+  /**
+   * Turn a device's status LED on or off. An outlet changes its power strip's
+   * LED, which all outlets share.
+   */
+  async setLed(id: string, on: boolean): Promise<Result<null, AppError>> {
+    try {
+      return { status: 'ok', data: await TAURI_INVOKE('set_led', { id, on }) };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  /**
+   * Turn the status LED of every device that has one on or off
+   */
+  async setAllLeds(on: boolean): Promise<DeviceOutcome[]> {
+    return await TAURI_INVOKE('set_all_leds', { on });
+  },
+  /**
+   * The presets of a dimmer or bulb
+   */
+  async getPresets(id: string): Promise<Result<PreferredState[], AppError>> {
+    try {
+      return { status: 'ok', data: await TAURI_INVOKE('get_presets', { id }) };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  /**
+   * Overwrite the preset in slot `preset.index`
+   */
+  async savePreset(
+    id: string,
+    preset: PreferredState
+  ): Promise<Result<null, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('save_preset', { id, preset }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  /**
+   * Switch a dimmer or bulb on with the preset in slot `index`
+   */
+  async applyPreset(
+    id: string,
+    index: number
+  ): Promise<Result<PreferredState, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('apply_preset', { id, index }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  /**
+   * Switch every dimmer and bulb on with its preset in slot `index`
+   */
+  async applyPresetEverywhere(index: number): Promise<DeviceOutcome[]> {
+    return await TAURI_INVOKE('apply_preset_everywhere', { index });
+  },
+  async getScenes(): Promise<Result<Scene[], AppError>> {
+    try {
+      return { status: 'ok', data: await TAURI_INVOKE('get_scenes') };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  /**
+   * Save the current state of the devices with `ids` as the scene `name`,
+   * replacing any scene with that name
+   */
+  async captureScene(
+    name: string,
+    ids: string[]
+  ): Promise<Result<Scene, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('capture_scene', { name, ids }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  /**
+   * Save an edited scene, replacing any scene with the same name
+   */
+  async saveScene(scene: Scene): Promise<Result<null, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('save_scene', { scene }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async deleteScene(name: string): Promise<Result<null, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('delete_scene', { name }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  /**
+   * Recall a scene, reporting how each of its devices went
+   */
+  async applyScene(name: string): Promise<Result<DeviceOutcome[], AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('apply_scene', { name }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async getGroups(): Promise<Result<Group[], AppError>> {
+    try {
+      return { status: 'ok', data: await TAURI_INVOKE('get_groups') };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  /**
+   * Save a group, replacing any group with the same name
+   */
+  async saveGroup(group: Group): Promise<Result<null, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('save_group', { group }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async deleteGroup(name: string): Promise<Result<null, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('delete_group', { name }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  /**
+   * How many members are on, and how bright
+   */
+  async getGroupState(name: string): Promise<Result<GroupState, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('get_group_state', { name }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  /**
+   * Switch every member on or off
+   */
+  async switchGroup(
+    name: string,
+    on: boolean
+  ): Promise<Result<DeviceOutcome[], AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('switch_group', { name, on }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  /**
+   * Switch the group off if most of it is on, otherwise on
+   */
+  async toggleGroup(name: string): Promise<Result<GroupToggled, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('toggle_group', { name }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  /**
+   * Switch every member on at `brightness`. Members that don't dim just
+   * switch on.
+   */
+  async setGroupBrightness(
+    name: string,
+    brightness: number
+  ): Promise<Result<DeviceOutcome[], AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('set_group_brightness', { name, brightness }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async getAutomations(): Promise<Result<Automation[], AppError>> {
+    try {
+      return { status: 'ok', data: await TAURI_INVOKE('get_automations') };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  /**
+   * Save an automation, replacing any automation with the same name
+   */
+  async saveAutomation(
+    automation: Automation
+  ): Promise<Result<null, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('save_automation', { automation }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async deleteAutomation(name: string): Promise<Result<null, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('delete_automation', { name }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  /**
+   * Run an automation now, regardless of its trigger
+   */
+  async runAutomation(name: string): Promise<Result<Execution, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('run_automation', { name }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  /**
+   * When an automation will next run on its own, as a Unix timestamp
+   * (seconds). `None` if it is disabled or waits for a device to be switched.
+   */
+  async getNextAutomationRun(
+    name: string
+  ): Promise<Result<number | null, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('get_next_automation_run', { name }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  /**
+   * Past automation runs, newest first
+   */
+  async getAutomationHistory(): Promise<Result<Execution[], AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('get_automation_history'),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async getMqttConfig(): Promise<Result<MqttConfig, AppError>> {
+    try {
+      return { status: 'ok', data: await TAURI_INVOKE('get_mqtt_config') };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  /**
+   * Save the MQTT bridge settings, reconnecting or stopping the bridge to match
+   */
+  async setMqttConfig(config: MqttConfig): Promise<Result<null, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('set_mqtt_config', { config }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  async getApiConfig(): Promise<Result<ApiConfig, AppError>> {
+    try {
+      return { status: 'ok', data: await TAURI_INVOKE('get_api_config') };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+  /**
+   * Save the HTTP API settings and start or stop the server to match. Returns
+   * the settings as saved, with a token if one was generated.
+   */
+  async setApiConfig(config: ApiConfig): Promise<Result<ApiConfig, AppError>> {
+    try {
+      return {
+        status: 'ok',
+        data: await TAURI_INVOKE('set_api_config', { config }),
+      };
+    } catch (e) {
+      if (e instanceof Error) throw e;
+      else return { status: 'error', error: e as any };
+    }
+  },
+};
+
+/** user-defined events **/
+
+export const events = __makeEvents__<{
+  deviceDiscovered: DeviceDiscovered;
+  deviceUpdated: DeviceUpdated;
+  deviceWentOffline: DeviceWentOffline;
+  energyReading: EnergyReading;
+}>({
+  deviceDiscovered: 'device-discovered',
+  deviceUpdated: 'device-updated',
+  deviceWentOffline: 'device-went-offline',
+  energyReading: 'energy-reading',
+});
+
+/** user-defined constants **/
+
+/** user-defined types **/
+
+/**
+ * Something an automation does
+ */
+export type Action =
+  /**
+   * Put a device or outlet in a state
+   */
+  | { type: 'device'; id: string; state: PowerState }
+  /**
+   * Put every member of a group in a state
+   */
+  | { type: 'group'; name: string; state: PowerState }
+  /**
+   * Recall a scene
+   */
+  | { type: 'scene'; name: string };
+/**
+ * Which feature last switched the relay, from sysinfo's `active_mode`
+ */
+export type ActiveMode =
+  | 'none'
+  | 'schedule'
+  | 'count_down'
+  | 'anti_theft'
+  | 'unknown';
+/**
+ * An ambient light sensor's settings, from `smartlife.iot.LAS.get_config`.
+ * Motion only switches the light on while it is darker than the level at
+ * `dark_index`.
+ */
+export type AmbientLightConfig = {
+  enabled: boolean;
+  /**
+   * Which of `levels` is the threshold
+   */
+  darkIndex: number;
+  levels: AmbientLightLevel[];
+  /**
+   * The span of raw sensor readings
+   */
+  minAdc: number;
+  maxAdc: number;
+};
+/**
+ * A named light level such as "dawn" or "twilight"
+ */
+export type AmbientLightLevel = {
+  name: string;
+  /**
+   * The raw sensor reading
+   */
+  adc: number;
+  /**
+   * In percent of the sensor's span
+   */
+  value: number;
+};
+/**
+ * A single setting of an ambient light sensor's [`AmbientLightConfig`]
+ */
+export type AmbientLightSetting =
+  | { setting: 'enabled'; value: boolean }
+  /**
+   * Brightness in percent below which motion switches the light on
+   */
+  | { setting: 'threshold'; value: number };
+/**
+ * Where the API listens and the token it expects
+ */
+export type ApiConfig = {
+  enabled: boolean;
+  /**
+   * Loopback by default, so only scripts on this host can reach it
+   */
+  address: string;
+  port: number;
+  /**
+   * Generated when the API is enabled without one
+   */
+  token: string;
+};
+export type AppError =
+  /**
+   * Tried to send a command to a device that was never discovered
+   */
+  | { NotFound: string }
+  /**
+   * TPLinker error
+   */
+  | { Tp: TpError }
+  /**
+   * Failed to read or write the persistent store
+   */
+  | { Store: string }
+  /**
+   * No scene with this name was saved
+   */
+  | { SceneNotFound: string }
+  /**
+   * No group with this name was saved
+   */
+  | { GroupNotFound: string }
+  /**
+   * No automation with this name was saved
+   */
+  | { AutomationNotFound: string }
+  /**
+   * The automation can't be saved as it is, such as for a malformed cron
+   * expression
+   */
+  | { InvalidAutomation: string };
+export type Automation = {
+  /**
+   * Unique among automations
+   */
+  name: string;
+  enabled: boolean;
+  trigger: Trigger;
+  /**
+   * Run in order
+   */
+  actions: Action[];
+};
+/**
+ * A window in which the device switches on and off at random, so the house
+ * looks occupied
+ */
+export type AwayModeRule = {
+  /**
+   * Assigned by the device when the rule is added
+   */
+  id: string | null;
+  name: string;
+  enabled: boolean;
+  /**
+   * The days the rule runs on, Sunday first
+   */
+  weekdays: [boolean, boolean, boolean, boolean, boolean, boolean, boolean];
+  /**
+   * Run every week rather than once
+   */
+  repeat: boolean;
+  start: RuleTime;
+  end: RuleTime;
+};
+/**
+ * The rules of the `anti_theft` module, and whether away mode is on at all
+ */
+export type AwayModeRules = { enabled: boolean; rules: AwayModeRule[] };
+export type BehaviorAction = {
+  mode: BehaviorMode;
+  /**
+   * The preset slot to apply when `mode` is `customize_preset`
+   */
+  index?: number | null;
+};
+export type BehaviorMode =
+  | 'none'
+  | 'last_status'
+  | 'instant_on_off'
+  | 'gentle_on_off'
+  | 'customize_preset'
+  /**
+   * A mode introduced by newer firmware
+   */
+  | 'unknown';
+/**
+ * An outlet of a power strip, from the `children` of its sysinfo
+ */
+export type ChildInfo = {
+  /**
+   * Either the full child id or just its two digit index, depending on
+   * firmware. See [`ChildInfo::full_id`].
+   */
+  id: string;
+  alias: string;
+  state: number;
+  on_time: number | null;
+  /**
+   * What the outlet's schedule will do next
+   */
+  next_action: NextAction | null;
+};
+/**
+ * How a device clock compares to the host's
+ */
+export type ClockReport = {
+  id: string;
+  name: string;
+  time: DeviceTime | null;
+  /**
+   * The IANA zone the device's timezone index stands for
+   */
+  zone: string | null;
+  /**
+   * Seconds the device is ahead of the host, negative when behind. `None`
+   * if the device reported a time that doesn't exist.
+   */
+  drift: number | null;
+  /**
+   * The device has enabled schedule rules that will fire at the wrong time
+   */
+  scheduleAffected: boolean;
+  /**
+   * The clock was reset to the host's
+   */
+  synced: boolean;
+  error: TpError | null;
+};
+/**
+ * A timer that switches the device once its delay has passed
+ */
+export type CountdownRule = {
+  /**
+   * Assigned by the device when the rule is added
+   */
+  id: string | null;
+  name: string;
+  enabled: boolean;
+  /**
+   * Seconds from when the rule is added or enabled
+   */
+  delay: number;
+  /**
+   * Switch on rather than off when the timer runs out
+   */
+  on: boolean;
+  /**
+   * Seconds left, if the timer is running
+   */
+  remaining: number | null;
+};
+/**
+ * What a dimmer does in response to each kind of input, from
+ * `smartlife.iot.dimmer.get_default_behavior`
+ */
+export type DefaultBehavior = {
+  hard_on: BehaviorAction | null;
+  soft_on: BehaviorAction | null;
+  long_press: BehaviorAction | null;
+  double_click: BehaviorAction | null;
+};
+/**
+ * A controllable entry in the frontend. Each outlet of a power strip is its
+ * own entry, with the outlet's child id as its `id`.
+ */
+export type Device = {
+  addr: string;
+  id: string;
+  /**
+   * For an outlet, the id of the power strip it belongs to
+   */
+  parentId: string | null;
+  model: string;
+  name: string;
+  brightness: number | null;
+  isOn: boolean;
+  /**
+   * Set for smart bulbs
+   */
+  lightState: LightState | null;
+  isColor: boolean;
+  /**
+   * What last switched the device, when it reports it
+   */
+  activeMode: ActiveMode | null;
+  /**
+   * What the device's schedule will do next
+   */
+  nextAction: NextAction | null;
+  /**
+   * Whether the status LED is lit. Outlets share their strip's LED.
+   */
+  ledOn: boolean;
+  /**
+   * Brightness or light presets
+   */
+  preferredState: PreferredState[];
+};
+/**
+ * Which optional modules a device answers, found by probing it rather than
+ * by its model name
+ */
+export type DeviceCapabilities = {
+  /**
+   * `smartlife.iot.dimmer`
+   */
+  dimmer: boolean;
+  /**
+   * `emeter` or `smartlife.iot.common.emeter`, on the device itself or on
+   * its outlets
+   */
+  emeter: boolean;
+  /**
+   * `smartlife.iot.smartbulb.lightingservice`
+   */
+  lighting: boolean;
+  /**
+   * `schedule` or `smartlife.iot.common.schedule`
+   */
+  schedule: boolean;
+  /**
+   * `count_down`
+   */
+  countdown: boolean;
+  /**
+   * `time` or `smartlife.iot.common.timesetting`
+   */
+  timesetting: boolean;
+  /**
+   * `cnCloud` or `smartlife.iot.common.cloud`
+   */
+  cloud: boolean;
+  /**
+   * The device is a power strip with individually switched outlets
+   */
+  outlets: boolean;
+  /**
+   * `smartlife.iot.PIR`, a motion sensor. Absent from capabilities probed
+   * by older versions.
+   */
+  motion?: boolean;
+};
+/**
+ * Discovery found a device that wasn't in the registry
+ */
+export type DeviceDiscovered = { device: Device };
+/**
+ * What happened to one device when changing several at once
+ */
+export type DeviceOutcome = {
+  id: string;
+  name: string;
+  /**
+   * `None` if the change succeeded
+   */
+  error: AppError | null;
+};
+/**
+ * Everything worth knowing about a device, read in one round trip. Modules
+ * the device doesn't have are `None`.
+ */
+export type DeviceSnapshot = {
+  sysinfo: SysInfo;
+  realtime: EmeterRealtime | null;
+  light_state: LightState | null;
+  dimmer_parameters: DimmerParameters | null;
+};
+/**
+ * A device's wall clock, from `get_time`. It is in the device's own
+ * timezone and carries no offset.
+ */
+export type DeviceTime = {
+  year: number;
+  month: number;
+  mday: number;
+  hour: number;
+  min: number;
+  sec: number;
+};
+/**
+ * A device's timezone, from `get_timezone`
+ */
+export type DeviceTimezone = {
+  /**
+   * See [`timezones`](super::timezones)
+   */
+  index: number;
+  /**
+   * The name the Kasa app shows, on newer firmware
+   */
+  zone_str: string | null;
+  /**
+   * POSIX TZ string, on newer firmware
+   */
+  tz_str: string | null;
+  /**
+   * Minutes added during daylight saving time
+   */
+  dst_offset: number | null;
+};
+/**
+ * A known device answered and its state differs from what we last saw
+ */
+export type DeviceUpdated = { device: Device };
+/**
+ * A device that was answering has stopped responding
+ */
+export type DeviceWentOffline = { id: string; addr: string };
+/**
+ * The buttons on a dimmer whose action can be reassigned
+ */
+export type DimmerButton = 'long_press' | 'double_click';
+/**
+ * A single tunable field of [`DimmerParameters`]. Times are in milliseconds.
+ */
+export type DimmerParameter =
+  | { parameter: 'fadeOnTime'; value: number }
+  | { parameter: 'fadeOffTime'; value: number }
+  | { parameter: 'gentleOnTime'; value: number }
+  | { parameter: 'gentleOffTime'; value: number }
+  | { parameter: 'rampRate'; value: number }
+  | { parameter: 'minThreshold'; value: number };
+/**
+ * Dimmer tuning from `smartlife.iot.dimmer.get_dimmer_parameters`.
+ * All times are in milliseconds.
+ */
+export type DimmerParameters = {
+  /**
+   * The lowest brightness the connected load can be driven at
+   */
+  minThreshold: number;
+  /**
+   * Fade time when switched on from the app
+   */
+  fadeOnTime: number;
+  /**
+   * Fade time when switched off from the app
+   */
+  fadeOffTime: number;
+  /**
+   * Fade time when switched on with a press of the switch
+   */
+  gentleOnTime: number;
+  /**
+   * Fade time when switched off with a press of the switch
+   */
+  gentleOffTime: number;
+  /**
+   * How quickly brightness changes while the switch is held
+   */
+  rampRate: number;
+  bulb_type: number;
+  calibration_type: number | null;
+};
+/**
+ * Where discovery packets get sent
+ */
+export type DiscoveryConfig = {
+  /**
+   * Send to the directed broadcast address of every local IPv4 interface
+   */
+  scanInterfaces: boolean;
+  /**
+   * Send to the limited broadcast address 255.255.255.255
+   */
+  globalBroadcast: boolean;
+  /**
+   * Extra addresses to probe, either single hosts (`10.0.4.20`) or CIDR
+   * ranges (`192.168.50.0/24`), which are swept host by host
+   */
+  targets: string[];
 };
-
-/** user-defined events **/
-
-/** user-defined constants **/
-
-/** user-defined types **/
-
-export type AppError =
+/**
+ * A reading from `emeter.get_realtime`, in volts, amps, watts and
+ * kilowatt-hours regardless of which units the firmware reports
+ */
+export type EmeterRealtime = {
+  voltage: number;
+  current: number;
+  power: number;
   /**
-   * Tried to send a command to a device that was never discovered
+   * Energy used since the stats were last erased
    */
-  | { NotFound: string }
+  total: number;
+};
+/**
+ * Energy used over a day or a month, from `emeter.get_daystat` and
+ * `emeter.get_monthstat`
+ */
+export type EmeterStat = {
+  year: number;
+  month: number;
   /**
-   * TPLinker error
+   * Only set for daily stats
+   */
+  day: number | null;
+  /**
+   * In kilowatt-hours
+   */
+  energy: number;
+};
+/**
+ * A metered device reported its power draw. Sent on every poll so the
+ * frontend can chart consumption.
+ */
+export type EnergyReading = {
+  id: string;
+  realtime: EmeterRealtime;
+  /**
+   * Seconds since the Unix epoch
+   */
+  timestamp: number;
+};
+/**
+ * A record of an automation having run
+ */
+export type Execution = {
+  automation: string;
+  /**
+   * Unix timestamp (seconds)
+   */
+  at: number;
+  /**
+   * What triggered it, for display
+   */
+  reason: string;
+  outcomes: DeviceOutcome[];
+  /**
+   * Set if an action couldn't be started at all, such as a deleted scene
+   */
+  error: AppError | null;
+};
+/**
+ * A named set of devices
+ */
+export type Group = {
+  /**
+   * Unique among groups
+   */
+  name: string;
+  /**
+   * Device ids, or outlets' full ids
+   */
+  members: string[];
+};
+/**
+ * How a group looks as a whole, for display
+ */
+export type GroupState = {
+  name: string;
+  /**
+   * Members that answered
+   */
+  reachable: number;
+  /**
+   * Reachable members that are on
+   */
+  on: number;
+  /**
+   * Average brightness of the members that are on and dim, if any
+   */
+  brightness: number | null;
+};
+/**
+ * The result of toggling a group
+ */
+export type GroupToggled = {
+  /**
+   * Whether the group was switched on rather than off
+   */
+  on: boolean;
+  outcomes: DeviceOutcome[];
+};
+/**
+ * The hardware details of a bulb, from
+ * `smartlife.iot.smartbulb.lightingservice.get_light_details`
+ */
+export type LightDetails = {
+  lamp_beam_angle: number;
+  min_voltage: number;
+  max_voltage: number;
+  wattage: number;
+  incandescent_equivalent: number;
+  max_lumens: number;
+  color_rendering_index: number;
+};
+/**
+ * A color and brightness a bulb can be in. Color temperature takes
+ * precedence over hue and saturation when it is non-zero.
+ */
+export type LightPreset = {
+  mode?: string | null;
+  hue?: number | null;
+  saturation?: number | null;
+  /**
+   * In kelvin
+   */
+  color_temp?: number | null;
+  brightness?: number | null;
+};
+/**
+ * The state of a smart bulb's light, from
+ * `smartlife.iot.smartbulb.lightingservice.get_light_state`.
+ *
+ * While the bulb is off only `on_off` and `dft_on_state` are reported.
+ */
+export type LightState = {
+  on_off: number;
+  mode?: string | null;
+  hue?: number | null;
+  saturation?: number | null;
+  /**
+   * In kelvin
+   */
+  color_temp?: number | null;
+  brightness?: number | null;
+  /**
+   * The state the bulb returns to when it is switched back on
+   */
+  dft_on_state?: LightPreset | null;
+};
+/**
+ * A change to a bulb's light state. Fields left as `None` are unchanged.
+ */
+export type LightTransition = {
+  on: boolean | null;
+  /**
+   * 0-360
+   */
+  hue: number | null;
+  /**
+   * 0-100
+   */
+  saturation: number | null;
+  /**
+   * In kelvin, or 0 to switch to hue and saturation
+   */
+  colorTemp: number | null;
+  /**
+   * 0-100
+   */
+  brightness: number | null;
+  /**
+   * How long the change should take, in milliseconds
+   */
+  transitionPeriod: number | null;
+};
+/**
+ * A motion sensor's settings, from `smartlife.iot.PIR.get_config`
+ */
+export type MotionConfig = {
+  enabled: boolean;
+  /**
+   * Which of `thresholds` is in use
+   */
+  range: MotionRange;
+  /**
+   * How long after the last motion the light stays on, in milliseconds
+   */
+  coldTime: number;
+  /**
+   * The trigger threshold for each range, far to near then custom, as a
+   * percentage of the sensor's span
+   */
+  thresholds: number[];
+  /**
+   * The span of raw sensor readings
+   */
+  minAdc: number;
+  maxAdc: number;
+};
+/**
+ * How far away motion is noticed. Nearer ranges need more motion.
+ */
+export type MotionRange =
+  | 'far'
+  | 'mid'
+  | 'near'
+  /**
+   * The threshold set with `set_trigger_sens`
+   */
+  | 'custom';
+/**
+ * A single setting of a motion sensor's [`MotionConfig`]
+ */
+export type MotionSetting =
+  | { setting: 'enabled'; value: boolean }
+  | { setting: 'range'; value: MotionRange }
+  /**
+   * Custom threshold in percent, which also selects the custom range
+   */
+  | { setting: 'threshold'; value: number }
+  /**
+   * In milliseconds
+   */
+  | { setting: 'coldTime'; value: number };
+/**
+ * Where to find the broker and which topics to use
+ */
+export type MqttConfig = {
+  enabled: boolean;
+  host: string;
+  port: number;
+  username: string | null;
+  password: string | null;
+  /**
+   * Home Assistant's discovery prefix
+   */
+  discoveryPrefix: string;
+  /**
+   * Prefix of the state, command and availability topics
+   */
+  baseTopic: string;
+};
+/**
+ * A [`NextAction`] in the form the device sends it, which is also how the
+ * frontend sees it
+ */
+export type NextAction = {
+  /**
+   * -1 when nothing is scheduled, 1 for a schedule rule
+   */
+  type: number;
+  id?: string | null;
+  /**
+   * Seconds after midnight, device local time
+   */
+  schd_sec?: number | null;
+  /**
+   * 1 to switch on, 0 to switch off
+   */
+  action?: number | null;
+};
+/**
+ * Whether a device or outlet is on and, for dimmers and bulbs, how bright
+ */
+export type PowerState = {
+  on: boolean;
+  /**
+   * 1-100. Ignored by devices that don't dim.
+   */
+  brightness: number | null;
+};
+/**
+ * A brightness preset of a dimmer, or a light preset of a bulb
+ */
+export type PreferredState = {
+  index: number;
+  brightness: number;
+  hue?: number | null;
+  saturation?: number | null;
+  /**
+   * In kelvin
+   */
+  color_temp?: number | null;
+};
+/**
+ * Everything we remember about a device between launches
+ */
+export type RegistryEntry = {
+  /**
+   * The device's `deviceId`, which survives address changes
    */
-  | { Tp: TpError };
-export type Device = {
-  addr: string;
   id: string;
+  mac: string;
+  /**
+   * Where the device was last seen. May be stale if DHCP has moved it.
+   */
+  addr: string;
   model: string;
+  alias: string;
+  hwVer: string;
+  swVer: string;
+  /**
+   * Unix timestamp (seconds) of the last successful contact
+   */
+  lastSeen: number;
+  /**
+   * What the device was found to support when it was probed, `None`
+   * until then
+   */
+  capabilities?: DeviceCapabilities | null;
+};
+/**
+ * When a rule fires, in device local time
+ */
+export type RuleTime =
+  /**
+   * Minutes after midnight
+   */
+  | { type: 'at'; minute: number }
+  /**
+   * Minutes after sunrise, negative for before
+   */
+  | { type: 'sunrise'; offset: number }
+  /**
+   * Minutes after sunset, negative for before
+   */
+  | { type: 'sunset'; offset: number };
+/**
+ * A named state for a set of devices, such as "Movie night"
+ */
+export type Scene = {
+  /**
+   * Unique among scenes
+   */
+  name: string;
+  devices: SceneDevice[];
+};
+/**
+ * One device's part in a [`Scene`]
+ */
+export type SceneDevice = {
+  /**
+   * A device id, or an outlet's full id
+   */
+  id: string;
+  /**
+   * The device's name when the scene was saved
+   */
+  name: string;
+  state: PowerState;
+};
+/**
+ * A rule that switches the device on or off at a time of day
+ */
+export type ScheduleRule = {
+  /**
+   * Assigned by the device when the rule is added
+   */
+  id: string | null;
   name: string;
+  enabled: boolean;
+  /**
+   * The days the rule runs on, Sunday first
+   */
+  weekdays: [boolean, boolean, boolean, boolean, boolean, boolean, boolean];
+  /**
+   * Run every week rather than once
+   */
+  repeat: boolean;
+  time: RuleTime;
+  /**
+   * Switch on rather than off
+   */
+  on: boolean;
+  /**
+   * For dimmers and bulbs, the brightness to switch on at
+   */
   brightness: number | null;
-  isOn: boolean;
 };
+/**
+ * The rules of the `schedule` module, and whether they run at all
+ */
+export type ScheduleRules = { enabled: boolean; rules: ScheduleRule[] };
 /**
  * Error response for a section of the JSON response
  */
@@ -75,22 +1913,154 @@ export type SectionError = {
    */
   err_msg: string | null;
 };
+export type SolarEvent = 'sunrise' | 'sunset';
+/**
+ * Everything a device reports about itself in `system.get_sysinfo`.
+ *
+ * Fields differ between device types and firmware. Anything not modelled
+ * here is kept in `other`, so a `SysInfo` serializes back to what the
+ * device sent.
+ */
+export type SysInfo = {
+  /**
+   * What is currently driving the relay
+   */
+  active_mode: ActiveMode | null;
+  alias: string;
+  brightness: number | null;
+  /**
+   * The outlets of a power strip
+   */
+  children: ChildInfo[] | null;
+  /**
+   * The product name, e.g. "Smart Wi-Fi Dimmer"
+   */
+  dev_name: string | null;
+  deviceId: string;
+  err_code: number;
+  /**
+   * Colon-separated feature flags, e.g. `TIM:ENE` for a device with
+   * timers and an energy meter
+   */
+  feature: string | null;
+  hwId: string;
+  hw_type: string;
+  hw_ver: string;
+  icon_hash: string | null;
+  /**
+   * Set on bulbs that can change hue and saturation
+   */
+  is_color: number | null;
+  /**
+   * In degrees. Sent as `latitude_i` in ten-thousandths of a degree, or as
+   * `latitude` in degrees by older firmware.
+   */
+  latitude_i?: number | null;
+  /**
+   * Whether the status LED is turned off
+   */
+  led_off?: boolean | null;
+  /**
+   * Present on smart bulbs, which have no relay
+   */
+  light_state: LightState | null;
+  /**
+   * In degrees, see `latitude`
+   */
+  longitude_i?: number | null;
+  mac: string;
+  model: string;
+  /**
+   * What the device's schedule will do next
+   */
+  next_action: NextAction | null;
+  /**
+   * Thermistor state on switches that have one
+   */
+  ntc_state: number | null;
+  /**
+   * Where the device was set up from, e.g. `tplink`
+   */
+  obd_src: string | null;
+  oemId: string | null;
+  on_time: number | null;
+  /**
+   * Brightness presets of a dimmer, selectable from the Kasa app
+   */
+  preferred_state: PreferredState[] | null;
+  relay_state: number | null;
+  rssi: number;
+  /**
+   * `new` until the device has been set up with the Kasa app
+   */
+  status: string | null;
+  sw_ver: string;
+  updating: number | null;
+};
 /**
  * Error type for TPLinker
+ *
+ * Errors that come from a device carry its address once it is known. The
+ * transport fills it in; errors decoded from a response can have it added
+ * with [`TpError::at`].
  */
 export type TpError =
   /**
-   * Wrapped errors from std::io
+   * The device didn't answer within the timeout
+   */
+  | { Timeout: { addr: string | null; message: string } }
+  /**
+   * Nothing is listening at the address
+   */
+  | { ConnectionRefused: { addr: string | null } }
+  /**
+   * Any other error from std::io while talking to the device
    */
-  | { IO: string }
+  | { IO: { addr: string | null; message: string } }
+  /**
+   * The response wasn't a well-formed length-prefixed frame
+   */
+  | { MalformedFrame: { addr: string | null; message: string } }
   /**
    * Wrapped errors from serde_json
    */
   | { Serde: string }
   /**
-   * Error decoding a section of the JSON response
+   * The device doesn't have the module (`err_code` -1)
+   */
+  | { ModuleNotSupported: { addr: string | null; module: string } }
+  /**
+   * The module doesn't have the method (`err_code` -2)
+   */
+  | {
+      MethodNotSupported: {
+        addr: string | null;
+        module: string;
+        method: string;
+      };
+    }
+  /**
+   * The device rejected the parameters (`err_code` -3)
    */
-  | { TPLink: SectionError }
+  | {
+      InvalidArgument: {
+        addr: string | null;
+        module: string;
+        method: string;
+        message: string | null;
+      };
+    }
+  /**
+   * Any other non-zero `err_code` in a section of the response
+   */
+  | {
+      TPLink: {
+        addr: string | null;
+        module: string;
+        method: string | null;
+        error: SectionError;
+      };
+    }
   /**
    * Unknown device model
    */
@@ -103,6 +2073,28 @@ export type TpError =
    * A generic error
    */
   | { Unknown: string };
+/**
+ * How `set_dimmer_transition` moves to the new brightness
+ */
+export type TransitionMode = 'gentle_on_off' | 'instant_on_off';
+/**
+ * What makes an automation run
+ */
+export type Trigger =
+  /**
+   * A cron expression in local time, either the usual five fields or six
+   * with seconds first
+   */
+  | { type: 'cron'; expression: string }
+  /**
+   * Minutes after sunrise or sunset, or before if negative, at the location
+   * the devices report
+   */
+  | { type: 'solar'; event: SolarEvent; offset: number }
+  /**
+   * A device or outlet was switched on or off, by anything
+   */
+  | { type: 'switched'; id: string; on: boolean };
 
 /** tauri-specta globals **/
 
@@ -0,0 +1,42 @@
+import { AppError, TpError } from './bindings';
+
+/** A short, human readable description of a device error */
+export function describeTpError(error: TpError): string {
+  if ('Timeout' in error) {
+    return `${error.Timeout.addr ?? 'The device'} didn't answer in time`;
+  }
+  if ('ConnectionRefused' in error) {
+    return `${error.ConnectionRefused.addr ?? 'The device'} refused the connection`;
+  }
+  if ('IO' in error) return error.IO.message;
+  if ('MalformedFrame' in error) return 'The device sent a malformed response';
+  if ('ModuleNotSupported' in error) {
+    return `This device doesn't support ${error.ModuleNotSupported.module}`;
+  }
+  if ('MethodNotSupported' in error) {
+    return `This device doesn't support ${error.MethodNotSupported.method}`;
+  }
+  if ('InvalidArgument' in error) {
+    return `The device rejected the request to ${error.InvalidArgument.method}`;
+  }
+  if ('TPLink' in error) {
+    return `The device reported error ${error.TPLink.error.err_code}`;
+  }
+  if ('UnknownModel' in error) return `Unknown model ${error.UnknownModel}`;
+  if ('Unsupported' in error) return `Unsupported: ${error.Unsupported}`;
+  if ('Serde' in error) return "Couldn't parse the device's response";
+  return error.Unknown;
+}
+
+/** A short, human readable description of a command error */
+export function describeError(error: AppError): string {
+  if ('NotFound' in error) return `Unknown device ${error.NotFound}`;
+  if ('Store' in error) return `Couldn't save settings: ${error.Store}`;
+  if ('SceneNotFound' in error) return `Unknown scene ${error.SceneNotFound}`;
+  if ('GroupNotFound' in error) return `Unknown group ${error.GroupNotFound}`;
+  if ('AutomationNotFound' in error) {
+    return `Unknown automation ${error.AutomationNotFound}`;
+  }
+  if ('InvalidAutomation' in error) return error.InvalidAutomation;
+  return describeTpError(error.Tp);
+}